@@ -0,0 +1,50 @@
+/* This module writes typed data files in the binary formats of `alpacker::data::serialized`,
+so values serialized at build time load back with the matching wrapper asset (e.g. `Bincode<T>`). */
+
+use std::io;
+
+#[cfg(feature = "bincode")]
+use alpacker::data::serialized::BincodeFormat;
+use alpacker::data::serialized::Format;
+#[cfg(feature = "msgpack")]
+use alpacker::data::serialized::MsgPackFormat;
+#[cfg(feature = "postcard")]
+use alpacker::data::serialized::PostcardFormat;
+use serde::Serialize;
+
+/// A [Format] that can also serialize values.
+pub trait WriteFormat: Format {
+    /// Serializes a value into the contents of a file.
+    ///
+    /// # Returns
+    /// * `Ok(Vec<u8>)` with the serialized value.
+    /// * `Err(io::Error)` with kind `InvalidInput` if the value can't be represented in the format.
+    fn to_vec<T: Serialize + ?Sized>(value: &T) -> io::Result<Vec<u8>>;
+}
+
+#[cfg(any(feature = "postcard", feature = "bincode", feature = "msgpack"))]
+fn invalid_input(err: impl std::error::Error + Send + Sync + 'static) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, err)
+}
+
+#[cfg(feature = "postcard")]
+impl WriteFormat for PostcardFormat {
+    fn to_vec<T: Serialize + ?Sized>(value: &T) -> io::Result<Vec<u8>> {
+        postcard::to_allocvec(value).map_err(invalid_input)
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl WriteFormat for BincodeFormat {
+    fn to_vec<T: Serialize + ?Sized>(value: &T) -> io::Result<Vec<u8>> {
+        bincode::serde::encode_to_vec(value, Self::config()).map_err(invalid_input)
+    }
+}
+
+#[cfg(feature = "msgpack")]
+impl WriteFormat for MsgPackFormat {
+    fn to_vec<T: Serialize + ?Sized>(value: &T) -> io::Result<Vec<u8>> {
+        // Structs are written as maps, so fields can be added without breaking old readers.
+        rmp_serde::to_vec_named(value).map_err(invalid_input)
+    }
+}
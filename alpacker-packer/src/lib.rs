@@ -1,9 +1,22 @@
+pub mod data;
 pub mod pack;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod transform;
 
+use data::WriteFormat;
+use pack::codec::Encode;
+
 #[allow(unused)]
 pub use alpacker::pack::*;
+#[cfg(feature = "sign")]
+use alpacker::sign::{Hashed, SIGNATURE_FILE, SignatureError, SigningKey};
 use alpacker::{Assets, JsonIoError, MANIFEST_FILE, PackManifest, PackMeta};
+#[cfg(feature = "checksum")]
+use alpacker::{checksum::checksum, path::VirtualPath};
+use serde::Serialize;
+#[cfg(any(feature = "sign", feature = "checksum"))]
+use std::collections::BTreeMap;
 use std::{
     borrow::Cow,
     collections::HashMap,
@@ -90,6 +103,10 @@ impl PackBuilder {
     }
 
     #[inline]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(transform = std::any::type_name::<T>()))
+    )]
     pub fn transform<T: Transform>(self, transformer: &mut T) -> Result<Self, T::Error> {
         transformer.transform(&self.work_dir)?;
         Ok(self)
@@ -97,14 +114,49 @@ impl PackBuilder {
 
     /// Copies files from the source directory into the package directory.
     #[inline]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(src = ?src.as_ref()), err)
+    )]
     pub fn copy_from(self, src: impl AsRef<Path>) -> io::Result<Self> {
         copy_dir_all(src, &self.work_dir)?;
         Ok(self)
     }
 
     #[inline]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(extension = %P::extension()), err)
+    )]
     pub fn write_pack<P: MakePack>(&self, write: impl io::Write) -> io::Result<()> {
-        let manifest = PackManifest {
+        P::make(&self.work_dir, write, self.manifest()?)
+    }
+
+    /// Writes the pack, encoding it with the given codec instance.
+    ///
+    /// This is needed for codecs that carry state, such as encryption keys.
+    #[inline]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "write_pack",
+            skip_all,
+            fields(extension = %P::extension(), codec = %C::extension()),
+            err
+        )
+    )]
+    pub fn write_encoded_pack<P: MakePack, C: Encode>(
+        &self,
+        codec: &C,
+        write: impl io::Write,
+    ) -> io::Result<()> {
+        let mut write = codec.encode(write)?;
+        P::make(&self.work_dir, &mut write, self.manifest()?)?;
+        C::finish(write)
+    }
+
+    fn manifest(&self) -> io::Result<PackManifest> {
+        Ok(PackManifest {
             entry_count: WalkDir::new(&self.work_dir).into_iter().flatten().count(),
             file_count: WalkDir::new(&self.work_dir)
                 .into_iter()
@@ -112,9 +164,31 @@ impl PackBuilder {
                 .filter_map(|entry| entry.metadata().ok())
                 .filter(|meta| meta.is_file())
                 .count(),
-        };
+            #[cfg(feature = "checksum")]
+            checksums: self.checksums()?,
+            ..Default::default()
+        })
+    }
 
-        P::make(&self.work_dir, write, manifest)
+    /// Computes the checksum of every file, keyed by its path relative to the work directory.
+    #[cfg(feature = "checksum")]
+    fn checksums(&self) -> io::Result<BTreeMap<String, u64>> {
+        let mut checksums = BTreeMap::new();
+        for entry in WalkDir::new(&self.work_dir) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+
+            let path = entry
+                .path()
+                .strip_prefix(&self.work_dir)
+                .map_err(io::Error::other)?;
+            let path = VirtualPath::new(path)?;
+            let data = fs::read(entry.path())?;
+            checksums.insert(path.to_string(), checksum(&data));
+        }
+        Ok(checksums)
     }
 
     pub fn insert_file(&mut self, path: impl AsRef<Path>, content: &[u8]) -> io::Result<()> {
@@ -123,6 +197,19 @@ impl PackBuilder {
         Ok(())
     }
 
+    /// Serializes a value into a file of the pack, in the format `F`.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the file relative to the pack root.
+    /// * `value` - The value, loaded back with the wrapper asset of `F` (e.g. `Bincode<T>`).
+    pub fn insert_data<F: WriteFormat>(
+        &mut self,
+        path: impl AsRef<Path>,
+        value: &(impl Serialize + ?Sized),
+    ) -> io::Result<()> {
+        self.insert_file(path, &F::to_vec(value)?)
+    }
+
     pub const fn work_dir(&self) -> &PathBuf {
         &self.work_dir
     }
@@ -132,10 +219,10 @@ impl Drop for PackBuilder {
     /// Removes the temporary directory when the `PackBuilder` is dropped,
     /// if the `remove_dir` flag is set to `true`.
     fn drop(&mut self) {
-        if self.cleanup_on_drop {
-            if let Err(err) = fs::remove_dir_all(&self.work_dir) {
-                eprintln!("Warning: Failed to remove temp dir: {err}");
-            }
+        if self.cleanup_on_drop
+            && let Err(err) = fs::remove_dir_all(&self.work_dir)
+        {
+            eprintln!("Warning: Failed to remove temp dir: {err}");
         }
     }
 }
@@ -146,6 +233,10 @@ pub struct AssetsBuilder {
     root: PathBuf,
     packs_dir: PathBuf,
     packs: HashMap<String, PackMeta>,
+
+    /// Hashes of the written pack files, used for signing the manifest.
+    #[cfg(feature = "sign")]
+    hashes: BTreeMap<String, String>,
 }
 
 impl AssetsBuilder {
@@ -162,36 +253,121 @@ impl AssetsBuilder {
             root,
             packs_dir,
             packs: HashMap::new(),
+            #[cfg(feature = "sign")]
+            hashes: BTreeMap::new(),
         })
     }
 
     /// Adds a package to the builder, creates the package file, and updates the manifest.
-    pub fn add_pack<P: MakePack>(mut self, name: &str, pack: &PackBuilder) -> io::Result<Self> {
+    pub fn add_pack<P: MakePack>(self, name: &str, pack: &PackBuilder) -> io::Result<Self> {
+        self.add_pack_using(name, &P::extension(), |write| pack.write_pack::<P>(write))
+    }
+
+    /// Adds a package encoded with the given codec instance, e.g. an encryption codec with a key.
+    pub fn add_encoded_pack<P: MakePack, C: Encode>(
+        self,
+        name: &str,
+        pack: &PackBuilder,
+        codec: &C,
+    ) -> io::Result<Self> {
+        let mut extension = P::extension().to_string();
+        extension.push_str(&C::extension());
+
+        self.add_pack_using(name, &extension, |write| {
+            pack.write_encoded_pack::<P, C>(codec, write)
+        })
+    }
+
+    fn add_pack_using(
+        mut self,
+        name: &str,
+        extension: &str,
+        write_pack: impl FnOnce(&mut dyn io::Write) -> io::Result<()>,
+    ) -> io::Result<Self> {
         let mut file_name = name.to_string();
-        file_name.push_str(&P::extension());
+        file_name.push_str(extension);
         let path = self.root.join(&self.packs_dir).join(&file_name);
 
         let mut file = File::create_new(&path)?;
-        pack.write_pack::<P>(&mut file)?;
 
-        let meta = PackMeta(PathBuf::from(file_name));
+        #[cfg(feature = "sign")]
+        {
+            let mut write = Hashed::new(&mut file);
+            write_pack(&mut write)?;
+            self.hashes.insert(name.to_string(), write.finish());
+        }
+        #[cfg(not(feature = "sign"))]
+        write_pack(&mut file)?;
+
+        let meta = PackMeta::new(file_name);
         self.packs.insert(name.to_string(), meta);
 
         Ok(self)
     }
 
+    /// Records that the pack `name` depends on other packs,
+    /// so `Assets::load_group` loads them before it.
+    pub fn add_dependencies(
+        mut self,
+        name: &str,
+        dependencies: impl IntoIterator<Item = impl Into<String>>,
+    ) -> io::Result<Self> {
+        let Some(meta) = self.packs.get_mut(name) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No pack \"{name}\" was added"),
+            ));
+        };
+
+        meta.depends
+            .extend(dependencies.into_iter().map(Into::into));
+        Ok(self)
+    }
+
     /// Writes the asset manifest (`manifest.json`) containing metadata about packaged assets.
     pub fn write_manifest(self, allow_overwrite: bool) -> Result<(), JsonIoError> {
         let manifest_path = self.root.join(MANIFEST_FILE);
-
         let assets = Assets::new(self.packs_dir, self.packs);
 
-        let file = match allow_overwrite {
-            true => File::create(manifest_path),
-            false => File::create_new(manifest_path),
-        }?;
-        serde_json::to_writer(file, &assets)?;
+        Self::write_assets(&assets, &manifest_path, allow_overwrite)
+    }
+
+    /// Writes the asset manifest with the hashes of every added pack, and its signature made with `key`
+    /// into `manifest.json.sig`.
+    ///
+    /// The resulting assets can be loaded with `Assets::load_from_dir_signed`.
+    #[cfg(feature = "sign")]
+    pub fn write_manifest_signed(
+        self,
+        key: &SigningKey,
+        allow_overwrite: bool,
+    ) -> Result<(), SignatureError> {
+        let mut assets = Assets::new(self.packs_dir, self.packs);
+        let signed = assets.sign(self.hashes, key)?;
+
+        let mut file = Self::create(&self.root.join(MANIFEST_FILE), allow_overwrite)?;
+        io::Write::write_all(&mut file, &signed.manifest)?;
+        let mut file = Self::create(&self.root.join(SIGNATURE_FILE), allow_overwrite)?;
+        io::Write::write_all(&mut file, signed.signature.as_bytes())?;
+
+        Ok(())
+    }
+
+    fn write_assets(
+        assets: &Assets,
+        manifest_path: &Path,
+        allow_overwrite: bool,
+    ) -> Result<(), JsonIoError> {
+        let file = Self::create(manifest_path, allow_overwrite)?;
+        serde_json::to_writer(file, assets)?;
 
         Ok(())
     }
+
+    fn create(path: &Path, allow_overwrite: bool) -> io::Result<File> {
+        match allow_overwrite {
+            true => File::create(path),
+            false => File::create_new(path),
+        }
+    }
 }
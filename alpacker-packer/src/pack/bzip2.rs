@@ -1,14 +1,23 @@
 use alpacker::pack::bzip2::Bzip2Codec;
 use bzip2::Compression;
-use std::{borrow::Cow, io};
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
 
 use super::codec::Encode;
 
 impl Encode for Bzip2Codec {
-    fn encode(write: impl io::Write) -> io::Result<impl io::Write> {
+    type Writer<W: Write> = bzip2::write::BzEncoder<W>;
+
+    fn encode<W: Write>(&self, write: W) -> io::Result<Self::Writer<W>> {
         Ok(bzip2::write::BzEncoder::new(write, Compression::default()))
     }
 
+    fn finish<W: Write>(write: Self::Writer<W>) -> io::Result<()> {
+        write.finish()?.flush()
+    }
+
     fn extension() -> Cow<'static, str> {
         Cow::Borrowed(".bz2")
     }
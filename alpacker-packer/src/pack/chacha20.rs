@@ -0,0 +1,109 @@
+use alpacker::pack::chacha20::{CHUNK_SIZE, ChaCha20Codec, KeyProvider, MAGIC, NONCE_SIZE, TAG_SIZE};
+use chacha20poly1305::{
+    ChaCha20Poly1305, Key,
+    aead::{OsRng, generic_array::GenericArray, rand_core::RngCore, stream::EncryptorBE32},
+};
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
+
+use super::codec::Encode;
+
+impl<K: KeyProvider> Encode for ChaCha20Codec<K> {
+    type Writer<W: Write> = EncryptWriter<W>;
+
+    fn encode<W: Write>(&self, write: W) -> io::Result<Self::Writer<W>> {
+        EncryptWriter::new(write, &self.keys.key()?)
+    }
+
+    fn finish<W: Write>(mut write: Self::Writer<W>) -> io::Result<()> {
+        write.finish()
+    }
+
+    fn extension() -> Cow<'static, str> {
+        Cow::Borrowed(".enc")
+    }
+}
+
+/// A writer that encrypts data into a chunked ChaCha20-Poly1305 stream.
+///
+/// Call [EncryptWriter::finish] to write the last chunk and handle errors.
+/// If the writer is dropped unfinished, the stream is still finished on drop, but errors are ignored
+/// and the pack then fails authentication when it's loaded.
+pub struct EncryptWriter<W: Write> {
+    write: W,
+    encryptor: Option<EncryptorBE32<ChaCha20Poly1305>>,
+    /// Plaintext of the current chunk.
+    buf: Vec<u8>,
+}
+
+fn encrypt_error(_: chacha20poly1305::Error) -> io::Error {
+    io::Error::other("Failed to encrypt chunk")
+}
+
+impl<W: Write> EncryptWriter<W> {
+    /// Writes the stream header with a random nonce and prepares encryption with `key`.
+    pub fn new(mut write: W, key: &[u8; 32]) -> io::Result<Self> {
+        let mut nonce = [0; NONCE_SIZE];
+        OsRng.fill_bytes(&mut nonce);
+
+        write.write_all(&MAGIC)?;
+        write.write_all(&nonce)?;
+
+        let nonce = GenericArray::from_slice(&nonce);
+        let encryptor = EncryptorBE32::new(Key::from_slice(key), nonce);
+
+        Ok(Self {
+            write,
+            encryptor: Some(encryptor),
+            buf: Vec::with_capacity(CHUNK_SIZE + TAG_SIZE),
+        })
+    }
+
+    /// Encrypts the remaining data as the last chunk and flushes the underlying writer.
+    pub fn finish(&mut self) -> io::Result<()> {
+        let Some(encryptor) = self.encryptor.take() else {
+            return Ok(());
+        };
+
+        encryptor
+            .encrypt_last_in_place(&[], &mut self.buf)
+            .map_err(encrypt_error)?;
+        self.write.write_all(&self.buf)?;
+        self.write.flush()
+    }
+}
+
+impl<W: Write> Write for EncryptWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let Some(encryptor) = &mut self.encryptor else {
+            return Err(io::Error::other("Encrypted stream is already finished"));
+        };
+
+        // A full chunk is never the last one, so it can be written out right away.
+        // If the data ends exactly at a chunk boundary, the last chunk is empty.
+        let len = buf.len().min(CHUNK_SIZE - self.buf.len());
+        self.buf.extend_from_slice(&buf[..len]);
+
+        if self.buf.len() == CHUNK_SIZE {
+            encryptor
+                .encrypt_next_in_place(&[], &mut self.buf)
+                .map_err(encrypt_error)?;
+            self.write.write_all(&self.buf)?;
+            self.buf.clear();
+        }
+
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write.flush()
+    }
+}
+
+impl<W: Write> Drop for EncryptWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
@@ -15,17 +15,29 @@ use crate::MakePack;
 ///
 /// This is the inverse of [`Decode`] and is used by pack builders
 /// to write compressed archive formats like `.tar.zst` or `.tar.bz2`.
+/// Like [`Decode`], encoders are instances and may carry state such as encryption keys.
 pub trait Encode {
+    /// The encoder wrapping a writer of type `W`.
+    type Writer<W: Write>: Write;
+
     /// Wraps the given writer in an encoder.
-    fn encode(write: impl Write) -> io::Result<impl Write>;
+    fn encode<W: Write>(&self, write: W) -> io::Result<Self::Writer<W>>;
+
+    /// Writes the end of the encoded stream and flushes it.
+    ///
+    /// Encoders may also finish on drop, but only this reports errors,
+    /// so a failure here means the written stream is truncated.
+    fn finish<W: Write>(write: Self::Writer<W>) -> io::Result<()>;
 
     /// Returns the file extension (e.g., ".zst", ".bz2") for this encoder.
     fn extension() -> Cow<'static, str>;
 }
 
-impl<P: MakePack, C: Encode + Decode> MakePack for EncodedPack<P, C> {
+impl<P: MakePack, C: Encode + Decode + Default> MakePack for EncodedPack<P, C> {
     fn make(root: impl AsRef<Path>, write: impl Write, manifest: PackManifest) -> io::Result<()> {
-        P::make(root, C::encode(write)?, manifest)
+        let mut write = C::default().encode(write)?;
+        P::make(root, &mut write, manifest)?;
+        C::finish(write)
     }
 
     fn extension() -> Cow<'static, str> {
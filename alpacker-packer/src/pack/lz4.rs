@@ -1,13 +1,22 @@
 use alpacker::pack::lz4::Lz4Codec;
-use std::{borrow::Cow, io};
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
 
 use super::codec::Encode;
 
 impl Encode for Lz4Codec {
-    fn encode(write: impl io::Write) -> io::Result<impl io::Write> {
+    type Writer<W: Write> = lz4_flex::frame::FrameEncoder<W>;
+
+    fn encode<W: Write>(&self, write: W) -> io::Result<Self::Writer<W>> {
         Ok(lz4_flex::frame::FrameEncoder::new(write))
     }
 
+    fn finish<W: Write>(write: Self::Writer<W>) -> io::Result<()> {
+        write.finish()?.flush()
+    }
+
     fn extension() -> Cow<'static, str> {
         Cow::Borrowed(".lz4")
     }
@@ -11,5 +11,6 @@ pack_fmt!("tar", tar, TarPack);
 pack_fmt!("zstd", zstd, Zstd);
 pack_fmt!("bzip2", bzip2, Bzip2);
 pack_fmt!("lz4", lz4, Lz4);
+pack_fmt!("chacha20", chacha20, Encrypted);
 
 pub mod codec;
@@ -1,11 +1,20 @@
 use alpacker::pack::zstd::ZstdCodec;
-use std::{borrow::Cow, io};
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
 
 use super::codec::Encode;
 
 impl Encode for ZstdCodec {
-    fn encode(write: impl io::Write) -> io::Result<impl io::Write> {
-        Ok(zstd::Encoder::new(write, 7)?.auto_finish())
+    type Writer<W: Write> = zstd::Encoder<'static, W>;
+
+    fn encode<W: Write>(&self, write: W) -> io::Result<Self::Writer<W>> {
+        zstd::Encoder::new(write, 7)
+    }
+
+    fn finish<W: Write>(write: Self::Writer<W>) -> io::Result<()> {
+        write.finish()?.flush()
     }
 
     fn extension() -> Cow<'static, str> {
@@ -0,0 +1,21 @@
+/* Conformance checks for pack formats, see `alpacker::testing`. */
+
+pub use alpacker::testing::*;
+
+use alpacker::Pack;
+
+use crate::{MakePack, PackBuilder};
+
+/// Runs every check of [check_pack] against a [MakePack] and [Pack] pair.
+///
+/// The pack is built through [PackBuilder], so it includes a manifest like a shipped pack.
+///
+/// # Panics
+/// If building the pack fails, or the pack doesn't pass a check.
+pub fn check_make_pack<P: MakePack + Pack>() {
+    check_pack::<P>(|root| {
+        let mut data = Vec::new();
+        PackBuilder::with_temp_dir(root, false).write_pack::<P>(&mut data)?;
+        Ok(data)
+    });
+}
@@ -0,0 +1,126 @@
+mod common;
+use common::*;
+
+use std::{fs, io};
+
+use testdir::testdir;
+
+use alpacker::{
+    LoadOptions, Pack,
+    pack::chacha20::{ChaCha20Codec, KeyProvider, MAGIC, NONCE_SIZE, StaticKey},
+};
+use alpacker_packer::{AssetsBuilder, Encrypted, PackBuilder, TarZstPack};
+
+const KEY: [u8; 32] = [42; 32];
+
+/// A key embedded into the game, which makes the pack loadable through [Pack::load].
+#[derive(Default)]
+struct GameKey;
+
+impl KeyProvider for GameKey {
+    fn key(&self) -> io::Result<[u8; 32]> {
+        Ok(KEY)
+    }
+}
+
+/// A writer that fails once more than `limit` bytes are written.
+struct LimitedWriter {
+    limit: usize,
+}
+
+impl io::Write for LimitedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() > self.limit {
+            return Err(io::Error::new(io::ErrorKind::StorageFull, "Writer is full"));
+        }
+        self.limit -= buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_encrypted_pack() -> TestResult {
+    let test_dir = testdir!();
+    let codec = ChaCha20Codec::new(StaticKey(KEY));
+
+    // Larger than a single chunk, to check chunk boundaries
+    let big = (0..200_000).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+
+    let mut pack = PackBuilder::new()?.copy_from(ASSETS_DIR)?;
+    pack.insert_file("big.bin", &big)?;
+
+    let builder = AssetsBuilder::new(&test_dir, "./")?
+        .add_encoded_pack::<TarZstPack, _>("test", &pack, &codec)?;
+    let assets = write_and_load(builder, &test_dir)?;
+
+    let mut pack =
+        assets.load_encoded_pack::<TarZstPack, _>("test", &codec, &LoadOptions::default())?;
+    assert_eq!(pack.get::<String>("myfile.txt")?, "Hello, World!\n");
+    assert_eq!(pack.get::<Vec<u8>>("big.bin")?, big);
+
+    let data = fs::read(test_dir.join("test.tar.zst.enc"))?;
+    let mut pack = Encrypted::<TarZstPack, GameKey>::load(data.as_slice())?;
+    assert_eq!(pack.get::<String>("myfile.txt")?, "Hello, World!\n");
+
+    let wrong = ChaCha20Codec::new(StaticKey([0; 32]));
+    assert!(
+        assets
+            .load_encoded_pack::<TarZstPack, _>("test", &wrong, &LoadOptions::default())
+            .is_err(),
+        "Expected decryption with a wrong key to fail"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_tampered_pack() -> TestResult {
+    let codec = ChaCha20Codec::new(StaticKey(KEY));
+
+    let pack = PackBuilder::new()?.copy_from(ASSETS_DIR)?;
+    let mut data = Vec::new();
+    pack.write_encoded_pack::<TarZstPack, _>(&codec, &mut data)?;
+
+    let truncated = &data[..data.len() - 1];
+    assert!(
+        Encrypted::<TarZstPack, _>::load_with_codec(truncated, &codec, &LoadOptions::default())
+            .is_err(),
+        "Expected a truncated pack to be rejected"
+    );
+
+    let mut modified = data.clone();
+    let middle = modified.len() / 2;
+    modified[middle] ^= 1;
+    assert!(
+        Encrypted::<TarZstPack, _>::load_with_codec(
+            modified.as_slice(),
+            &codec,
+            &LoadOptions::default()
+        )
+        .is_err(),
+        "Expected a modified pack to be rejected"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_finish_error() -> TestResult {
+    let codec = ChaCha20Codec::new(StaticKey(KEY));
+    let pack = PackBuilder::new()?.copy_from(ASSETS_DIR)?;
+
+    // Only the stream header fits, the pack itself is written when the stream is finished
+    let write = LimitedWriter {
+        limit: MAGIC.len() + NONCE_SIZE,
+    };
+    let err = pack
+        .write_encoded_pack::<TarZstPack, _>(&codec, write)
+        .expect_err("Expected the truncated pack to fail");
+    assert_eq!(err.kind(), io::ErrorKind::StorageFull);
+
+    Ok(())
+}
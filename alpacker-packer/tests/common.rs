@@ -1,8 +1,34 @@
 #![allow(unused)]
 
-use std::error::Error;
+use std::{error::Error, path::Path};
+
+use alpacker::Assets;
+#[cfg(feature = "sign")]
+use alpacker::sign::SigningKey;
+use alpacker_packer::AssetsBuilder;
 
 pub type TestResult = Result<(), Box<dyn Error>>;
 
 pub const ASSETS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/assets/");
 pub const IMAGE: &str = "cat.png";
+
+/// The key manifests are signed with when the `sign` feature is enabled.
+#[cfg(feature = "sign")]
+pub const SIGNING_KEY: [u8; 32] = [7; 32];
+
+/// Writes the manifest into `dir` and loads it back.
+///
+/// Signed builds only load signed manifests, so with the `sign` feature the manifest is signed.
+pub fn write_and_load(builder: AssetsBuilder, dir: &Path) -> Result<Assets, Box<dyn Error>> {
+    #[cfg(feature = "sign")]
+    {
+        let key = SigningKey::from_bytes(&SIGNING_KEY);
+        builder.write_manifest_signed(&key, false)?;
+        Ok(Assets::load_from_dir_signed(dir, key.verifying_key())?)
+    }
+    #[cfg(not(feature = "sign"))]
+    {
+        builder.write_manifest(false)?;
+        Ok(Assets::load_from_dir(dir)?)
+    }
+}
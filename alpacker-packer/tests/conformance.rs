@@ -0,0 +1,24 @@
+use alpacker_packer::{TarPack, testing::check_make_pack};
+
+#[test]
+fn test_tar() {
+    check_make_pack::<TarPack>();
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_tar_zstd() {
+    check_make_pack::<alpacker_packer::TarZstPack>();
+}
+
+#[cfg(feature = "bzip2")]
+#[test]
+fn test_tar_bzip2() {
+    check_make_pack::<alpacker_packer::TarBz2Pack>();
+}
+
+#[cfg(feature = "lz4")]
+#[test]
+fn test_tar_lz4() {
+    check_make_pack::<alpacker_packer::TarLz4Pack>();
+}
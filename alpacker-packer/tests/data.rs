@@ -0,0 +1,112 @@
+mod common;
+use common::*;
+
+use std::collections::BTreeMap;
+
+use alpacker::{
+    Pack,
+    data::serialized::{
+        Bincode, BincodeFormat, Data, DataError, MsgPack, MsgPackFormat, Postcard, PostcardFormat,
+    },
+};
+use alpacker_packer::{PackBuilder, TarPack};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Item {
+    name: String,
+    price: u32,
+    tags: Vec<String>,
+}
+
+fn items() -> BTreeMap<u32, Item> {
+    (0..100)
+        .map(|id| {
+            let item = Item {
+                name: format!("Item #{id}"),
+                price: id * 10,
+                tags: vec!["common".into(); id as usize % 3],
+            };
+            (id, item)
+        })
+        .collect()
+}
+
+/// Test that data written by the packer loads back with the matching wrapper.
+#[test]
+fn test_binary_round_trip() -> TestResult {
+    let items = items();
+
+    let mut builder = PackBuilder::new()?;
+    builder.insert_data::<PostcardFormat>("items.postcard", &items)?;
+    builder.insert_data::<BincodeFormat>("items.bincode", &items)?;
+    builder.insert_data::<MsgPackFormat>("items.msgpack", &items)?;
+
+    let mut data = Vec::new();
+    builder.write_pack::<TarPack>(&mut data)?;
+    let mut pack: TarPack = TarPack::load(data.as_slice())?;
+
+    let Postcard(loaded) = pack.get::<Postcard<BTreeMap<u32, Item>>>("items.postcard")?;
+    assert_eq!(loaded, items);
+    let Bincode(loaded) = pack.get::<Bincode<BTreeMap<u32, Item>>>("items.bincode")?;
+    assert_eq!(loaded, items);
+    let MsgPack(loaded) = pack.get::<MsgPack<BTreeMap<u32, Item>>>("items.msgpack")?;
+    assert_eq!(loaded, items);
+
+    for path in ["items.postcard", "items.bincode", "items.msgpack"] {
+        let Data(loaded) = pack.get::<Data<BTreeMap<u32, Item>>>(path)?;
+        assert_eq!(loaded, items, "{path}");
+    }
+
+    Ok(())
+}
+
+/// Test that a mismatched type fails with a parse error naming the entry.
+#[test]
+fn test_binary_mismatch() -> TestResult {
+    let mut builder = PackBuilder::new()?;
+    builder.insert_data::<BincodeFormat>("name.bincode", "Not an item")?;
+
+    let mut data = Vec::new();
+    builder.write_pack::<TarPack>(&mut data)?;
+    let mut pack: TarPack = TarPack::load(data.as_slice())?;
+
+    let err = pack.get::<Bincode<Item>>("name.bincode").unwrap_err();
+    assert!(matches!(err, DataError::Parse { .. }), "{err}");
+    assert!(err.to_string().contains("name.bincode"), "{err}");
+
+    Ok(())
+}
+
+/// A tree nested as deep as the data says.
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct Node {
+    children: Vec<Node>,
+}
+
+/// Test that hostile lengths and nesting fail to parse instead of exhausting memory or the stack.
+#[test]
+fn test_binary_limits() -> TestResult {
+    // A string claiming u64::MAX bytes
+    let mut huge = vec![0xFD];
+    huge.extend_from_slice(&u64::MAX.to_le_bytes());
+    // Nodes nested 400 levels deep, each a struct array holding the children array
+    let mut nested = [0x91, 0x91].repeat(200);
+    nested.extend_from_slice(&[0x91, 0x90]);
+
+    let mut builder = PackBuilder::new()?;
+    builder.insert_file("huge.bincode", &huge)?;
+    builder.insert_file("nested.msgpack", &nested)?;
+
+    let mut data = Vec::new();
+    builder.write_pack::<TarPack>(&mut data)?;
+    let mut pack: TarPack = TarPack::load(data.as_slice())?;
+
+    let err = pack.get::<Bincode<String>>("huge.bincode").unwrap_err();
+    assert!(matches!(err, DataError::Parse { .. }), "{err}");
+    let err = pack.get::<MsgPack<Node>>("nested.msgpack").unwrap_err();
+    assert!(matches!(err, DataError::Parse { .. }), "{err}");
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+mod common;
+use common::*;
+
+use testdir::testdir;
+
+use alpacker::{PackLoadError, error::ErrorKind, pack::TarPack};
+use alpacker_packer::{AssetsBuilder, PackBuilder};
+
+#[test]
+fn test_load_group() -> TestResult {
+    let test_dir = testdir!();
+    let pack = PackBuilder::new()?.copy_from(ASSETS_DIR)?;
+
+    let builder = AssetsBuilder::new(&test_dir, "./")?
+        .add_pack::<TarPack>("common", &pack)?
+        .add_pack::<TarPack>("music", &pack)?
+        .add_pack::<TarPack>("level1", &pack)?
+        .add_pack::<TarPack>("broken", &pack)?
+        .add_pack::<TarPack>("loop_a", &pack)?
+        .add_pack::<TarPack>("loop_b", &pack)?
+        .add_dependencies("music", ["common"])?
+        .add_dependencies("level1", ["common", "music"])?
+        .add_dependencies("broken", ["missing"])?
+        .add_dependencies("loop_a", ["loop_b"])?
+        .add_dependencies("loop_b", ["loop_a"])?;
+    let assets = write_and_load(builder, &test_dir)?;
+
+    assert_eq!(
+        assets.dependency_order("level1")?,
+        ["common", "music", "level1"],
+        "Dependencies must be loaded first, each only once"
+    );
+    assert_eq!(assets.load_group::<TarPack>("level1")?.len(), 3);
+
+    let err = assets.load_group::<TarPack>("broken").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Dependency);
+    assert!(
+        matches!(
+            err.downcast_ref(),
+            Some(PackLoadError::MissingDependency { dependency, .. }) if dependency == "missing"
+        ),
+        "Expected a missing dependency to be reported"
+    );
+
+    let err = assets.dependency_order("loop_a").unwrap_err();
+    assert!(
+        matches!(
+            err.downcast_ref(),
+            Some(PackLoadError::CyclicDependency(cycle)) if cycle == &["loop_a", "loop_b", "loop_a"]
+        ),
+        "Expected a dependency cycle to be reported"
+    );
+
+    Ok(())
+}
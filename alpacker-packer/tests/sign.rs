@@ -0,0 +1,140 @@
+mod common;
+use common::*;
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+use testdir::testdir;
+
+use alpacker::{
+    Assets, JsonIoError, LoadOptions, MANIFEST_FILE, Pack,
+    error::ErrorKind,
+    limits::LoadLimits,
+    progress::LoadProgress,
+    sign::{SIGNATURE_FILE, SignatureError, SigningKey},
+};
+use alpacker_packer::{AssetsBuilder, PackBuilder, TarZstPack};
+
+const SECRET: [u8; 32] = [7; 32];
+
+#[test]
+fn test_signed_pack() -> TestResult {
+    let test_dir = testdir!();
+    let key = SigningKey::from_bytes(&SECRET);
+
+    let pack = PackBuilder::new()?.copy_from(ASSETS_DIR)?;
+    AssetsBuilder::new(&test_dir, "./")?
+        .add_pack::<TarZstPack>("test", &pack)?
+        .write_manifest_signed(&key, false)?;
+
+    // A valid signature lets packs load as usual
+    let assets = Assets::load_from_dir_signed(&test_dir, key.verifying_key())?;
+    let mut pack = assets.load_pack::<TarZstPack>("test")?;
+    assert_eq!(pack.get::<String>("myfile.txt")?, "Hello, World!\n");
+
+    // Signed packs are buffered for hashing, but never beyond the total size limit
+    let options = LoadOptions {
+        limits: LoadLimits {
+            max_total_size: 16,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let err = assets
+        .load_pack_with::<TarZstPack>("test", &options)
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Limit);
+
+    // A different public key must reject the manifest
+    let other = SigningKey::from_bytes(&[8; 32]).verifying_key();
+    assert!(
+        matches!(
+            Assets::load_from_dir_signed(&test_dir, other),
+            Err(SignatureError::BadSignature(_))
+        ),
+        "Expected the manifest to be rejected with a wrong key"
+    );
+
+    // Tampering with the pack must be detected on load
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(test_dir.join("test.tar.zst"))?;
+    file.write_all(&[0])?;
+
+    let progress = LoadProgress::new();
+    let options = LoadOptions {
+        progress: Some(progress.clone()),
+        ..Default::default()
+    };
+    let err = assets
+        .load_pack_with::<TarZstPack>("test", &options)
+        .unwrap_err();
+    assert_eq!(err.pack(), Some("test"));
+    assert!(
+        matches!(err.downcast_ref(), Some(SignatureError::HashMismatch(_))),
+        "Expected a modified pack to fail verification"
+    );
+    // Progress only counts bytes that are parsed, not the ones read for hashing
+    assert_eq!(progress.bytes_read(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_unsigned_manifest() -> TestResult {
+    let test_dir = testdir!();
+    let key = SigningKey::from_bytes(&SECRET);
+
+    let pack = PackBuilder::new()?.copy_from(ASSETS_DIR)?;
+    AssetsBuilder::new(&test_dir, "./")?
+        .add_pack::<TarZstPack>("test", &pack)?
+        .write_manifest(false)?;
+
+    assert!(
+        matches!(
+            Assets::load_from_dir_signed(&test_dir, key.verifying_key()),
+            Err(SignatureError::Unsigned)
+        ),
+        "Expected an unsigned manifest to be rejected"
+    );
+
+    // Signed builds never load manifests without verifying them
+    assert!(
+        matches!(
+            Assets::load_from_dir(&test_dir),
+            Err(JsonIoError::Signature(SignatureError::KeyRequired))
+        ),
+        "Expected loading without a key to fail"
+    );
+
+    Ok(())
+}
+
+/// Test that the signature covers the exact bytes of the manifest file.
+#[test]
+fn test_modified_manifest() -> TestResult {
+    let test_dir = testdir!();
+    let key = SigningKey::from_bytes(&SECRET);
+
+    let pack = PackBuilder::new()?.copy_from(ASSETS_DIR)?;
+    AssetsBuilder::new(&test_dir, "./")?
+        .add_pack::<TarZstPack>("test", &pack)?
+        .write_manifest_signed(&key, false)?;
+    assert!(test_dir.join(SIGNATURE_FILE).exists());
+
+    // Whitespace doesn't change the parsed manifest, but it's still a different file
+    let manifest_path = test_dir.join(MANIFEST_FILE);
+    let mut manifest = fs::read(&manifest_path)?;
+    manifest.push(b'\n');
+    fs::write(&manifest_path, manifest)?;
+
+    assert!(
+        matches!(
+            Assets::load_from_dir_signed(&test_dir, key.verifying_key()),
+            Err(SignatureError::BadSignature(_))
+        ),
+        "Expected a modified manifest to be rejected"
+    );
+
+    Ok(())
+}
@@ -3,7 +3,7 @@ use common::*;
 
 use testdir::testdir;
 
-use alpacker::{Assets, MANIFEST_FILE, Pack};
+use alpacker::{MANIFEST_FILE, Pack, pack::Diagnostics};
 use alpacker_packer::{AssetsBuilder, PackBuilder, TarZstPack, tar::Skipped};
 
 #[test]
@@ -13,9 +13,8 @@ fn test_tar_zstd_pack() -> TestResult {
 
     let pack = PackBuilder::new()?.copy_from(ASSETS_DIR)?;
 
-    AssetsBuilder::new(&test_dir, "./")?
-        .add_pack::<TarZstPack>("test", &pack)?
-        .write_manifest(false)?;
+    let builder = AssetsBuilder::new(&test_dir, "./")?.add_pack::<TarZstPack>("test", &pack)?;
+    let assets = write_and_load(builder, &test_dir)?;
 
     let manifest_path = test_dir.join(MANIFEST_FILE);
     assert!(
@@ -23,9 +22,7 @@ fn test_tar_zstd_pack() -> TestResult {
         "Manifest file not found at {manifest_path:?}"
     );
 
-    // Test assets and pack loading
-    let assets = Assets::load_from_dir(test_dir)?;
-
+    // Test pack loading
     let mut pack = assets.load_pack::<TarZstPack>("test")?;
 
     let skipped = pack.skipped();
@@ -44,3 +41,30 @@ fn test_tar_zstd_pack() -> TestResult {
 
     Ok(())
 }
+
+#[cfg(feature = "checksum")]
+#[test]
+fn test_corrupt_entry() -> TestResult {
+    use alpacker_packer::TarPack;
+
+    let mut data = Vec::new();
+    PackBuilder::new()?
+        .copy_from(ASSETS_DIR)?
+        .write_pack::<TarPack>(&mut data)?;
+
+    // Flip a bit inside the contents of `myfile.txt`
+    let offset = data
+        .windows(5)
+        .position(|window| window == b"Hello")
+        .expect("File contents not found");
+    data[offset] ^= 1;
+
+    let mut pack = <TarPack>::load(data.as_slice())?;
+    assert!(
+        pack.get::<String>("myfile.txt").is_err(),
+        "Expected the corrupt file to be rejected"
+    );
+    assert!(pack.verify().is_err());
+
+    Ok(())
+}
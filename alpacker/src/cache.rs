@@ -0,0 +1,239 @@
+use std::{
+    any::{Any, TypeId, type_name},
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, PoisonError, TryLockError, Weak},
+};
+
+use crate::{Assets, Error, Pack, stats::PackStats};
+
+/// A pack shared between independent users of [Assets].
+///
+/// [Pack::get_raw] needs mutable access, so the pack is guarded by a mutex.
+pub type SharedPack<P> = Arc<Mutex<P>>;
+
+/// Describes a pack currently held in the cache of [Assets].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadedPack {
+    pub name: String,
+    /// The name of the pack type, e.g. `alpacker::pack::tar::TarPack`.
+    pub type_name: &'static str,
+    /// Statistics of the pack, if its type keeps track of them. See [Pack::stats].
+    ///
+    /// If the pack is locked by another user, these are the statistics from when it was loaded.
+    pub stats: Option<PackStats>,
+}
+
+/// Reads the statistics of a type-erased [SharedPack] without waiting for its lock.
+///
+/// # Returns
+/// `None` if the pack is locked or doesn't keep track of statistics.
+type StatsFn = fn(&(dyn Any + Send + Sync)) -> Option<PackStats>;
+
+/// A type-erased [SharedPack].
+type AnyPack = Arc<dyn Any + Send + Sync>;
+
+/// A pack in the cache, with its statistics from when it was loaded.
+#[derive(Debug)]
+struct Cached {
+    pack: Weak<dyn Any + Send + Sync>,
+    stats: Option<PackStats>,
+}
+
+impl Cached {
+    fn new<P: Pack + Send + 'static>(pack: &SharedPack<P>, stats: Option<PackStats>) -> Self {
+        let pack: AnyPack = pack.clone();
+        Self {
+            pack: Arc::downgrade(&pack),
+            stats,
+        }
+    }
+}
+
+/// The pack cached under one key. Its mutex is held while the pack loads,
+/// so loading one pack doesn't block requests for other packs.
+type Slot = Arc<Mutex<Cached>>;
+
+#[derive(Debug)]
+struct CacheEntry {
+    type_name: &'static str,
+    slot: Slot,
+    stats: StatsFn,
+}
+
+/// Weak references to loaded packs, keyed by pack name and type.
+///
+/// The cache never keeps a pack alive by itself: once every [SharedPack] is dropped, the memory is freed.
+#[derive(Debug, Default)]
+pub(crate) struct PackCache {
+    packs: Mutex<HashMap<(String, TypeId), CacheEntry>>,
+}
+
+impl PackCache {
+    fn lock(&self) -> MutexGuard<'_, HashMap<(String, TypeId), CacheEntry>> {
+        // The map stays consistent even if a pack failed to load while the lock was held.
+        self.packs.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the slot of a pack, inserting an empty one if the pack isn't cached.
+    fn slot<P: Pack + Send + 'static>(&self, name: &str) -> Slot {
+        let key = (name.to_string(), TypeId::of::<P>());
+        let mut packs = self.lock();
+        let entry = packs.entry(key).or_insert_with(|| CacheEntry {
+            type_name: type_name::<P>(),
+            slot: Arc::new(Mutex::new(Cached {
+                pack: Weak::<()>::new(),
+                stats: None,
+            })),
+            stats: |pack| {
+                let pack = pack.downcast_ref::<Mutex<P>>()?;
+                // Never wait for a pack, its holder may be the caller.
+                pack.try_lock().ok()?.stats()
+            },
+        });
+        entry.slot.clone()
+    }
+}
+
+fn lock_pack<P>(pack: &Mutex<P>) -> MutexGuard<'_, P> {
+    pack.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Returns the pack in a slot if it's still in use.
+fn upgrade<P: Send + 'static>(cached: &Cached) -> Option<SharedPack<P>> {
+    cached.pack.upgrade()?.downcast::<Mutex<P>>().ok()
+}
+
+impl Assets {
+    /// Returns a shared pack by name, loading it only if it isn't already loaded.
+    ///
+    /// Packs are cached by name and type, so independent systems asking for the same pack
+    /// get the same instance without coordinating with each other.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the asset pack to load.
+    ///
+    /// # Returns
+    /// * `Ok(SharedPack<P>)` if the pack is cached or successfully loaded.
+    /// * `Err(Error)` if the pack is missing or fails to load.
+    pub fn shared_pack<P: Pack + Send + 'static>(
+        &self,
+        name: &str,
+    ) -> Result<SharedPack<P>, Error> {
+        // Only the slot of this pack is locked while loading, so the same pack is never loaded twice,
+        // while other packs can be requested meanwhile.
+        let slot = self.cache.slot::<P>(name);
+        let mut cached = lock_pack(&slot);
+        if let Some(pack) = upgrade(&cached) {
+            return Ok(pack);
+        }
+
+        let pack = self.load_pack::<P>(name)?;
+        let stats = pack.stats();
+        let pack = Arc::new(Mutex::new(pack));
+        *cached = Cached::new(&pack, stats);
+        Ok(pack)
+    }
+
+    /// Loads a pack again, replacing the cached contents.
+    ///
+    /// If the pack is in use, the existing [SharedPack] is updated in place,
+    /// so every holder sees the new contents.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the asset pack to reload.
+    ///
+    /// # Returns
+    /// * `Ok(SharedPack<P>)` if the pack is successfully loaded.
+    /// * `Err(Error)` if the pack is missing or fails to load. The cached pack is kept.
+    pub fn reload<P: Pack + Send + 'static>(&self, name: &str) -> Result<SharedPack<P>, Error> {
+        let pack = self.load_pack::<P>(name)?;
+        let stats = pack.stats();
+
+        let slot = self.cache.slot::<P>(name);
+        let mut cached = lock_pack(&slot);
+        if let Some(shared) = upgrade::<P>(&cached) {
+            cached.stats = stats;
+            // Release the slot first, a holder of the pack may be waiting for it.
+            drop(cached);
+            *lock_pack(&shared) = pack;
+            return Ok(shared);
+        }
+
+        let shared = Arc::new(Mutex::new(pack));
+        *cached = Cached::new(&shared, stats);
+        Ok(shared)
+    }
+
+    /// Removes a pack of any type from the cache.
+    ///
+    /// Existing [SharedPack]s stay valid, but the next [Assets::shared_pack] call loads the pack again.
+    /// A pack that is still loading is kept, so it isn't loaded twice.
+    ///
+    /// # Returns
+    /// `true` if a pack with this name was in use.
+    pub fn unload(&self, name: &str) -> bool {
+        let mut unloaded = false;
+        self.cache.lock().retain(|(pack, _), entry| {
+            if pack != name {
+                return true;
+            }
+            match cached(&entry.slot) {
+                None => true,
+                Some(pack) => {
+                    unloaded |= pack.is_some();
+                    false
+                }
+            }
+        });
+        unloaded
+    }
+
+    /// Lists packs that are currently loaded and in use, with their statistics.
+    ///
+    /// Summing [PackStats::bytes_held] over the result gives the memory held by cached packs.
+    /// Packs that are still loading aren't listed. This never waits for a pack that is in use.
+    pub fn loaded_packs(&self) -> Vec<LoadedPack> {
+        let mut packs = self.cache.lock();
+        // Slots that are loading are kept, so the pack isn't loaded twice.
+        packs.retain(|_, entry| !matches!(cached(&entry.slot), Some(None)));
+
+        let loaded = packs
+            .iter()
+            .filter_map(|((name, _), entry)| {
+                let (pack, snapshot) = cached(&entry.slot)??;
+                Some((name.clone(), entry.type_name, entry.stats, pack, snapshot))
+            })
+            .collect::<Vec<_>>();
+        // Release the cache first, a holder of a pack may be waiting for it.
+        drop(packs);
+
+        loaded
+            .into_iter()
+            .map(|(name, type_name, stats, pack, snapshot)| LoadedPack {
+                name,
+                type_name,
+                stats: stats(&*pack).or(snapshot),
+            })
+            .collect()
+    }
+}
+
+/// Returns the pack in a slot without waiting for it to load.
+///
+/// # Returns
+/// * `None` if the pack is loading.
+/// * `Some(None)` if the pack isn't in use.
+/// * `Some(Some((pack, stats)))` if the pack is loaded and in use, with its statistics from loading.
+fn cached(slot: &Slot) -> Option<Option<(AnyPack, Option<PackStats>)>> {
+    let cached = match slot.try_lock() {
+        Ok(cached) => cached,
+        Err(TryLockError::Poisoned(err)) => err.into_inner(),
+        Err(TryLockError::WouldBlock) => return None,
+    };
+    Some(
+        cached
+            .pack
+            .upgrade()
+            .map(|pack| (pack, cached.stats.clone())),
+    )
+}
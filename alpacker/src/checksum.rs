@@ -0,0 +1,54 @@
+/* This module detects corrupt pack entries. The packer records an xxh3 checksum of every file
+in the pack manifest, and packs compare the data against it when an entry is read,
+or all at once when loading, depending on the `ChecksumPolicy`. */
+
+use std::{io, path::PathBuf};
+
+use thiserror::Error;
+use twox_hash::XxHash3_64;
+
+use crate::error::ErrorKind;
+
+/// Computes the checksum of an entry, as stored in [PackManifest::checksums](crate::PackManifest::checksums).
+pub fn checksum(data: &[u8]) -> u64 {
+    XxHash3_64::oneshot(data)
+}
+
+/// Error type for entries whose data doesn't match the recorded checksum.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[error("Entry {path:?} is corrupt: expected checksum {expected:016x}, found {found:016x}")]
+pub struct ChecksumError {
+    pub path: PathBuf,
+    pub expected: u64,
+    pub found: u64,
+}
+
+impl From<ChecksumError> for crate::Error {
+    fn from(err: ChecksumError) -> Self {
+        let path = err.path.clone();
+        Self::new(ErrorKind::Corrupt, err).with_path(path)
+    }
+}
+
+impl From<ChecksumError> for io::Error {
+    fn from(err: ChecksumError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// Compares the checksum of `data` to the expected one.
+pub(crate) fn verify(
+    path: impl Into<PathBuf>,
+    data: &[u8],
+    expected: u64,
+) -> Result<(), ChecksumError> {
+    let found = checksum(data);
+    match found == expected {
+        true => Ok(()),
+        false => Err(ChecksumError {
+            path: path.into(),
+            expected,
+            found,
+        }),
+    }
+}
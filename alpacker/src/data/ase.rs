@@ -0,0 +1,896 @@
+/* Disclaimer:
+The term "Aseprite" is used solely to refer to the file format of the Aseprite software.
+This project is not an official product, nor is it affiliated with or endorsed by the developers of Aseprite.
+All trademarks and logos, including "Aseprite", are the property of their respective owners. */
+
+/* This module parses binary `.aseprite`/`.ase` source files, so they can be packed without an export step.
+It reads layers, frames, cels, tags, slices and the palette, and composites frames into RGBA pixels
+(or `DynamicImage` with the `image` feature). Tilemap cels are parsed but not drawn, and the
+hue/saturation/color/luminosity blend modes are drawn as normal ones. */
+
+use flate2::read::ZlibDecoder;
+use std::{
+    io::{self, Read},
+    path::Path,
+    time::Duration,
+};
+use thiserror::Error;
+
+use crate::{Asset, AssetResult, Pack, error::ErrorKind};
+
+const FILE_MAGIC: u16 = 0xA5E0;
+const FRAME_MAGIC: u16 = 0xF1FA;
+const HEADER_SIZE: usize = 128;
+const FRAME_HEADER_SIZE: usize = 16;
+const CHUNK_HEADER_SIZE: usize = 6;
+
+const CHUNK_OLD_PALETTE: u16 = 0x0004;
+const CHUNK_LAYER: u16 = 0x2004;
+const CHUNK_CEL: u16 = 0x2005;
+const CHUNK_TAGS: u16 = 0x2018;
+const CHUNK_PALETTE: u16 = 0x2019;
+const CHUNK_SLICE: u16 = 0x2022;
+
+/// Header flag: layer opacity is valid.
+const FLAG_LAYER_OPACITY: u32 = 1;
+
+/// Palette entries past this index are ignored, so a bogus index can't exhaust memory.
+const MAX_PALETTE_SIZE: usize = 1 << 16;
+
+/// Maximum number of pixels of the canvas and of a single cel, 8192x8192.
+/// Sizes come from the file, so larger images are rejected instead of being allocated.
+pub const MAX_PIXELS: usize = 1 << 26;
+
+/// Maximum number of bytes of cel pixels in a whole file, 256 MiB.
+/// Many cels within [MAX_PIXELS] would otherwise still add up to gigabytes.
+pub const MAX_DECODED_BYTES: usize = 1 << 28;
+
+/// Errors that can occur while parsing Aseprite files.
+#[derive(Debug, Error)]
+pub enum AsepriteError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Not an Aseprite file (magic number {0:#06x})")]
+    Magic(u16),
+
+    #[error("Invalid frame {0} (magic number {1:#06x})")]
+    FrameMagic(usize, u16),
+
+    #[error("Unsupported color depth: {0} bits per pixel")]
+    ColorDepth(u16),
+
+    #[error("Chunk {chunk:#06x} in frame {frame} is truncated")]
+    Truncated { frame: usize, chunk: u16 },
+
+    #[error("Image of {width}x{height} pixels is larger than {MAX_PIXELS} pixels")]
+    TooLarge { width: u16, height: u16 },
+
+    #[error("Cels in frame {frame} exceed {MAX_DECODED_BYTES} bytes of pixels in total")]
+    TooMuchData { frame: usize },
+
+    #[error("Cel in frame {frame} has {found} bytes of pixels, expected {expected}")]
+    CelSize {
+        frame: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl From<AsepriteError> for crate::Error {
+    fn from(err: AsepriteError) -> Self {
+        match err {
+            AsepriteError::Io(err) => err.into(),
+            err => Self::new(ErrorKind::Asset, err),
+        }
+    }
+}
+
+/// The pixel format of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorDepth {
+    /// 4 bytes per pixel: red, green, blue and alpha.
+    Rgba,
+    /// 2 bytes per pixel: value and alpha.
+    Grayscale,
+    /// 1 byte per pixel: an index into the palette.
+    Indexed,
+}
+
+impl ColorDepth {
+    pub const fn bytes_per_pixel(self) -> usize {
+        match self {
+            Self::Rgba => 4,
+            Self::Grayscale => 2,
+            Self::Indexed => 1,
+        }
+    }
+}
+
+/// The kind of a layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LayerKind {
+    Normal,
+    Group,
+    /// A layer of tiles from the tileset with the given index.
+    Tilemap(u32),
+}
+
+/// How a layer is blended with the layers below it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+    Addition,
+    Subtract,
+    Divide,
+}
+
+impl BlendMode {
+    fn from_id(id: u16) -> Self {
+        const MODES: [BlendMode; 19] = [
+            BlendMode::Normal,
+            BlendMode::Multiply,
+            BlendMode::Screen,
+            BlendMode::Overlay,
+            BlendMode::Darken,
+            BlendMode::Lighten,
+            BlendMode::ColorDodge,
+            BlendMode::ColorBurn,
+            BlendMode::HardLight,
+            BlendMode::SoftLight,
+            BlendMode::Difference,
+            BlendMode::Exclusion,
+            BlendMode::Hue,
+            BlendMode::Saturation,
+            BlendMode::Color,
+            BlendMode::Luminosity,
+            BlendMode::Addition,
+            BlendMode::Subtract,
+            BlendMode::Divide,
+        ];
+        MODES.get(id as usize).copied().unwrap_or_default()
+    }
+
+    /// Blends a color channel of the source with the backdrop, both in `0.0..=1.0`.
+    fn blend(self, backdrop: f32, source: f32) -> f32 {
+        let (b, s) = (backdrop, source);
+        match self {
+            Self::Multiply => b * s,
+            Self::Screen => b + s - b * s,
+            Self::Overlay => Self::HardLight.blend(s, b),
+            Self::Darken => b.min(s),
+            Self::Lighten => b.max(s),
+            Self::ColorDodge if b == 0.0 => 0.0,
+            Self::ColorDodge if s >= 1.0 => 1.0,
+            Self::ColorDodge => (b / (1.0 - s)).min(1.0),
+            Self::ColorBurn if b >= 1.0 => 1.0,
+            Self::ColorBurn if s <= 0.0 => 0.0,
+            Self::ColorBurn => 1.0 - ((1.0 - b) / s).min(1.0),
+            Self::HardLight if s <= 0.5 => Self::Multiply.blend(b, 2.0 * s),
+            Self::HardLight => Self::Screen.blend(b, 2.0 * s - 1.0),
+            Self::SoftLight => {
+                let d = match b <= 0.25 {
+                    true => ((16.0 * b - 12.0) * b + 4.0) * b,
+                    false => b.sqrt(),
+                };
+                match s <= 0.5 {
+                    true => b - (1.0 - 2.0 * s) * b * (1.0 - b),
+                    false => b + (2.0 * s - 1.0) * (d - b),
+                }
+            }
+            Self::Difference => (b - s).abs(),
+            Self::Exclusion => b + s - 2.0 * b * s,
+            Self::Addition => (b + s).min(1.0),
+            Self::Subtract => (b - s).max(0.0),
+            Self::Divide if s == 0.0 => 1.0,
+            Self::Divide => (b / s).min(1.0),
+            // Non-separable modes aren't supported, so they fall back to normal blending.
+            Self::Normal | Self::Hue | Self::Saturation | Self::Color | Self::Luminosity => s,
+        }
+    }
+}
+
+/// A layer, listed from the bottom to the top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layer {
+    pub name: String,
+    pub kind: LayerKind,
+    /// The raw layer flags, see [Layer::is_visible] and [Layer::is_background].
+    pub flags: u16,
+    /// The nesting depth, layers are children of the closest previous layer one level up.
+    pub child_level: u16,
+    pub blend_mode: BlendMode,
+    pub opacity: u8,
+}
+
+impl Layer {
+    pub const fn is_visible(&self) -> bool {
+        self.flags & 1 != 0
+    }
+
+    pub const fn is_background(&self) -> bool {
+        self.flags & 8 != 0
+    }
+
+    /// Reference layers are guides for the artist, and aren't part of the sprite.
+    pub const fn is_reference(&self) -> bool {
+        self.flags & 64 != 0
+    }
+}
+
+/// The contents of a cel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CelContent {
+    /// Pixels in the [ColorDepth] of the file, row by row.
+    Image {
+        width: u16,
+        height: u16,
+        pixels: Vec<u8>,
+    },
+    /// The same image as the cel of this layer in another frame.
+    Linked(u16),
+    /// Tiles of a tilemap layer, which aren't decoded.
+    Tilemap,
+}
+
+/// The image of a layer in a frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cel {
+    /// The index of the layer in [AsepriteFile::layers].
+    pub layer: usize,
+    pub x: i16,
+    pub y: i16,
+    pub opacity: u8,
+    /// Moves the cel up or down in the layer stack of this frame.
+    pub z_index: i16,
+    pub content: CelContent,
+}
+
+/// A frame of the animation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Frame {
+    pub duration: Duration,
+    pub cels: Vec<Cel>,
+}
+
+/// The direction a tagged animation plays in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Direction {
+    #[default]
+    Forward,
+    Reverse,
+    PingPong,
+    PingPongReverse,
+}
+
+/// A named range of frames, usually an animation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tag {
+    pub name: String,
+    /// The first frame, inclusive.
+    pub from: u16,
+    /// The last frame, inclusive.
+    pub to: u16,
+    pub direction: Direction,
+    /// How many times the animation plays, 0 for infinitely.
+    pub repeat: u16,
+}
+
+/// A rectangle in sprite coordinates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// The bounds of a slice, starting at a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SliceKey {
+    pub frame: u32,
+    pub bounds: Rect,
+    /// The center of a 9-patch slice, relative to the bounds.
+    pub center: Option<Rect>,
+    /// The pivot point, relative to the bounds.
+    pub pivot: Option<(i32, i32)>,
+}
+
+/// A named region of the sprite, e.g. a hitbox or a 9-patch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slice {
+    pub name: String,
+    pub keys: Vec<SliceKey>,
+}
+
+/// A parsed `.aseprite` or `.ase` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsepriteFile {
+    pub width: u16,
+    pub height: u16,
+    pub color_depth: ColorDepth,
+    /// The palette index that is transparent in indexed files.
+    pub transparent_index: u8,
+    pub layers: Vec<Layer>,
+    pub frames: Vec<Frame>,
+    pub tags: Vec<Tag>,
+    pub slices: Vec<Slice>,
+    /// Palette colors as RGBA.
+    pub palette: Vec<[u8; 4]>,
+}
+
+/// Reads little-endian values from a chunk.
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let (bytes, rest) = self.data.split_at_checked(len)?;
+        self.data = rest;
+        Some(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.bytes(N)?.try_into().ok()
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        self.array::<1>().map(|[byte]| byte)
+    }
+
+    fn word(&mut self) -> Option<u16> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    fn short(&mut self) -> Option<i16> {
+        self.array().map(i16::from_le_bytes)
+    }
+
+    fn dword(&mut self) -> Option<u32> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn long(&mut self) -> Option<i32> {
+        self.array().map(i32::from_le_bytes)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = self.word()?;
+        let bytes = self.bytes(len as usize)?;
+        Some(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    fn rect(&mut self) -> Option<Rect> {
+        Some(Rect {
+            x: self.long()?,
+            y: self.long()?,
+            width: self.dword()?,
+            height: self.dword()?,
+        })
+    }
+}
+
+impl AsepriteFile {
+    /// Parses a file from a stream.
+    ///
+    /// # Returns
+    /// * `Ok(AsepriteFile)` if the file is successfully parsed.
+    /// * `Err(AsepriteError)` if the file is malformed or uses an unsupported color depth.
+    pub fn parse(mut read: impl Read) -> Result<Self, AsepriteError> {
+        let mut header = [0; HEADER_SIZE];
+        read.read_exact(&mut header)?;
+        let mut header = Reader { data: &header };
+
+        // The header is 128 bytes long, so reading its fields can't fail.
+        let _file_size = header.dword();
+        let magic = header.word().unwrap_or_default();
+        if magic != FILE_MAGIC {
+            return Err(AsepriteError::Magic(magic));
+        }
+        let frame_count = header.word().unwrap_or_default();
+        let width = header.word().unwrap_or_default();
+        let height = header.word().unwrap_or_default();
+        check_size(width, height)?;
+        let color_depth = match header.word().unwrap_or_default() {
+            32 => ColorDepth::Rgba,
+            16 => ColorDepth::Grayscale,
+            8 => ColorDepth::Indexed,
+            depth => return Err(AsepriteError::ColorDepth(depth)),
+        };
+        let flags = header.dword().unwrap_or_default();
+        header.bytes(10);
+        let transparent_index = header.byte().unwrap_or_default();
+
+        let mut file = Self {
+            width,
+            height,
+            color_depth,
+            transparent_index,
+            layers: Vec::new(),
+            frames: Vec::with_capacity(frame_count as usize),
+            tags: Vec::new(),
+            slices: Vec::new(),
+            palette: Vec::new(),
+        };
+        // Bytes of cel pixels decoded so far, across all frames.
+        let mut decoded = 0;
+
+        for index in 0..frame_count as usize {
+            let mut frame_header = [0; FRAME_HEADER_SIZE];
+            read.read_exact(&mut frame_header)?;
+            let mut frame_header = Reader {
+                data: &frame_header,
+            };
+
+            let _frame_size = frame_header.dword();
+            let magic = frame_header.word().unwrap_or_default();
+            if magic != FRAME_MAGIC {
+                return Err(AsepriteError::FrameMagic(index, magic));
+            }
+            let old_chunks = frame_header.word().unwrap_or_default();
+            let duration = frame_header.word().unwrap_or_default();
+            frame_header.bytes(2);
+            let chunks = match frame_header.dword().unwrap_or_default() {
+                0 => old_chunks as u32,
+                chunks => chunks,
+            };
+
+            file.frames.push(Frame {
+                duration: Duration::from_millis(duration.into()),
+                cels: Vec::new(),
+            });
+            for _ in 0..chunks {
+                file.read_chunk(&mut read, index, flags, &mut decoded)?;
+            }
+        }
+
+        Ok(file)
+    }
+
+    fn read_chunk(
+        &mut self,
+        read: &mut impl Read,
+        frame: usize,
+        flags: u32,
+        decoded: &mut usize,
+    ) -> Result<(), AsepriteError> {
+        let mut header = [0; CHUNK_HEADER_SIZE];
+        read.read_exact(&mut header)?;
+        let size = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let chunk = u16::from_le_bytes([header[4], header[5]]);
+
+        let len = (size as usize).saturating_sub(CHUNK_HEADER_SIZE);
+        // The buffer grows with the data actually read, so a bogus size can't exhaust memory.
+        let mut data = Vec::new();
+        read.take(len as u64).read_to_end(&mut data)?;
+        if data.len() != len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        let mut reader = Reader { data: &data };
+        let parsed = match chunk {
+            CHUNK_LAYER => self.read_layer(&mut reader, flags),
+            CHUNK_CEL => match self.read_cel(&mut reader, frame, decoded)? {
+                Some(cel) => {
+                    self.frames[frame].cels.push(cel);
+                    Some(())
+                }
+                None => None,
+            },
+            CHUNK_TAGS => self.read_tags(&mut reader),
+            CHUNK_PALETTE => self.read_palette(&mut reader),
+            // Newer files contain both palette chunks, the old one only matters when it's alone.
+            CHUNK_OLD_PALETTE if self.palette.is_empty() => self.read_old_palette(&mut reader),
+            CHUNK_SLICE => self.read_slice(&mut reader),
+            _ => Some(()),
+        };
+        parsed.ok_or(AsepriteError::Truncated { frame, chunk })
+    }
+
+    fn read_layer(&mut self, reader: &mut Reader, flags: u32) -> Option<()> {
+        let layer_flags = reader.word()?;
+        let kind = reader.word()?;
+        let child_level = reader.word()?;
+        reader.bytes(4)?;
+        let blend_mode = BlendMode::from_id(reader.word()?);
+        let opacity = reader.byte()?;
+        reader.bytes(3)?;
+        let name = reader.string()?;
+        let kind = match kind {
+            1 => LayerKind::Group,
+            2 => LayerKind::Tilemap(reader.dword()?),
+            _ => LayerKind::Normal,
+        };
+
+        self.layers.push(Layer {
+            name,
+            kind,
+            flags: layer_flags,
+            child_level,
+            blend_mode,
+            opacity: match flags & FLAG_LAYER_OPACITY {
+                0 => u8::MAX,
+                _ => opacity,
+            },
+        });
+        Some(())
+    }
+
+    /// Reads a cel and adds its pixels to `decoded`. The outer `Option` is `None` if the chunk is truncated.
+    fn read_cel(
+        &self,
+        reader: &mut Reader,
+        frame: usize,
+        decoded: &mut usize,
+    ) -> Result<Option<Cel>, AsepriteError> {
+        let Some((layer, x, y, opacity, kind, z_index)) = (|| {
+            let header = (
+                reader.word()?,
+                reader.short()?,
+                reader.short()?,
+                reader.byte()?,
+                reader.word()?,
+                reader.short()?,
+            );
+            reader.bytes(5)?;
+            Some(header)
+        })() else {
+            return Ok(None);
+        };
+
+        let content = match kind {
+            0 | 2 => {
+                let (Some(width), Some(height)) = (reader.word(), reader.word()) else {
+                    return Ok(None);
+                };
+                // Compressed cels could otherwise expand into gigabytes.
+                check_size(width, height)?;
+                let expected =
+                    width as usize * height as usize * self.color_depth.bytes_per_pixel();
+                *decoded += expected;
+                if *decoded > MAX_DECODED_BYTES {
+                    return Err(AsepriteError::TooMuchData { frame });
+                }
+                let pixels = match kind {
+                    0 => reader.data.get(..expected).unwrap_or(reader.data).to_vec(),
+                    _ => {
+                        let mut pixels = Vec::new();
+                        ZlibDecoder::new(reader.data)
+                            .take(expected as u64)
+                            .read_to_end(&mut pixels)?;
+                        pixels
+                    }
+                };
+                if pixels.len() != expected {
+                    return Err(AsepriteError::CelSize {
+                        frame,
+                        expected,
+                        found: pixels.len(),
+                    });
+                }
+                CelContent::Image {
+                    width,
+                    height,
+                    pixels,
+                }
+            }
+            1 => match reader.word() {
+                Some(frame) => CelContent::Linked(frame),
+                None => return Ok(None),
+            },
+            _ => CelContent::Tilemap,
+        };
+
+        Ok(Some(Cel {
+            layer: layer as usize,
+            x,
+            y,
+            opacity,
+            z_index,
+            content,
+        }))
+    }
+
+    fn read_tags(&mut self, reader: &mut Reader) -> Option<()> {
+        let count = reader.word()?;
+        reader.bytes(8)?;
+
+        for _ in 0..count {
+            let from = reader.word()?;
+            let to = reader.word()?;
+            let direction = match reader.byte()? {
+                1 => Direction::Reverse,
+                2 => Direction::PingPong,
+                3 => Direction::PingPongReverse,
+                _ => Direction::Forward,
+            };
+            let repeat = reader.word()?;
+            reader.bytes(10)?;
+            let name = reader.string()?;
+
+            self.tags.push(Tag {
+                name,
+                from,
+                to,
+                direction,
+                repeat,
+            });
+        }
+        Some(())
+    }
+
+    fn set_color(&mut self, index: usize, color: [u8; 4]) {
+        if index >= MAX_PALETTE_SIZE {
+            return;
+        }
+        if self.palette.len() <= index {
+            self.palette.resize(index + 1, [0; 4]);
+        }
+        self.palette[index] = color;
+    }
+
+    fn read_palette(&mut self, reader: &mut Reader) -> Option<()> {
+        let _size = reader.dword()?;
+        let first = reader.dword()?;
+        let last = reader.dword()?;
+        reader.bytes(8)?;
+
+        for index in first..=last {
+            let flags = reader.word()?;
+            let color = reader.array()?;
+            if flags & 1 != 0 {
+                let _name = reader.string()?;
+            }
+            self.set_color(index as usize, color);
+        }
+        Some(())
+    }
+
+    fn read_old_palette(&mut self, reader: &mut Reader) -> Option<()> {
+        let packets = reader.word()?;
+        let mut index = 0;
+
+        for _ in 0..packets {
+            index += reader.byte()? as usize;
+            let count = match reader.byte()? {
+                0 => 256,
+                count => count as usize,
+            };
+            for _ in 0..count {
+                let [r, g, b] = reader.array()?;
+                self.set_color(index, [r, g, b, u8::MAX]);
+                index += 1;
+            }
+        }
+        Some(())
+    }
+
+    fn read_slice(&mut self, reader: &mut Reader) -> Option<()> {
+        let count = reader.dword()?;
+        let flags = reader.dword()?;
+        reader.bytes(4)?;
+        let name = reader.string()?;
+
+        let mut keys = Vec::new();
+        for _ in 0..count {
+            let frame = reader.dword()?;
+            let bounds = reader.rect()?;
+            let center = match flags & 1 {
+                0 => None,
+                _ => Some(reader.rect()?),
+            };
+            let pivot = match flags & 2 {
+                0 => None,
+                _ => Some((reader.long()?, reader.long()?)),
+            };
+            keys.push(SliceKey {
+                frame,
+                bounds,
+                center,
+                pivot,
+            });
+        }
+
+        self.slices.push(Slice { name, keys });
+        Some(())
+    }
+
+    /// Returns the tag with the given name.
+    pub fn tag(&self, name: &str) -> Option<&Tag> {
+        self.tags.iter().find(|tag| tag.name == name)
+    }
+
+    /// Returns the slice with the given name.
+    pub fn slice(&self, name: &str) -> Option<&Slice> {
+        self.slices.iter().find(|slice| slice.name == name)
+    }
+
+    /// Returns whether each layer is drawn, taking hidden groups into account.
+    fn visible_layers(&self) -> Vec<bool> {
+        // Visibility of the enclosing groups, by child level.
+        let mut groups: Vec<bool> = Vec::new();
+
+        self.layers
+            .iter()
+            .map(|layer| {
+                groups.truncate(layer.child_level as usize);
+                let parent = groups.last().copied().unwrap_or(true);
+                let visible = parent && layer.is_visible() && !layer.is_reference();
+                if layer.kind == LayerKind::Group {
+                    groups.push(visible);
+                }
+                visible
+            })
+            .collect()
+    }
+
+    /// Returns the pixels of a cel, following a link to another frame.
+    fn cel_image<'c>(&'c self, cel: &'c Cel) -> Option<(u16, u16, &'c [u8])> {
+        match &cel.content {
+            CelContent::Image {
+                width,
+                height,
+                pixels,
+            } => Some((*width, *height, pixels)),
+            CelContent::Linked(frame) => {
+                let linked = self
+                    .frames
+                    .get(*frame as usize)?
+                    .cels
+                    .iter()
+                    .find(|linked| linked.layer == cel.layer)?;
+                // Links point at cels with images, so they are followed only once.
+                match &linked.content {
+                    CelContent::Image {
+                        width,
+                        height,
+                        pixels,
+                    } => Some((*width, *height, pixels)),
+                    _ => None,
+                }
+            }
+            CelContent::Tilemap => None,
+        }
+    }
+
+    /// Converts a pixel of a cel into RGBA.
+    fn color(&self, pixel: &[u8], background: bool) -> [u8; 4] {
+        match (self.color_depth, pixel) {
+            (ColorDepth::Rgba, &[r, g, b, a]) => [r, g, b, a],
+            (ColorDepth::Grayscale, &[value, alpha]) => [value, value, value, alpha],
+            // The transparent index is a regular color on the background layer.
+            (ColorDepth::Indexed, &[index]) if index == self.transparent_index && !background => {
+                [0; 4]
+            }
+            (ColorDepth::Indexed, &[index]) => {
+                self.palette.get(index as usize).copied().unwrap_or([0; 4])
+            }
+            _ => [0; 4],
+        }
+    }
+
+    /// Composites a frame from its visible layers.
+    ///
+    /// # Returns
+    /// * `Some(Vec<u8>)` with `width * height` RGBA pixels, row by row.
+    /// * `None` if there is no such frame.
+    pub fn frame_rgba(&self, index: usize) -> Option<Vec<u8>> {
+        let frame = self.frames.get(index)?;
+        let visible = self.visible_layers();
+
+        let mut cels = frame
+            .cels
+            .iter()
+            .filter(|cel| visible.get(cel.layer).copied().unwrap_or(false))
+            .collect::<Vec<_>>();
+        // Z-indices move cels in the layer stack, and cels at the same position are ordered by them.
+        cels.sort_by_key(|cel| (cel.layer as i64 + cel.z_index as i64, cel.z_index));
+
+        let (width, height) = (self.width as usize, self.height as usize);
+        let mut canvas = vec![0; width * height * 4];
+
+        for cel in cels {
+            let Some((cel_width, cel_height, pixels)) = self.cel_image(cel) else {
+                continue;
+            };
+            let layer = &self.layers[cel.layer];
+            let opacity = (cel.opacity as f32 / 255.0) * (layer.opacity as f32 / 255.0);
+            let depth = self.color_depth.bytes_per_pixel();
+
+            for row in 0..cel_height as usize {
+                let y = cel.y as isize + row as isize;
+                if !(0..height as isize).contains(&y) {
+                    continue;
+                }
+                for column in 0..cel_width as usize {
+                    let x = cel.x as isize + column as isize;
+                    if !(0..width as isize).contains(&x) {
+                        continue;
+                    }
+
+                    let offset = (row * cel_width as usize + column) * depth;
+                    let source = self.color(&pixels[offset..offset + depth], layer.is_background());
+                    let target = (y as usize * width + x as usize) * 4;
+                    let backdrop = &mut canvas[target..target + 4];
+                    composite(backdrop, source, opacity, layer.blend_mode);
+                }
+            }
+        }
+
+        Some(canvas)
+    }
+
+    /// Composites a frame into an image.
+    ///
+    /// # Returns
+    /// * `Some(DynamicImage)` with an RGBA image of the sprite size.
+    /// * `None` if there is no such frame.
+    #[cfg(feature = "image")]
+    pub fn frame_image(&self, index: usize) -> Option<image::DynamicImage> {
+        let pixels = self.frame_rgba(index)?;
+        image::RgbaImage::from_raw(self.width.into(), self.height.into(), pixels)
+            .map(image::DynamicImage::ImageRgba8)
+    }
+
+    /// Composites every frame into an image.
+    #[cfg(feature = "image")]
+    pub fn frame_images(&self) -> Vec<image::DynamicImage> {
+        (0..self.frames.len())
+            .filter_map(|index| self.frame_image(index))
+            .collect()
+    }
+}
+
+/// Checks that an image isn't larger than [MAX_PIXELS].
+fn check_size(width: u16, height: u16) -> Result<(), AsepriteError> {
+    match width as usize * height as usize > MAX_PIXELS {
+        true => Err(AsepriteError::TooLarge { width, height }),
+        false => Ok(()),
+    }
+}
+
+/// Blends a source pixel over a backdrop pixel in place.
+fn composite(backdrop: &mut [u8], source: [u8; 4], opacity: f32, mode: BlendMode) {
+    let source_alpha = source[3] as f32 / 255.0 * opacity;
+    if source_alpha <= 0.0 {
+        return;
+    }
+    let backdrop_alpha = backdrop[3] as f32 / 255.0;
+    let alpha = source_alpha + backdrop_alpha * (1.0 - source_alpha);
+
+    for channel in 0..3 {
+        let b = backdrop[channel] as f32 / 255.0;
+        let s = source[channel] as f32 / 255.0;
+        // The blend mode only applies where the backdrop is opaque.
+        let s = (1.0 - backdrop_alpha) * s + backdrop_alpha * mode.blend(b, s);
+        let color = (source_alpha * s + backdrop_alpha * b * (1.0 - source_alpha)) / alpha;
+        backdrop[channel] = (color * 255.0).round() as u8;
+    }
+    backdrop[3] = (alpha * 255.0).round() as u8;
+}
+
+impl Asset for AsepriteFile {
+    type Error = AsepriteError;
+
+    fn load(pack: &mut impl Pack, path: impl AsRef<Path>) -> AssetResult<Self> {
+        let raw = pack.get_raw(path.as_ref())?;
+        Self::parse(raw.read)
+    }
+}
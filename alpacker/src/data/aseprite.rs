@@ -3,19 +3,23 @@ The term "Aseprite" is used solely to refer to the file format exported by the A
 This project is not an official product, nor is it affiliated with or endorsed by the developers of Aseprite.
 All trademarks and logos, including "Aseprite", are the property of their respective owners. */
 
-/* This module provides functionality for loading sprite assets that combine metadata (like Aseprite spritesheets)
-and their associated image data. It handles path resolution, error propagation, and asset loading in a generic way. */
+/* This module provides Aseprite's JSON sprite sheet export (`SpritesheetData`) as sprite metadata.
+Sprites, animations and cropping are format-independent and live in `data::sprite`, they're re-exported
+here so existing paths keep working. */
 
-use aseprite::SpritesheetData;
+pub use aseprite::SpritesheetData;
 use std::{
-    error::Error,
-    io,
     path::{Path, PathBuf},
+    time::Duration,
 };
-use thiserror::Error;
 
+use super::sprite::{Direction, FrameTag, Rect, Rotation, SpriteFrame};
+pub use super::sprite::{SpriteError, SpriteMeta};
 use crate::{Asset, AssetResult, JsonIoError, Pack};
 
+/// A sprite with Aseprite metadata by default. See [`Sprite`](super::sprite::Sprite).
+pub type Sprite<I, M = SpritesheetData> = super::sprite::Sprite<I, M>;
+
 // Implementation of Asset trait for Aseprite SpritesheetData
 impl Asset for SpritesheetData {
     type Error = JsonIoError;
@@ -26,59 +30,48 @@ impl Asset for SpritesheetData {
     }
 }
 
-/// Trait for assets that can provide associated image paths
-pub trait SpriteMeta: Asset {
-    fn image_path(&self, meta_path: impl AsRef<Path>) -> Option<PathBuf>;
-}
-
 impl SpriteMeta for SpritesheetData {
     fn image_path(&self, meta_path: impl AsRef<Path>) -> Option<PathBuf> {
         let empty = PathBuf::new();
-
-        match &self.meta.image {
-            Some(image) => Some(meta_path.as_ref().parent().unwrap_or(&empty).join(image)),
-            None => None,
-        }
+        let dir = meta_path.as_ref().parent().unwrap_or(&empty);
+        self.meta.image.as_ref().map(|image| dir.join(image))
     }
-}
-
-/// Container combining loaded metadata and its associated image
-pub struct Sprite<I: Asset, M: Asset = SpritesheetData>
-where
-    I::Error: Error,
-    M::Error: Error,
-{
-    pub image: Option<I>,
-    pub meta: M,
-}
 
-/// Unified error type for sprite loading operations
-#[derive(Debug, Error)]
-pub enum SpriteError<M: Error, I: Error> {
-    #[error("I/O error: {0}")]
-    Io(#[from] io::Error),
-
-    #[error("Metadata error: {0}")]
-    Meta(M),
-
-    #[error("Image error: {0}")]
-    Image(I),
-}
+    fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
 
-impl<I: Asset, M: SpriteMeta> Asset for Sprite<I, M>
-where
-    I::Error: Error,
-    M::Error: Error,
-{
-    type Error = SpriteError<M::Error, I::Error>;
+    fn frame(&self, index: usize) -> Option<SpriteFrame> {
+        let frame = self.frames.get(index)?;
+        let rect = frame.frame;
 
-    fn load(pack: &mut impl Pack, path: impl AsRef<Path>) -> AssetResult<Self> {
-        let meta = M::load(pack, &path).map_err(SpriteError::Meta)?;
-        let image = match meta.image_path(path) {
-            Some(path) => Some(I::load(pack, path).map_err(SpriteError::Image)?),
-            None => None,
-        };
+        Some(SpriteFrame {
+            index,
+            page: 0,
+            rect: Rect {
+                x: rect.x,
+                y: rect.y,
+                width: rect.w,
+                height: rect.h,
+            },
+            // Aseprite never rotates frames in its sprite sheets
+            rotated: Rotation::None,
+            duration: Duration::from_millis(frame.duration.into()),
+        })
+    }
 
-        Ok(Self { image, meta })
+    fn tags(&self) -> Vec<FrameTag> {
+        let tags = self.meta.frame_tags.iter().flatten();
+        tags.map(|tag| FrameTag {
+            name: tag.name.clone(),
+            from: tag.from as usize,
+            to: tag.to as usize,
+            direction: match tag.direction {
+                aseprite::Direction::Forward => Direction::Forward,
+                aseprite::Direction::Reverse => Direction::Reverse,
+                aseprite::Direction::Pingpong => Direction::PingPong,
+            },
+        })
+        .collect()
     }
 }
@@ -0,0 +1,559 @@
+/* This module provides texture atlases packed by TexturePacker (JSON hash, array and multi-pack exports)
+and libGDX (`.atlas` text files) as sprite metadata. Both formats are parsed into the same `Atlas`,
+which lists the image pages and the regions packed into them. */
+
+use serde::{
+    Deserialize, Deserializer,
+    de::{MapAccess, Visitor},
+};
+use std::{
+    collections::BTreeMap,
+    fmt, io,
+    io::Read,
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use thiserror::Error;
+
+use super::sprite::{Direction, FrameTag, Rect, Rotation, SpriteFrame, SpriteMeta};
+use crate::{Asset, AssetResult, Pack};
+
+/// An image page of an atlas.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AtlasPage {
+    /// The path of the image, relative to the directory of the atlas file.
+    pub image: PathBuf,
+    /// The size of the image in pixels, if the atlas specifies it.
+    pub size: Option<(u32, u32)>,
+}
+
+/// A named area packed into an atlas page.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AtlasRegion {
+    pub name: String,
+    /// The index of the page the region is on.
+    pub page: usize,
+    /// The area of the region in the page image. The width and height of rotated regions are swapped.
+    pub rect: Rect,
+    /// How the region was turned when packed: TexturePacker turns regions clockwise,
+    /// libGDX counter-clockwise.
+    pub rotated: Rotation,
+    /// The index of the region among the regions with the same name, if it's a frame of an animation.
+    pub index: Option<u32>,
+}
+
+/// Pages and regions of a texture atlas, in the order they're listed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Atlas {
+    pub pages: Vec<AtlasPage>,
+    pub regions: Vec<AtlasRegion>,
+    pub tags: Vec<FrameTag>,
+}
+
+impl Atlas {
+    /// Returns the first region with the given name.
+    pub fn region(&self, name: &str) -> Option<&AtlasRegion> {
+        self.regions.iter().find(|region| region.name == name)
+    }
+
+    /// Returns the position of the first region with the given name, usable as a frame index.
+    pub fn region_index(&self, name: &str) -> Option<usize> {
+        self.regions.iter().position(|region| region.name == name)
+    }
+
+    /// Appends the pages and regions of another atlas, e.g. another page of a multi-pack export.
+    pub fn extend(&mut self, other: Atlas) {
+        let (pages, regions) = (self.pages.len(), self.regions.len());
+        self.pages.extend(other.pages);
+        self.regions
+            .extend(other.regions.into_iter().map(|region| AtlasRegion {
+                page: region.page + pages,
+                ..region
+            }));
+        self.tags.extend(other.tags.into_iter().map(|tag| FrameTag {
+            from: tag.from + regions,
+            to: tag.to + regions,
+            ..tag
+        }));
+    }
+
+    fn image_paths(&self, meta_path: &Path) -> Vec<PathBuf> {
+        let dir = meta_path.parent().unwrap_or(Path::new(""));
+        self.pages
+            .iter()
+            .map(|page| dir.join(&page.image))
+            .collect()
+    }
+
+    fn frame(&self, index: usize) -> Option<SpriteFrame> {
+        let region = self.regions.get(index)?;
+        Some(SpriteFrame {
+            index,
+            page: region.page,
+            rect: region.rect,
+            rotated: region.rotated,
+            // Atlases don't store frame timing
+            duration: Duration::ZERO,
+        })
+    }
+
+    /// Tags runs of regions that share a name and have indices, as libGDX animations do.
+    fn tag_indexed_runs(&mut self) {
+        let mut from = 0;
+        for (i, region) in self.regions.iter().enumerate() {
+            let next = self.regions.get(i + 1);
+            if next.is_some_and(|next| next.name == region.name && next.index.is_some()) {
+                continue;
+            }
+            if region.index.is_some() && self.regions[from].name == region.name {
+                self.tags.push(FrameTag {
+                    name: region.name.clone(),
+                    from,
+                    to: i,
+                    direction: Direction::Forward,
+                });
+            }
+            from = i + 1;
+        }
+    }
+}
+
+/// Error type for atlas loading operations
+#[derive(Debug, Error)]
+pub enum AtlasError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Invalid TexturePacker atlas {path:?}: {source}")]
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+
+    #[error("Invalid libGDX atlas {path:?} at line {line}: {message}")]
+    Syntax {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+impl AtlasError {
+    /// Returns the path of the atlas file the error occurred in, if known.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Io(_) => None,
+            Self::Json { path, .. } | Self::Syntax { path, .. } => Some(path),
+        }
+    }
+}
+
+impl From<AtlasError> for crate::Error {
+    fn from(err: AtlasError) -> Self {
+        match err {
+            AtlasError::Io(err) => err.into(),
+            err => Self::asset(err.path().map(Path::to_path_buf), err),
+        }
+    }
+}
+
+macro_rules! atlas_format {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Default, PartialEq, Eq)]
+        pub struct $name(pub Atlas);
+
+        impl $name {
+            pub fn into_inner(self) -> Atlas {
+                self.0
+            }
+        }
+
+        impl Deref for $name {
+            type Target = Atlas;
+
+            #[inline(always)]
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl DerefMut for $name {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.0
+            }
+        }
+
+        impl SpriteMeta for $name {
+            fn image_path(&self, meta_path: impl AsRef<Path>) -> Option<PathBuf> {
+                self.image_paths(meta_path).into_iter().next()
+            }
+
+            fn image_paths(&self, meta_path: impl AsRef<Path>) -> Vec<PathBuf> {
+                self.0.image_paths(meta_path.as_ref())
+            }
+
+            fn frame_count(&self) -> usize {
+                self.regions.len()
+            }
+
+            fn frame(&self, index: usize) -> Option<SpriteFrame> {
+                self.0.frame(index)
+            }
+
+            fn tags(&self) -> Vec<FrameTag> {
+                self.0.tags.clone()
+            }
+        }
+    };
+}
+
+atlas_format!(
+    /// A TexturePacker JSON atlas, in the hash or array layout.
+    ///
+    /// Multi-pack exports are loaded whole: the atlases listed in `meta.related_multi_packs` are
+    /// read from the same pack and appended as further pages. Phaser 3 multi-atlases, which list
+    /// their pages in `textures`, are supported too. Animations from `meta.animations` become tags
+    /// when their frames are listed in a row.
+    TexturePackerAtlas
+);
+
+atlas_format!(
+    /// A libGDX `.atlas` text file, in the legacy or the compact (libGDX 1.9.13+) layout.
+    ///
+    /// Regions that share a name and have an `index` become a tag with that name.
+    GdxAtlas
+);
+
+#[derive(Deserialize)]
+struct TpFile {
+    frames: Option<TpFrames>,
+    #[serde(default)]
+    textures: Vec<TpTexture>,
+    #[serde(default)]
+    meta: TpMeta,
+}
+
+#[derive(Deserialize)]
+struct TpTexture {
+    image: String,
+    size: Option<TpSize>,
+    frames: TpFrames,
+}
+
+#[derive(Deserialize, Default)]
+struct TpMeta {
+    image: Option<String>,
+    size: Option<TpSize>,
+    #[serde(default)]
+    related_multi_packs: Vec<String>,
+    #[serde(default)]
+    animations: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+struct TpSize {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+struct TpRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+struct TpFrame {
+    frame: TpRect,
+    #[serde(default)]
+    rotated: bool,
+}
+
+#[derive(Deserialize)]
+struct TpNamedFrame {
+    filename: String,
+    #[serde(flatten)]
+    frame: TpFrame,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TpFrames {
+    Array(Vec<TpNamedFrame>),
+    Hash(TpFrameMap),
+}
+
+/// Frames of the hash layout, in the order they're listed.
+struct TpFrameMap(Vec<(String, TpFrame)>);
+
+impl<'de> Deserialize<'de> for TpFrameMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FrameMapVisitor;
+
+        impl<'de> Visitor<'de> for FrameMapVisitor {
+            type Value = TpFrameMap;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map of frame names to frames")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut frames = Vec::with_capacity(map.size_hint().unwrap_or(0));
+                while let Some(entry) = map.next_entry()? {
+                    frames.push(entry);
+                }
+                Ok(TpFrameMap(frames))
+            }
+        }
+
+        deserializer.deserialize_map(FrameMapVisitor)
+    }
+}
+
+impl TpFrames {
+    fn into_regions(self, page: usize) -> impl Iterator<Item = AtlasRegion> {
+        let frames = match self {
+            Self::Array(frames) => frames.into_iter().map(|f| (f.filename, f.frame)).collect(),
+            Self::Hash(TpFrameMap(frames)) => frames,
+        };
+
+        frames.into_iter().map(move |(name, frame)| {
+            let TpRect { x, y, w, h } = frame.frame;
+            // The frame size is the size of the sprite, which is stored turned in rotated frames
+            let (width, height) = if frame.rotated { (h, w) } else { (w, h) };
+            let rotated = match frame.rotated {
+                true => Rotation::Clockwise,
+                false => Rotation::None,
+            };
+
+            AtlasRegion {
+                name,
+                page,
+                rect: Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                },
+                rotated,
+                index: None,
+            }
+        })
+    }
+}
+
+impl TpFile {
+    fn into_atlas(self) -> Atlas {
+        let mut atlas = Atlas::default();
+        let size = |size: Option<TpSize>| size.map(|size| (size.w, size.h));
+
+        if let Some(image) = self.meta.image {
+            atlas.pages.push(AtlasPage {
+                image: image.into(),
+                size: size(self.meta.size),
+            });
+        }
+        if let Some(frames) = self.frames {
+            atlas.regions.extend(frames.into_regions(0));
+        }
+
+        for texture in self.textures {
+            let page = atlas.pages.len();
+            atlas.pages.push(AtlasPage {
+                image: texture.image.into(),
+                size: size(texture.size),
+            });
+            atlas.regions.extend(texture.frames.into_regions(page));
+        }
+
+        for (name, frames) in self.meta.animations {
+            let indices = frames.iter().map(|frame| atlas.region_index(frame));
+            let indices = indices.collect::<Option<Vec<_>>>().unwrap_or_default();
+
+            if let (Some(&from), Some(&to)) = (indices.first(), indices.last())
+                && indices
+                    .iter()
+                    .enumerate()
+                    .all(|(i, &index)| index == from + i)
+            {
+                atlas.tags.push(FrameTag {
+                    name,
+                    from,
+                    to,
+                    direction: Direction::Forward,
+                });
+            }
+        }
+
+        atlas
+    }
+}
+
+impl TexturePackerAtlas {
+    /// Parses a single TexturePacker JSON file, returning it with its related multi-pack files.
+    fn parse(read: impl Read, path: impl AsRef<Path>) -> Result<(Self, Vec<String>), AtlasError> {
+        let mut file: TpFile =
+            serde_json::from_reader(read).map_err(|source| AtlasError::Json {
+                path: path.as_ref().to_path_buf(),
+                source,
+            })?;
+
+        let related = std::mem::take(&mut file.meta.related_multi_packs);
+        Ok((Self(file.into_atlas()), related))
+    }
+}
+
+impl Asset for TexturePackerAtlas {
+    type Error = AtlasError;
+
+    fn load(pack: &mut impl Pack, path: impl AsRef<Path>) -> AssetResult<Self> {
+        let path = path.as_ref();
+        let (mut atlas, related) = Self::parse(pack.get_raw(path)?.read, path)?;
+
+        // Related files list each other, only the ones of the loaded file are followed
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for related in related {
+            let path = dir.join(related);
+            let (other, _) = Self::parse(pack.get_raw(&path)?.read, &path)?;
+            atlas.extend(other.0);
+        }
+
+        Ok(atlas)
+    }
+}
+
+impl GdxAtlas {
+    /// Parses a libGDX `.atlas` file.
+    ///
+    /// # Arguments
+    /// * `text` - The contents of the file.
+    /// * `path` - The path of the file, used in errors.
+    pub fn parse(text: &str, path: impl AsRef<Path>) -> Result<Self, AtlasError> {
+        let mut atlas = Atlas::default();
+        // Whether a page is open, and whether it already has regions
+        let mut page_open = false;
+        let mut region_open = false;
+
+        for (i, line) in text.lines().enumerate() {
+            let syntax = |message: String| AtlasError::Syntax {
+                path: path.as_ref().to_path_buf(),
+                line: i + 1,
+                message,
+            };
+
+            let line = line.trim();
+            if line.is_empty() {
+                // A blank line ends the page
+                (page_open, region_open) = (false, false);
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once(':') else {
+                if page_open {
+                    atlas.regions.push(AtlasRegion {
+                        name: line.to_owned(),
+                        page: atlas.pages.len() - 1,
+                        rect: Rect::default(),
+                        rotated: Rotation::None,
+                        index: None,
+                    });
+                    region_open = true;
+                } else {
+                    atlas.pages.push(AtlasPage {
+                        image: line.into(),
+                        size: None,
+                    });
+                    page_open = true;
+                }
+                continue;
+            };
+
+            let (key, value) = (key.trim(), value.trim());
+            if !page_open {
+                return Err(syntax(format!("`{key}` is outside of a page")));
+            }
+
+            let numbers = || {
+                value
+                    .split(',')
+                    .map(|n| n.trim().parse::<i64>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|err| syntax(format!("invalid `{key}` value `{value}`: {err}")))
+            };
+            let pair = || match numbers()?[..] {
+                [a, b] => Ok((to_u32(a), to_u32(b))),
+                _ => Err(syntax(format!("`{key}` must have 2 values"))),
+            };
+
+            if !region_open {
+                if key == "size" {
+                    atlas.pages.last_mut().unwrap().size = Some(pair()?);
+                }
+                // Other page fields (format, filter, repeat, pma) don't affect the layout
+                continue;
+            }
+
+            let region = atlas.regions.last_mut().unwrap();
+            match key {
+                "xy" => (region.rect.x, region.rect.y) = pair()?,
+                "size" => (region.rect.width, region.rect.height) = pair()?,
+                "bounds" => match numbers()?[..] {
+                    [x, y, width, height] => {
+                        region.rect = Rect {
+                            x: to_u32(x),
+                            y: to_u32(y),
+                            width: to_u32(width),
+                            height: to_u32(height),
+                        }
+                    }
+                    _ => return Err(syntax("`bounds` must have 4 values".to_owned())),
+                },
+                "rotate" => {
+                    region.rotated = match value {
+                        "true" | "90" => Rotation::CounterClockwise,
+                        "false" | "0" => Rotation::None,
+                        _ => return Err(syntax(format!("invalid `rotate` value `{value}`"))),
+                    }
+                }
+                "index" => match numbers()?[..] {
+                    [index] => region.index = u32::try_from(index).ok(),
+                    _ => return Err(syntax("`index` must have 1 value".to_owned())),
+                },
+                // orig, offset, offsets, split and pad describe trimming and nine-patches
+                _ => {}
+            }
+        }
+
+        for region in &mut atlas.regions {
+            // Sizes are of the sprite, which is stored turned in rotated regions
+            if region.rotated != Rotation::None {
+                let rect = &mut region.rect;
+                (rect.width, rect.height) = (rect.height, rect.width);
+            }
+        }
+        atlas.tag_indexed_runs();
+
+        Ok(Self(atlas))
+    }
+}
+
+fn to_u32(n: i64) -> u32 {
+    n.clamp(0, u32::MAX.into()) as u32
+}
+
+impl Asset for GdxAtlas {
+    type Error = AtlasError;
+
+    fn load(pack: &mut impl Pack, path: impl AsRef<Path>) -> AssetResult<Self> {
+        let path = path.as_ref();
+        let mut raw = pack.get_raw(path)?;
+
+        let mut text = String::with_capacity(raw.size_hint.unwrap_or(0));
+        raw.read.read_to_string(&mut text)?;
+        Self::parse(&text, path)
+    }
+}
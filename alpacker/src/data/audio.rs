@@ -0,0 +1,318 @@
+/* This module decodes audio into interleaved PCM samples without an engine, for servers and custom mixers.
+WAV (`wav` feature), Ogg Vorbis (`vorbis` feature) and FLAC (`flac` feature) are decoded in pure Rust.
+The format is detected from the magic bytes of the entry, then from its extension. */
+
+use std::{
+    error::Error,
+    fmt,
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use thiserror::Error;
+
+use crate::{Asset, AssetResult, Pack};
+
+/// A container format of audio files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AudioFormat {
+    Wav,
+    Vorbis,
+    Flac,
+}
+
+impl AudioFormat {
+    /// The number of bytes [AudioFormat::from_magic] looks at.
+    pub const MAGIC_LEN: usize = 12;
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Wav => "WAV",
+            Self::Vorbis => "Ogg Vorbis",
+            Self::Flac => "FLAC",
+        }
+    }
+
+    /// Detects the format by the extension of a path, ignoring its case.
+    pub fn from_extension(path: impl AsRef<Path>) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "wav" | "wave" => Some(Self::Wav),
+            "ogg" | "oga" => Some(Self::Vorbis),
+            "flac" => Some(Self::Flac),
+            _ => None,
+        }
+    }
+
+    /// Detects the format by the first bytes of a file.
+    pub fn from_magic(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [b'R', b'I', b'F', b'F', _, _, _, _, rest @ ..] if rest.starts_with(b"WAVE") => {
+                Some(Self::Wav)
+            }
+            [b'O', b'g', b'g', b'S', ..] => Some(Self::Vorbis),
+            [b'f', b'L', b'a', b'C', ..] => Some(Self::Flac),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AudioFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Interleaved samples, in the type the source was stored with.
+///
+/// Sources of up to 16 bits are decoded as `i16`, deeper and floating-point ones as `f32` in `-1.0..=1.0`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Samples {
+    I16(Vec<i16>),
+    F32(Vec<f32>),
+}
+
+/// The scale between `i16` and `f32` samples, used both ways so conversions round-trip.
+const I16_SCALE: f32 = 32768.0;
+
+impl Samples {
+    pub fn len(&self) -> usize {
+        match self {
+            Self::I16(samples) => samples.len(),
+            Self::F32(samples) => samples.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Converts the samples to `f32` in `-1.0..1.0`.
+    pub fn into_f32(self) -> Vec<f32> {
+        match self {
+            Self::I16(samples) => samples.into_iter().map(|s| s as f32 / I16_SCALE).collect(),
+            Self::F32(samples) => samples,
+        }
+    }
+
+    /// Converts the samples to `i16`, clipping `f32` samples outside `-1.0..1.0`.
+    pub fn into_i16(self) -> Vec<i16> {
+        match self {
+            Self::I16(samples) => samples,
+            // Casting saturates, so 1.0 and above become `i16::MAX`
+            Self::F32(samples) => (samples.into_iter())
+                .map(|s| (s * I16_SCALE) as i16)
+                .collect(),
+        }
+    }
+}
+
+/// Decoded audio.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PcmAudio {
+    /// Frames per second.
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Samples of every channel, interleaved.
+    pub samples: Samples,
+}
+
+impl PcmAudio {
+    /// Returns the number of frames, each holding a sample of every channel.
+    pub fn frames(&self) -> usize {
+        self.samples.len() / usize::from(self.channels.max(1))
+    }
+
+    pub fn duration(&self) -> Duration {
+        match self.sample_rate {
+            0 => Duration::ZERO,
+            rate => Duration::from_secs_f64(self.frames() as f64 / f64::from(rate)),
+        }
+    }
+
+    /// Decodes audio of a known format.
+    fn decode(read: impl Read + Seek, format: AudioFormat) -> Result<Self, DecodeError> {
+        match format {
+            #[cfg(feature = "wav")]
+            AudioFormat::Wav => decode_wav(read),
+            #[cfg(feature = "vorbis")]
+            AudioFormat::Vorbis => decode_vorbis(read),
+            #[cfg(feature = "flac")]
+            AudioFormat::Flac => decode_flac(read),
+            #[cfg_attr(
+                all(feature = "wav", feature = "vorbis", feature = "flac"),
+                allow(unreachable_patterns)
+            )]
+            format => Err(DecodeError::Disabled(format)),
+        }
+    }
+}
+
+/// Error type for audio loading operations
+#[derive(Debug, Error)]
+pub enum AudioError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Unknown audio format: {0:?}")]
+    UnknownFormat(PathBuf),
+
+    #[error("Decoding {format} requires the `{}` feature", feature(*format))]
+    Disabled { path: PathBuf, format: AudioFormat },
+
+    #[error("Invalid {format} audio {path:?}: {source}")]
+    Decode {
+        path: PathBuf,
+        format: AudioFormat,
+        source: Box<dyn Error + Send + Sync>,
+    },
+}
+
+impl AudioError {
+    /// Returns the path of the audio file.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Io(_) => None,
+            Self::UnknownFormat(path) | Self::Disabled { path, .. } | Self::Decode { path, .. } => {
+                Some(path)
+            }
+        }
+    }
+}
+
+impl From<AudioError> for crate::Error {
+    fn from(err: AudioError) -> Self {
+        match err {
+            AudioError::Io(err) => err.into(),
+            err => Self::asset(err.path().map(Path::to_path_buf), err),
+        }
+    }
+}
+
+/// The feature that enables decoding a format.
+const fn feature(format: AudioFormat) -> &'static str {
+    match format {
+        AudioFormat::Wav => "wav",
+        AudioFormat::Vorbis => "vorbis",
+        AudioFormat::Flac => "flac",
+    }
+}
+
+/// Errors of decoders, before the path is known.
+enum DecodeError {
+    Disabled(AudioFormat),
+    Decoder(Box<dyn Error + Send + Sync>),
+}
+
+impl<E: Error + Send + Sync + 'static> From<E> for DecodeError {
+    fn from(err: E) -> Self {
+        Self::Decoder(Box::new(err))
+    }
+}
+
+/// Scales integer samples deeper than 16 bits to `f32`.
+#[cfg(any(feature = "wav", feature = "flac"))]
+fn deep_to_f32(samples: impl Iterator<Item = i32>, bits: u32) -> Vec<f32> {
+    let scale = (1u64 << (bits - 1)) as f32;
+    samples.map(|s| s as f32 / scale).collect()
+}
+
+#[cfg(feature = "wav")]
+fn decode_wav(read: impl Read) -> Result<PcmAudio, DecodeError> {
+    use hound::{SampleFormat, WavReader};
+
+    let mut reader = WavReader::new(read)?;
+    let spec = reader.spec();
+    let bits = u32::from(spec.bits_per_sample);
+
+    let samples = match spec.sample_format {
+        SampleFormat::Float => Samples::F32(reader.samples::<f32>().collect::<Result<_, _>>()?),
+        // Shallower samples are shifted up to the 16-bit range
+        SampleFormat::Int if bits <= 16 => Samples::I16(
+            (reader.samples::<i16>())
+                .map(|s| s.map(|s| s << (16 - bits)))
+                .collect::<Result<_, _>>()?,
+        ),
+        SampleFormat::Int => {
+            let samples = reader.samples::<i32>().collect::<Result<Vec<_>, _>>()?;
+            Samples::F32(deep_to_f32(samples.into_iter(), bits))
+        }
+    };
+
+    Ok(PcmAudio {
+        sample_rate: spec.sample_rate,
+        channels: spec.channels,
+        samples,
+    })
+}
+
+#[cfg(feature = "vorbis")]
+fn decode_vorbis(read: impl Read + Seek) -> Result<PcmAudio, DecodeError> {
+    use lewton::inside_ogg::OggStreamReader;
+
+    let mut reader = OggStreamReader::new(read)?;
+    let mut samples = Vec::new();
+    while let Some(packet) = reader.read_dec_packet_itl()? {
+        samples.extend(packet);
+    }
+
+    Ok(PcmAudio {
+        sample_rate: reader.ident_hdr.audio_sample_rate,
+        channels: reader.ident_hdr.audio_channels.into(),
+        samples: Samples::I16(samples),
+    })
+}
+
+#[cfg(feature = "flac")]
+fn decode_flac(read: impl Read) -> Result<PcmAudio, DecodeError> {
+    use claxon::FlacReader;
+
+    let mut reader = FlacReader::new(read)?;
+    let info = reader.streaminfo();
+    let samples = reader.samples().collect::<Result<Vec<_>, _>>()?;
+
+    let samples = match info.bits_per_sample {
+        bits if bits <= 16 => Samples::I16(
+            (samples.into_iter())
+                .map(|s| (s << (16 - bits)) as i16)
+                .collect(),
+        ),
+        bits => Samples::F32(deep_to_f32(samples.into_iter(), bits)),
+    };
+
+    Ok(PcmAudio {
+        sample_rate: info.sample_rate,
+        channels: info.channels as u16,
+        samples,
+    })
+}
+
+impl Asset for PcmAudio {
+    type Error = AudioError;
+
+    fn load(pack: &mut impl Pack, path: impl AsRef<Path>) -> AssetResult<Self> {
+        let path = path.as_ref();
+        let mut raw = pack.get_raw(path)?;
+
+        let mut magic = Vec::with_capacity(AudioFormat::MAGIC_LEN);
+        (&mut raw.read)
+            .take(AudioFormat::MAGIC_LEN as u64)
+            .read_to_end(&mut magic)?;
+        raw.read.seek(SeekFrom::Start(0))?;
+
+        let format = AudioFormat::from_magic(&magic).or_else(|| AudioFormat::from_extension(path));
+        let format = format.ok_or_else(|| AudioError::UnknownFormat(path.to_path_buf()))?;
+
+        Self::decode(raw.read, format).map_err(|err| match err {
+            DecodeError::Disabled(format) => AudioError::Disabled {
+                path: path.to_path_buf(),
+                format,
+            },
+            DecodeError::Decoder(source) => AudioError::Decode {
+                path: path.to_path_buf(),
+                format,
+                source,
+            },
+        })
+    }
+}
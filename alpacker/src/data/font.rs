@@ -0,0 +1,148 @@
+/* This module loads TrueType and OpenType fonts: `ab_glyph` fonts with the `ab_glyph` feature and `fontdue`
+fonts with the `fontdue` feature. A face of a collection (`.ttc`/`.otc`) is selected with a `#index` suffix
+on the asset path, e.g. `fonts/noto.ttc#2`, or with the `load_*` functions. Font data is shared with
+the pack: `fontdue` parses it in place, while `ab_glyph` fonts own their data, so it's copied once
+unless the pack read it into a new buffer. */
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+/// Error type for font loading operations
+#[derive(Debug, Error)]
+pub enum FontError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Invalid font {path:?} (face {index}): {message}")]
+    Invalid {
+        path: PathBuf,
+        index: u32,
+        message: String,
+    },
+}
+
+impl From<FontError> for crate::Error {
+    fn from(err: FontError) -> Self {
+        match err {
+            FontError::Io(err) => err.into(),
+            FontError::Invalid { ref path, .. } => Self::asset(Some(path.clone()), err),
+        }
+    }
+}
+
+/// Splits the face index off a font path.
+///
+/// # Returns
+/// * `(path, Some(index))` for paths like `fonts/noto.ttc#2`.
+/// * `(path, None)` if the path has no `#` followed by a number.
+pub fn face_path(path: &Path) -> (&Path, Option<u32>) {
+    let split = path.to_str().and_then(|str| {
+        let (path, index) = str.rsplit_once('#')?;
+        Some((Path::new(path), index.parse().ok()?))
+    });
+
+    match split {
+        Some((path, index)) => (path, Some(index)),
+        None => (path, None),
+    }
+}
+
+#[cfg(feature = "ab_glyph")]
+mod ab_glyph_font {
+    use ab_glyph::{FontArc, FontVec};
+    use std::path::Path;
+
+    use super::{FontError, face_path};
+    use crate::{Asset, AssetResult, Pack};
+
+    /// Loads a face of an `ab_glyph` font.
+    ///
+    /// # Arguments
+    /// * `pack` - The pack to read the font from.
+    /// * `path` - The path of the font file, without a `#index` suffix.
+    /// * `index` - The index of the face in a collection, 0 for single fonts.
+    pub fn load_font_vec(
+        pack: &mut impl Pack,
+        path: impl AsRef<Path>,
+        index: u32,
+    ) -> Result<FontVec, FontError> {
+        let path = path.as_ref();
+        // `FontVec` owns its data, so entries kept in memory by the pack are copied
+        let data = pack.get_shared(path)?.into_vec();
+
+        FontVec::try_from_vec_and_index(data, index).map_err(|err| FontError::Invalid {
+            path: path.to_path_buf(),
+            index,
+            message: err.to_string(),
+        })
+    }
+
+    impl Asset for FontVec {
+        type Error = FontError;
+
+        fn load(pack: &mut impl Pack, path: impl AsRef<Path>) -> AssetResult<Self> {
+            let (path, index) = face_path(path.as_ref());
+            load_font_vec(pack, path, index.unwrap_or(0))
+        }
+    }
+
+    impl Asset for FontArc {
+        type Error = FontError;
+
+        fn load(pack: &mut impl Pack, path: impl AsRef<Path>) -> AssetResult<Self> {
+            FontVec::load(pack, path).map(FontArc::new)
+        }
+    }
+}
+
+#[cfg(feature = "ab_glyph")]
+pub use ab_glyph_font::load_font_vec;
+
+#[cfg(feature = "fontdue")]
+mod fontdue_font {
+    use fontdue::{Font, FontSettings};
+    use std::path::Path;
+
+    use super::{FontError, face_path};
+    use crate::{Asset, AssetResult, Pack};
+
+    /// Loads a `fontdue` font with custom settings.
+    ///
+    /// # Arguments
+    /// * `pack` - The pack to read the font from.
+    /// * `path` - The path of the font file, without a `#index` suffix.
+    /// * `settings` - Settings of the font, including the face index of collections.
+    pub fn load_fontdue(
+        pack: &mut impl Pack,
+        path: impl AsRef<Path>,
+        settings: FontSettings,
+    ) -> Result<Font, FontError> {
+        let (path, index) = (path.as_ref(), settings.collection_index);
+        let data = pack.get_shared(path)?;
+
+        Font::from_bytes(&*data, settings).map_err(|message| FontError::Invalid {
+            path: path.to_path_buf(),
+            index,
+            message: message.to_owned(),
+        })
+    }
+
+    impl Asset for Font {
+        type Error = FontError;
+
+        fn load(pack: &mut impl Pack, path: impl AsRef<Path>) -> AssetResult<Self> {
+            let (path, index) = face_path(path.as_ref());
+            let settings = FontSettings {
+                collection_index: index.unwrap_or(0),
+                ..FontSettings::default()
+            };
+            load_fontdue(pack, path, settings)
+        }
+    }
+}
+
+#[cfg(feature = "fontdue")]
+pub use fontdue_font::load_fontdue;
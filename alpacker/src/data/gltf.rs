@@ -0,0 +1,303 @@
+/* This module loads glTF 2.0 models (`.gltf` and `.glb`) with the `gltf` feature.
+External buffers and images are resolved relative to the model inside the same pack, data URIs are decoded,
+and images stored in buffer views are sliced out of their buffers. Images are decoded with [ModelImage],
+which keeps them encoded by default, or decodes them into `DynamicImage` with the `image` feature. */
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use gltf::{Document, Gltf, buffer, image::Source as ImageSource};
+use std::{
+    convert::Infallible,
+    error::Error,
+    io,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+use crate::{
+    Asset, AssetResult, Pack,
+    path::{PathError, VirtualPath},
+};
+
+/// Error type for glTF loading operations
+#[derive(Debug, Error)]
+pub enum GltfError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Path error: {0}")]
+    Path(#[from] PathError),
+
+    #[error("Invalid glTF model {path:?}: {source}")]
+    Gltf { path: PathBuf, source: gltf::Error },
+
+    #[error("Invalid buffer {index} of {path:?}: {message}")]
+    Buffer {
+        path: PathBuf,
+        index: usize,
+        message: String,
+    },
+
+    #[error("Invalid image {index} of {path:?}: {source}")]
+    Image {
+        path: PathBuf,
+        index: usize,
+        source: Box<dyn Error + Send + Sync>,
+    },
+}
+
+impl GltfError {
+    /// Returns the path of the model the error occurred in, if known.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Io(_) | Self::Path(_) => None,
+            Self::Gltf { path, .. } | Self::Buffer { path, .. } | Self::Image { path, .. } => {
+                Some(path)
+            }
+        }
+    }
+}
+
+impl From<GltfError> for crate::Error {
+    fn from(err: GltfError) -> Self {
+        match err {
+            GltfError::Io(err) => err.into(),
+            GltfError::Path(err) => err.into(),
+            err => Self::asset(err.path().map(Path::to_path_buf), err),
+        }
+    }
+}
+
+/// Trait for image types that the images of a model are decoded into.
+pub trait ModelImage: Sized {
+    type Error: Error + Send + Sync + 'static;
+
+    /// Decodes an image of a model.
+    ///
+    /// # Arguments
+    /// * `data` - The encoded image.
+    /// * `mime_type` - The MIME type of the image, e.g. `image/png`, if the model specifies it.
+    fn decode(data: Vec<u8>, mime_type: Option<&str>) -> Result<Self, Self::Error>;
+}
+
+/// An image that is kept encoded, e.g. to be uploaded to the GPU by an engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedImage {
+    pub data: Vec<u8>,
+    pub mime_type: Option<String>,
+}
+
+impl ModelImage for EncodedImage {
+    type Error = Infallible;
+
+    fn decode(data: Vec<u8>, mime_type: Option<&str>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            data,
+            mime_type: mime_type.map(str::to_owned),
+        })
+    }
+}
+
+#[cfg(feature = "image")]
+impl ModelImage for image::DynamicImage {
+    type Error = image::ImageError;
+
+    fn decode(data: Vec<u8>, mime_type: Option<&str>) -> Result<Self, Self::Error> {
+        match mime_type.and_then(image::ImageFormat::from_mime_type) {
+            Some(format) => image::load_from_memory_with_format(&data, format),
+            None => image::load_from_memory(&data),
+        }
+    }
+}
+
+/// A glTF model with its buffers and images
+#[derive(Debug, Clone)]
+pub struct Model<I: ModelImage = EncodedImage> {
+    pub document: Document,
+    /// Buffer data, in the order of `document.buffers()`.
+    pub buffers: Vec<Vec<u8>>,
+    /// Decoded images, in the order of `document.images()`.
+    pub images: Vec<I>,
+}
+
+impl<I: ModelImage> Model<I> {
+    /// Returns the data of a buffer, e.g. for `Primitive::reader`.
+    pub fn buffer(&self, buffer: buffer::Buffer) -> Option<&[u8]> {
+        self.buffers.get(buffer.index()).map(Vec::as_slice)
+    }
+
+    /// Returns the data of a buffer view.
+    pub fn view(&self, view: &buffer::View) -> Option<&[u8]> {
+        view_data(&self.buffers, view)
+    }
+
+    /// Returns the decoded image of a glTF image.
+    pub fn image(&self, image: &gltf::Image) -> Option<&I> {
+        self.images.get(image.index())
+    }
+}
+
+impl<I: ModelImage> Deref for Model<I> {
+    type Target = Document;
+
+    fn deref(&self) -> &Self::Target {
+        &self.document
+    }
+}
+
+/// Slices the data of a buffer view out of its buffer.
+fn view_data<'b>(buffers: &'b [Vec<u8>], view: &buffer::View) -> Option<&'b [u8]> {
+    let buffer = buffers.get(view.buffer().index())?;
+    buffer.get(view.offset()..view.offset().checked_add(view.length())?)
+}
+
+/// Decodes a `data:` URI, returning its MIME type and bytes.
+///
+/// # Returns
+/// * `None` if the URI isn't a data URI.
+/// * `Some(Err(message))` if it isn't valid Base64 data.
+fn data_uri(uri: &str) -> Option<Result<(&str, Vec<u8>), String>> {
+    let (header, data) = uri.strip_prefix("data:")?.split_once(',')?;
+    let Some(mime_type) = header.strip_suffix(";base64") else {
+        return Some(Err(format!("data URI {header:?} isn't Base64")));
+    };
+    Some(
+        (STANDARD.decode(data))
+            .map(|data| (mime_type, data))
+            .map_err(|err| err.to_string()),
+    )
+}
+
+/// Decodes percent-encoded characters of a relative URI, e.g. `%20` for spaces.
+///
+/// # Returns
+/// * `None` if an escape is invalid, in which case the URI is used as is.
+fn decode_uri(uri: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(uri.len());
+    let mut rest = uri.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = match (byte, tail) {
+            (b'%', [high, low, tail @ ..]) => {
+                let hex = [*high, *low];
+                bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+                tail
+            }
+            _ => {
+                bytes.push(byte);
+                tail
+            }
+        };
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// A model being loaded, which resolves the files it references.
+struct Source<'a> {
+    path: &'a Path,
+    /// The model file, which relative URIs are resolved against.
+    model: VirtualPath,
+}
+
+impl Source<'_> {
+    /// Reads the file a relative URI points to.
+    fn read(&self, pack: &mut impl Pack, uri: &str) -> Result<Vec<u8>, GltfError> {
+        let decoded = decode_uri(uri);
+        let path = self.model.resolve_link(decoded.as_deref().unwrap_or(uri))?;
+        Ok(pack.get::<Vec<u8>>(path.as_str())?)
+    }
+
+    fn buffer(
+        &self,
+        pack: &mut impl Pack,
+        buffer: buffer::Buffer,
+        blob: &mut Option<Vec<u8>>,
+    ) -> Result<Vec<u8>, GltfError> {
+        let invalid = |message: String| GltfError::Buffer {
+            path: self.path.to_path_buf(),
+            index: buffer.index(),
+            message,
+        };
+
+        let data = match buffer.source() {
+            buffer::Source::Bin => blob
+                .take()
+                .ok_or_else(|| invalid("missing binary chunk".into()))?,
+            buffer::Source::Uri(uri) => match data_uri(uri) {
+                Some(data) => data.map_err(invalid)?.1,
+                None => self.read(pack, uri)?,
+            },
+        };
+
+        if data.len() < buffer.length() {
+            return Err(invalid(format!(
+                "expected {} bytes, found {}",
+                buffer.length(),
+                data.len()
+            )));
+        }
+        Ok(data)
+    }
+
+    fn image<I: ModelImage>(
+        &self,
+        pack: &mut impl Pack,
+        image: gltf::Image,
+        buffers: &[Vec<u8>],
+    ) -> Result<I, GltfError> {
+        let invalid = |source: Box<dyn Error + Send + Sync>| GltfError::Image {
+            path: self.path.to_path_buf(),
+            index: image.index(),
+            source,
+        };
+
+        let (data, mime_type) = match image.source() {
+            ImageSource::View { view, mime_type } => {
+                let data = view_data(buffers, &view)
+                    .ok_or_else(|| invalid("buffer view is out of bounds".into()))?;
+                (data.to_vec(), Some(mime_type))
+            }
+            ImageSource::Uri { uri, mime_type } => match data_uri(uri) {
+                Some(data) => {
+                    let (uri_type, data) = data.map_err(|message| invalid(message.into()))?;
+                    (data, mime_type.or(Some(uri_type)))
+                }
+                None => (self.read(pack, uri)?, mime_type),
+            },
+        };
+
+        I::decode(data, mime_type).map_err(|err| invalid(Box::new(err)))
+    }
+}
+
+impl<I: ModelImage> Asset for Model<I> {
+    type Error = GltfError;
+
+    fn load(pack: &mut impl Pack, path: impl AsRef<Path>) -> AssetResult<Self> {
+        let path = path.as_ref();
+        let data = pack.get::<Vec<u8>>(path)?;
+
+        let Gltf { document, mut blob } =
+            Gltf::from_slice(&data).map_err(|source| GltfError::Gltf {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        // URIs are relative to the model, like links are relative to their directory
+        let source = Source {
+            path,
+            model: VirtualPath::new(path)?,
+        };
+
+        let buffers = (document.buffers())
+            .map(|buffer| source.buffer(pack, buffer, &mut blob))
+            .collect::<Result<Vec<_>, _>>()?;
+        let images = (document.images())
+            .map(|image| source.image(pack, image, &buffers))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            document,
+            buffers,
+            images,
+        })
+    }
+}
@@ -1,7 +1,8 @@
 use image::{DynamicImage, ImageError, ImageFormat};
 use std::{io::BufReader, path::Path};
 
-use crate::{Asset, AssetResult, Pack};
+use super::sprite::{Crop, Rect};
+use crate::{Asset, AssetResult, Pack, error::ErrorKind};
 
 /// Implementation of the Asset trait for DynamicImage from the image crate
 ///
@@ -11,7 +12,7 @@ impl Asset for DynamicImage {
 
     /// Loads an image from the asset pack
     fn load(pack: &mut impl Pack, path: impl AsRef<Path>) -> AssetResult<Self> {
-        let raw = pack.get_raw(&path.as_ref())?; // Propagates pack's error
+        let raw = pack.get_raw(path.as_ref())?; // Propagates pack's error
 
         let buf_read = BufReader::new(raw.read);
         let format = ImageFormat::from_path(&path)?;
@@ -20,6 +21,19 @@ impl Asset for DynamicImage {
     }
 }
 
+impl Crop for DynamicImage {
+    fn crop(&self, rect: Rect) -> Self {
+        self.crop_imm(rect.x, rect.y, rect.width, rect.height)
+    }
+
+    fn rotate90(&self, clockwise: bool) -> Self {
+        match clockwise {
+            true => self.rotate90(),
+            false => self.rotate270(),
+        }
+    }
+}
+
 /// A type alias for a sprite that uses `DynamicImage` as its image representation.
 ///
 /// This alias is available only when the `aseprite` feature is enabled.
@@ -29,3 +43,20 @@ impl Asset for DynamicImage {
 /// See [`Sprite`](super::aseprite::Sprite) for more details.
 #[cfg(feature = "aseprite")]
 pub type ImageSprite = super::aseprite::Sprite<DynamicImage>;
+
+/// A type alias for a glTF model with images decoded into `DynamicImage`.
+///
+/// This alias is available only when the `gltf` feature is enabled.
+///
+/// See [`Model`](super::gltf::Model) for more details.
+#[cfg(feature = "gltf")]
+pub type ImageModel = super::gltf::Model<DynamicImage>;
+
+impl From<image::ImageError> for crate::Error {
+    fn from(err: image::ImageError) -> Self {
+        match err {
+            image::ImageError::IoError(err) => err.into(),
+            err => Self::new(ErrorKind::Asset, err),
+        }
+    }
+}
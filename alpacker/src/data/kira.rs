@@ -0,0 +1,58 @@
+/* This module loads `kira` sounds with the `kira` feature. Sounds are decoded from `SharedBytes`,
+so entries kept in memory aren't copied into a temporary buffer. Decoding doesn't need an audio manager.
+Formats are decoded by symphonia, so they're enabled with the features of the `kira` dependency. */
+
+use kira::sound::{FromFileError, static_sound::StaticSoundData};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+use crate::{Asset, AssetResult, Pack};
+
+/// Error type for `kira` sound loading operations
+#[derive(Debug, Error)]
+pub enum KiraError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Can't decode sound {path:?}: {source}")]
+    Decode {
+        path: PathBuf,
+        source: FromFileError,
+    },
+}
+
+impl KiraError {
+    /// Returns the path of the sound, if known.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Io(_) => None,
+            Self::Decode { path, .. } => Some(path),
+        }
+    }
+}
+
+impl From<KiraError> for crate::Error {
+    fn from(err: KiraError) -> Self {
+        match err {
+            KiraError::Io(err) => err.into(),
+            KiraError::Decode { ref path, .. } => Self::asset(Some(path.clone()), err),
+        }
+    }
+}
+
+impl Asset for StaticSoundData {
+    type Error = KiraError;
+
+    fn load(pack: &mut impl Pack, path: impl AsRef<Path>) -> AssetResult<Self> {
+        let path = path.as_ref();
+        let data = pack.get_shared(path)?;
+
+        StaticSoundData::from_cursor(data.cursor()).map_err(|source| KiraError::Decode {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+}
@@ -1,12 +1,39 @@
+#[cfg(feature = "ase")]
+pub mod ase;
+
 #[cfg(feature = "aseprite")]
 pub mod aseprite;
 
+#[cfg(feature = "atlas")]
+pub mod atlas;
+
+#[cfg(any(feature = "wav", feature = "vorbis", feature = "flac"))]
+pub mod audio;
+
+#[cfg(any(feature = "ab_glyph", feature = "fontdue"))]
+pub mod font;
+
+#[cfg(feature = "gltf")]
+pub mod gltf;
+
 #[cfg(feature = "image")]
 pub mod image;
 
+#[cfg(feature = "kira")]
+pub mod kira;
+
 #[cfg(feature = "raylib")]
 pub mod raylib;
 
+#[cfg(feature = "rodio")]
+pub mod rodio;
+
+pub mod serialized;
+pub mod sprite;
+
+#[cfg(feature = "tiled")]
+pub mod tiled;
+
 use std::{
     io::{Error, Read},
     path::Path,
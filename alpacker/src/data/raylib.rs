@@ -1,10 +1,12 @@
 use raylib::{
     audio::{Music, RaylibAudio, Wave},
+    math::Rectangle,
     texture::Image,
 };
 use std::{ffi::OsStr, io, path::Path};
 
-use crate::{Asset, AssetResult, Pack};
+use super::sprite::{Crop, Rect};
+use crate::{Asset, AssetResult, Pack, error::ErrorKind};
 
 /// Returns the file extension of the given path as a string, prefixed with a dot.
 /// If the path has no extension, the provided default extension is used instead.
@@ -25,6 +27,15 @@ pub enum RaylibError {
     Io(#[from] io::Error),
 }
 
+impl From<RaylibError> for crate::Error {
+    fn from(err: RaylibError) -> Self {
+        match err {
+            RaylibError::Io(err) => err.into(),
+            err => Self::new(ErrorKind::Asset, err),
+        }
+    }
+}
+
 impl Asset for Image {
     type Error = RaylibError;
 
@@ -37,6 +48,27 @@ impl Asset for Image {
     }
 }
 
+impl Crop for Image {
+    fn crop(&self, rect: Rect) -> Self {
+        let rect = Rectangle::new(
+            rect.x as f32,
+            rect.y as f32,
+            rect.width as f32,
+            rect.height as f32,
+        );
+        self.from_image(rect)
+    }
+
+    fn rotate90(&self, clockwise: bool) -> Self {
+        let mut image = self.clone();
+        match clockwise {
+            true => image.rotate_cw(),
+            false => image.rotate_ccw(),
+        }
+        image
+    }
+}
+
 /// A type alias for a sprite that uses `raylib::texture::Image` as its image representation.
 ///
 /// This alias is available only when the `aseprite` feature is enabled.
@@ -0,0 +1,78 @@
+/* This module loads `rodio` sources with the `rodio` feature. Decoders read entries through `SharedBytes`,
+so they don't borrow the pack while sounds play, and entries kept in memory aren't copied.
+Formats are decoded by rodio, so they're enabled with the features of the `rodio` dependency. */
+
+use rodio::{Decoder, Source, decoder::DecoderError, source::Buffered};
+use std::{
+    ffi::OsStr,
+    io::{self, Cursor},
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+use crate::{Asset, AssetResult, Pack, SharedBytes};
+
+/// A `rodio` decoder of a pack entry.
+pub type PackDecoder = Decoder<Cursor<SharedBytes>>;
+
+/// A decoded sound that can be cloned and played several times, e.g. a sound effect.
+pub type BufferedSound = Buffered<PackDecoder>;
+
+/// Error type for `rodio` sound loading operations
+#[derive(Debug, Error)]
+pub enum RodioError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Can't decode sound {path:?}: {source}")]
+    Decoder { path: PathBuf, source: DecoderError },
+}
+
+impl RodioError {
+    /// Returns the path of the sound, if known.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Io(_) => None,
+            Self::Decoder { path, .. } => Some(path),
+        }
+    }
+}
+
+impl From<RodioError> for crate::Error {
+    fn from(err: RodioError) -> Self {
+        match err {
+            RodioError::Io(err) => err.into(),
+            RodioError::Decoder { ref path, .. } => Self::asset(Some(path.clone()), err),
+        }
+    }
+}
+
+impl Asset for PackDecoder {
+    type Error = RodioError;
+
+    fn load(pack: &mut impl Pack, path: impl AsRef<Path>) -> AssetResult<Self> {
+        let path = path.as_ref();
+        let data = pack.get_shared(path)?;
+
+        let mut builder = Decoder::builder()
+            .with_byte_len(data.len() as u64)
+            .with_data(data.cursor());
+        // The extension helps to pick the format faster
+        if let Some(ext) = path.extension().and_then(OsStr::to_str) {
+            builder = builder.with_hint(ext);
+        }
+
+        builder.build().map_err(|source| RodioError::Decoder {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+}
+
+impl Asset for BufferedSound {
+    type Error = RodioError;
+
+    fn load(pack: &mut impl Pack, path: impl AsRef<Path>) -> AssetResult<Self> {
+        PackDecoder::load(pack, path).map(Source::buffered)
+    }
+}
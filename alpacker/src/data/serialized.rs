@@ -0,0 +1,460 @@
+/* This module provides assets for structured data in any serde format. Each format is a `Format`
+implementation behind its own feature, and wrapper types like `Json<T>` load any `DeserializeOwned` type
+with it. `Data<T>` picks the format from the file extension instead. Parse errors keep the entry path
+and, when the format reports one, the line and column. Binary formats that can decode from a stream
+implement `StreamFormat`, and are read straight from the `Raw` reader without buffering the entry. */
+
+use serde::de::DeserializeOwned;
+use std::{
+    error::Error as StdError,
+    ffi::OsStr,
+    fmt,
+    io::{self, Read},
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+use crate::{Asset, AssetResult, Pack};
+
+/// A position in a text file, both 1-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    /// Computes the location of a byte offset, counting columns in characters.
+    pub fn from_offset(data: &[u8], offset: usize) -> Self {
+        let before = &data[..offset.min(data.len())];
+        let line_start = before
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1);
+
+        Self {
+            line: before.iter().filter(|&&b| b == b'\n').count() + 1,
+            column: String::from_utf8_lossy(&before[line_start..])
+                .chars()
+                .count()
+                + 1,
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Formats the location suffix of [DataError::Parse].
+fn at(location: &Option<Location>) -> String {
+    match location {
+        Some(location) => format!(" at {location}"),
+        None => String::new(),
+    }
+}
+
+/// Errors that can occur while loading data assets.
+#[derive(Debug, Error)]
+pub enum DataError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Invalid {format} in {path:?}{}: {source}", at(.location))]
+    Parse {
+        format: &'static str,
+        path: PathBuf,
+        location: Option<Location>,
+        source: Box<dyn StdError + Send + Sync>,
+    },
+
+    #[error("Unknown data format of {0:?}")]
+    UnknownFormat(PathBuf),
+}
+
+impl DataError {
+    /// Returns the line and column of a parse error, if the format reports them.
+    pub const fn location(&self) -> Option<Location> {
+        match self {
+            Self::Parse { location, .. } => *location,
+            _ => None,
+        }
+    }
+}
+
+impl From<DataError> for crate::Error {
+    fn from(err: DataError) -> Self {
+        match err {
+            DataError::Io(err) => err.into(),
+            DataError::Parse { ref path, .. } | DataError::UnknownFormat(ref path) => {
+                Self::asset(Some(path.clone()), err)
+            }
+        }
+    }
+}
+
+/// A serde data format.
+pub trait Format {
+    /// The name of the format, e.g. `JSON`, used in error messages.
+    const NAME: &'static str;
+
+    /// Lowercase file extensions of the format, used by [Data].
+    const EXTENSIONS: &'static [&'static str];
+
+    type Error: StdError + Send + Sync + 'static;
+
+    /// Deserializes a value from the file contents.
+    fn from_slice<T: DeserializeOwned>(data: &[u8]) -> Result<T, Self::Error>;
+
+    /// Returns the location of an error, if the format reports it.
+    ///
+    /// # Arguments
+    /// * `err` - The error returned by [Format::from_slice].
+    /// * `data` - The file contents, for formats that report byte offsets.
+    fn location(err: &Self::Error, data: &[u8]) -> Option<Location> {
+        let _ = (err, data);
+        None
+    }
+}
+
+/// A format that can deserialize values straight from a reader.
+pub trait StreamFormat: Format {
+    /// Deserializes a value from the entry reader.
+    fn from_reader<T: DeserializeOwned>(read: impl io::Read) -> Result<T, Self::Error>;
+}
+
+/// Reads an entry and deserializes it with the format `F`.
+///
+/// # Arguments
+/// * `pack` - The pack to read the entry from.
+/// * `path` - The path of the entry.
+///
+/// # Returns
+/// * `Ok(T)` if the entry is successfully read and parsed.
+/// * `Err(DataError)` if reading or parsing fails.
+pub fn load_as<F: Format, T: DeserializeOwned>(
+    pack: &mut impl Pack,
+    path: impl AsRef<Path>,
+) -> Result<T, DataError> {
+    let path = path.as_ref();
+    let data = pack.get::<Vec<u8>>(path)?;
+
+    F::from_slice(&data).map_err(|err| DataError::Parse {
+        format: F::NAME,
+        path: path.to_path_buf(),
+        location: F::location(&err, &data),
+        source: err.into(),
+    })
+}
+
+/// Deserializes an entry with the format `F`, straight from the [Raw](crate::Raw) reader.
+///
+/// # Arguments
+/// * `pack` - The pack to read the entry from.
+/// * `path` - The path of the entry.
+///
+/// # Returns
+/// * `Ok(T)` if the entry is successfully parsed.
+/// * `Err(DataError)` if the entry is missing or parsing fails.
+pub fn read_as<F: StreamFormat, T: DeserializeOwned>(
+    pack: &mut impl Pack,
+    path: impl AsRef<Path>,
+) -> Result<T, DataError> {
+    let path = path.as_ref();
+    let raw = pack.get_raw(path)?;
+    // Never read past the end of the entry, if its size is known.
+    let limit = raw.size_hint.map_or(u64::MAX, |size| size as u64);
+
+    F::from_reader(raw.read.take(limit)).map_err(|err| DataError::Parse {
+        format: F::NAME,
+        path: path.to_path_buf(),
+        location: None,
+        source: err.into(),
+    })
+}
+
+/// Defines a newtype that holds a deserialized value.
+macro_rules! wrapper {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+        pub struct $name<T>(pub T);
+
+        impl<T> $name<T> {
+            pub fn into_inner(self) -> T {
+                self.0
+            }
+        }
+
+        impl<T> Deref for $name<T> {
+            type Target = T;
+
+            #[inline(always)]
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        impl<T> DerefMut for $name<T> {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.0
+            }
+        }
+    };
+}
+
+/// Defines a [Format] and a wrapper asset that loads values with it, using [load_as] or [read_as].
+macro_rules! data_format {
+    ($feature:literal, $format:ident, $wrapper:ident, $name:literal, $load:ident) => {
+        #[doc = concat!("The ", $name, " format.")]
+        #[cfg(feature = $feature)]
+        #[derive(Debug, Clone, Copy, Default)]
+        pub struct $format;
+
+        #[cfg(feature = $feature)]
+        wrapper! {
+            #[doc = concat!("An asset deserialized from ", $name, ".")]
+            ///
+            /// # Example
+            /// ```ignore
+            #[doc = concat!("let ", stringify!($wrapper), "(level) = pack.get::<", stringify!($wrapper), "<Level>>(\"levels/1\")?;")]
+            /// ```
+            $wrapper
+        }
+
+        #[cfg(feature = $feature)]
+        impl<T: DeserializeOwned> Asset for $wrapper<T> {
+            type Error = DataError;
+
+            fn load(pack: &mut impl Pack, path: impl AsRef<Path>) -> AssetResult<Self> {
+                $load::<$format, T>(pack, path).map(Self)
+            }
+        }
+    };
+}
+
+data_format!("json", JsonFormat, Json, "JSON", load_as);
+data_format!("toml", TomlFormat, Toml, "TOML", load_as);
+data_format!("ron", RonFormat, Ron, "RON", load_as);
+data_format!("yaml", YamlFormat, Yaml, "YAML", load_as);
+data_format!("postcard", PostcardFormat, Postcard, "postcard", load_as);
+data_format!("bincode", BincodeFormat, Bincode, "bincode", read_as);
+data_format!("msgpack", MsgPackFormat, MsgPack, "MessagePack", read_as);
+
+#[cfg(feature = "json")]
+impl Format for JsonFormat {
+    const NAME: &'static str = "JSON";
+    const EXTENSIONS: &'static [&'static str] = &["json"];
+
+    type Error = serde_json::Error;
+
+    fn from_slice<T: DeserializeOwned>(data: &[u8]) -> Result<T, Self::Error> {
+        serde_json::from_slice(data)
+    }
+
+    fn location(err: &Self::Error, _: &[u8]) -> Option<Location> {
+        // I/O errors have no location and report line 0.
+        (err.line() > 0).then(|| Location {
+            line: err.line(),
+            column: err.column(),
+        })
+    }
+}
+
+#[cfg(feature = "toml")]
+impl Format for TomlFormat {
+    const NAME: &'static str = "TOML";
+    const EXTENSIONS: &'static [&'static str] = &["toml"];
+
+    type Error = toml::de::Error;
+
+    fn from_slice<T: DeserializeOwned>(data: &[u8]) -> Result<T, Self::Error> {
+        toml::from_slice(data)
+    }
+
+    fn location(err: &Self::Error, data: &[u8]) -> Option<Location> {
+        err.span()
+            .map(|span| Location::from_offset(data, span.start))
+    }
+}
+
+#[cfg(feature = "ron")]
+impl Format for RonFormat {
+    const NAME: &'static str = "RON";
+    const EXTENSIONS: &'static [&'static str] = &["ron"];
+
+    type Error = ron::error::SpannedError;
+
+    fn from_slice<T: DeserializeOwned>(data: &[u8]) -> Result<T, Self::Error> {
+        ron::de::from_bytes(data)
+    }
+
+    fn location(err: &Self::Error, _: &[u8]) -> Option<Location> {
+        Some(Location {
+            line: err.span.start.line,
+            column: err.span.start.col,
+        })
+    }
+}
+
+#[cfg(feature = "yaml")]
+impl Format for YamlFormat {
+    const NAME: &'static str = "YAML";
+    const EXTENSIONS: &'static [&'static str] = &["yaml", "yml"];
+
+    type Error = serde_yaml_ng::Error;
+
+    fn from_slice<T: DeserializeOwned>(data: &[u8]) -> Result<T, Self::Error> {
+        serde_yaml_ng::from_slice(data)
+    }
+
+    fn location(err: &Self::Error, _: &[u8]) -> Option<Location> {
+        err.location().map(|location| Location {
+            line: location.line(),
+            column: location.column(),
+        })
+    }
+}
+
+#[cfg(feature = "postcard")]
+impl Format for PostcardFormat {
+    const NAME: &'static str = "postcard";
+    const EXTENSIONS: &'static [&'static str] = &["postcard"];
+
+    type Error = postcard::Error;
+
+    // Streaming needs a scratch buffer as large as the longest string or byte array,
+    // which only the entry size bounds, so the entry is read whole instead.
+    fn from_slice<T: DeserializeOwned>(data: &[u8]) -> Result<T, Self::Error> {
+        postcard::from_bytes(data)
+    }
+}
+
+/// Maximum number of bytes bincode may allocate while decoding a value.
+#[cfg(feature = "bincode")]
+pub const BINCODE_LIMIT: usize = 1 << 30; // 1 GiB
+
+/// Maximum nesting depth of MessagePack values.
+#[cfg(feature = "msgpack")]
+pub const MSGPACK_MAX_DEPTH: usize = 128;
+
+#[cfg(feature = "bincode")]
+impl BincodeFormat {
+    /// Returns the configuration files are encoded with, the bincode standard one,
+    /// with decoding limited to [BINCODE_LIMIT] bytes.
+    pub const fn config() -> bincode::config::Configuration<
+        bincode::config::LittleEndian,
+        bincode::config::Varint,
+        bincode::config::Limit<BINCODE_LIMIT>,
+    > {
+        bincode::config::standard().with_limit::<BINCODE_LIMIT>()
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl Format for BincodeFormat {
+    const NAME: &'static str = "bincode";
+    const EXTENSIONS: &'static [&'static str] = &["bincode"];
+
+    type Error = bincode::error::DecodeError;
+
+    fn from_slice<T: DeserializeOwned>(data: &[u8]) -> Result<T, Self::Error> {
+        bincode::serde::decode_from_slice(data, Self::config()).map(|(value, _)| value)
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl StreamFormat for BincodeFormat {
+    fn from_reader<T: DeserializeOwned>(mut read: impl io::Read) -> Result<T, Self::Error> {
+        bincode::serde::decode_from_std_read(&mut read, Self::config())
+    }
+}
+
+#[cfg(feature = "msgpack")]
+impl Format for MsgPackFormat {
+    const NAME: &'static str = "MessagePack";
+    const EXTENSIONS: &'static [&'static str] = &["msgpack", "mpk"];
+
+    type Error = rmp_serde::decode::Error;
+
+    fn from_slice<T: DeserializeOwned>(data: &[u8]) -> Result<T, Self::Error> {
+        let mut de = rmp_serde::Deserializer::from_read_ref(data);
+        de.set_max_depth(MSGPACK_MAX_DEPTH);
+        T::deserialize(&mut de)
+    }
+}
+
+#[cfg(feature = "msgpack")]
+impl StreamFormat for MsgPackFormat {
+    fn from_reader<T: DeserializeOwned>(read: impl io::Read) -> Result<T, Self::Error> {
+        let mut de = rmp_serde::Deserializer::new(read);
+        de.set_max_depth(MSGPACK_MAX_DEPTH);
+        T::deserialize(&mut de)
+    }
+}
+
+wrapper! {
+    /// An asset deserialized with the format matching its file extension.
+    ///
+    /// Only formats with enabled features are recognized.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let Data(settings) = pack.get::<Data<Settings>>("settings.toml")?;
+    /// ```
+    Data
+}
+
+/// Checks whether an extension belongs to the format `F`.
+#[allow(dead_code)]
+fn matches<F: Format>(extension: &str) -> bool {
+    F::EXTENSIONS.contains(&extension)
+}
+
+impl<T: DeserializeOwned> Asset for Data<T> {
+    type Error = DataError;
+
+    #[allow(unused_variables)]
+    fn load(pack: &mut impl Pack, path: impl AsRef<Path>) -> AssetResult<Self> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(OsStr::to_str)
+            .map(str::to_ascii_lowercase)
+            .unwrap_or_default();
+
+        #[cfg(feature = "json")]
+        if matches::<JsonFormat>(&extension) {
+            return load_as::<JsonFormat, T>(pack, path).map(Self);
+        }
+        #[cfg(feature = "toml")]
+        if matches::<TomlFormat>(&extension) {
+            return load_as::<TomlFormat, T>(pack, path).map(Self);
+        }
+        #[cfg(feature = "ron")]
+        if matches::<RonFormat>(&extension) {
+            return load_as::<RonFormat, T>(pack, path).map(Self);
+        }
+        #[cfg(feature = "yaml")]
+        if matches::<YamlFormat>(&extension) {
+            return load_as::<YamlFormat, T>(pack, path).map(Self);
+        }
+        #[cfg(feature = "postcard")]
+        if matches::<PostcardFormat>(&extension) {
+            return load_as::<PostcardFormat, T>(pack, path).map(Self);
+        }
+        #[cfg(feature = "bincode")]
+        if matches::<BincodeFormat>(&extension) {
+            return read_as::<BincodeFormat, T>(pack, path).map(Self);
+        }
+        #[cfg(feature = "msgpack")]
+        if matches::<MsgPackFormat>(&extension) {
+            return read_as::<MsgPackFormat, T>(pack, path).map(Self);
+        }
+
+        Err(DataError::UnknownFormat(path.to_path_buf()))
+    }
+}
@@ -0,0 +1,328 @@
+/* This module provides sprites: images combined with metadata that describes their frames, like an
+Aseprite sprite sheet or a multi-page texture atlas. Metadata formats implement `SpriteMeta`, `Animation`
+plays tagged frame sequences over any of them, and `Crop` cuts frames out of the loaded pages. */
+
+use std::{
+    error::Error,
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use thiserror::Error;
+
+use crate::{Asset, AssetResult, Pack, error::ErrorKind};
+
+/// A rectangle in image pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A frame of a sprite sheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SpriteFrame {
+    /// The index of the frame in the sprite sheet.
+    pub index: usize,
+    /// The index of the image page the frame is on.
+    pub page: usize,
+    /// Where the frame is in the image. The width and height of rotated frames are swapped.
+    pub rect: Rect,
+    /// How the frame is turned in the image.
+    pub rotated: Rotation,
+    /// How long the frame is shown.
+    pub duration: Duration,
+}
+
+/// How a frame was turned by 90 degrees when it was packed into its image.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Rotation {
+    #[default]
+    None,
+    Clockwise,
+    CounterClockwise,
+}
+
+/// The direction an animation plays in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Direction {
+    #[default]
+    Forward,
+    Reverse,
+    /// Forward, then back, without repeating the first and the last frames.
+    PingPong,
+    /// Backward, then forward, without repeating the first and the last frames.
+    PingPongReverse,
+}
+
+/// A named range of frames.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FrameTag {
+    pub name: String,
+    /// The first frame, inclusive.
+    pub from: usize,
+    /// The last frame, inclusive.
+    pub to: usize,
+    pub direction: Direction,
+}
+
+/// Trait for sprite metadata: the paths of the image pages, their frames and tags.
+///
+/// Only [SpriteMeta::image_path] is required, formats without frames or tags
+/// keep the default implementations.
+pub trait SpriteMeta: Asset {
+    /// Returns the path of the first image page, relative to the pack root.
+    fn image_path(&self, meta_path: impl AsRef<Path>) -> Option<PathBuf>;
+
+    /// Returns the paths of every image page, relative to the pack root.
+    ///
+    /// Single-page formats keep the default implementation, which returns [SpriteMeta::image_path].
+    fn image_paths(&self, meta_path: impl AsRef<Path>) -> Vec<PathBuf> {
+        self.image_path(meta_path).into_iter().collect()
+    }
+
+    /// Returns the number of frames in the sprite sheet.
+    fn frame_count(&self) -> usize {
+        0
+    }
+
+    /// Returns a frame by its index, or `None` if it's out of range.
+    fn frame(&self, index: usize) -> Option<SpriteFrame> {
+        let _ = index;
+        None
+    }
+
+    /// Returns every tag of the sprite sheet.
+    fn tags(&self) -> Vec<FrameTag> {
+        Vec::new()
+    }
+
+    /// Returns a tag by its name.
+    fn tag(&self, name: &str) -> Option<FrameTag> {
+        self.tags().into_iter().find(|tag| tag.name == name)
+    }
+}
+
+/// Types of images that sprite frames can be cut out of.
+pub trait Crop: Sized {
+    /// Returns a copy of the area of the image within `rect`.
+    fn crop(&self, rect: Rect) -> Self;
+
+    /// Returns a copy of the image turned by 90 degrees, clockwise if `clockwise` is `true`.
+    fn rotate90(&self, clockwise: bool) -> Self;
+
+    /// Cuts a frame out of the image, turning rotated frames back upright.
+    fn crop_frame(&self, frame: &SpriteFrame) -> Self {
+        let image = self.crop(frame.rect);
+        match frame.rotated {
+            Rotation::None => image,
+            Rotation::Clockwise => image.rotate90(false),
+            Rotation::CounterClockwise => image.rotate90(true),
+        }
+    }
+}
+
+/// A sequence of frames played over time.
+///
+/// Animations loop, each frame shown for its own duration.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Animation {
+    frames: Vec<SpriteFrame>,
+    duration: Duration,
+}
+
+impl Animation {
+    /// Creates an animation from the frames of a tag, in the order of its direction.
+    ///
+    /// # Arguments
+    /// * `meta` - The sprite metadata, which provides the frames.
+    /// * `tag` - The tag to play. Frames missing from the metadata are skipped.
+    pub fn new(meta: &impl SpriteMeta, tag: &FrameTag) -> Self {
+        let (from, to) = (tag.from.min(tag.to), tag.from.max(tag.to));
+        // Frames between the ends, which are played twice in ping-pong directions.
+        let inner = from + 1..to;
+
+        let indices: Vec<usize> = match tag.direction {
+            Direction::Forward => (from..=to).collect(),
+            Direction::Reverse => (from..=to).rev().collect(),
+            Direction::PingPong => (from..=to).chain(inner.rev()).collect(),
+            Direction::PingPongReverse => (from..=to).rev().chain(inner).collect(),
+        };
+        Self::from_frames(indices.into_iter().filter_map(|index| meta.frame(index)))
+    }
+
+    /// Creates an animation from a tag by its name.
+    ///
+    /// # Returns
+    /// * `Some(Animation)` if the tag exists.
+    /// * `None` otherwise.
+    pub fn from_tag(meta: &impl SpriteMeta, name: &str) -> Option<Self> {
+        meta.tag(name).map(|tag| Self::new(meta, &tag))
+    }
+
+    /// Creates an animation over every frame of the sprite sheet.
+    pub fn all(meta: &impl SpriteMeta) -> Self {
+        Self::from_frames((0..meta.frame_count()).filter_map(|index| meta.frame(index)))
+    }
+
+    /// Creates an animation from frames in the order they're played.
+    pub fn from_frames(frames: impl IntoIterator<Item = SpriteFrame>) -> Self {
+        let frames = frames.into_iter().collect::<Vec<_>>();
+        let duration = frames.iter().map(|frame| frame.duration).sum();
+        Self { frames, duration }
+    }
+
+    /// Returns the frames in the order they're played.
+    pub fn frames(&self) -> &[SpriteFrame] {
+        &self.frames
+    }
+
+    /// Returns the duration of a single cycle.
+    pub const fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Returns the frame shown after `elapsed` time, looping the animation.
+    ///
+    /// # Returns
+    /// * `Some(&SpriteFrame)` with the current frame.
+    /// * `None` if the animation has no frames.
+    pub fn frame_at(&self, elapsed: Duration) -> Option<&SpriteFrame> {
+        if self.duration.is_zero() {
+            return self.frames.first();
+        }
+        let nanos = elapsed.as_nanos() % self.duration.as_nanos();
+        self.frame_within(Duration::from_nanos(nanos as u64))
+    }
+
+    /// Returns the frame shown after `elapsed` time, playing the animation once
+    /// and then holding the last frame.
+    pub fn frame_at_once(&self, elapsed: Duration) -> Option<&SpriteFrame> {
+        match self.is_finished(elapsed) {
+            true => self.frames.last(),
+            false => self.frame_within(elapsed),
+        }
+    }
+
+    /// Returns whether a single cycle is over after `elapsed` time.
+    pub fn is_finished(&self, elapsed: Duration) -> bool {
+        elapsed >= self.duration
+    }
+
+    /// Finds the frame shown at a time within the first cycle.
+    fn frame_within(&self, mut elapsed: Duration) -> Option<&SpriteFrame> {
+        for frame in &self.frames {
+            if elapsed < frame.duration {
+                return Some(frame);
+            }
+            elapsed -= frame.duration;
+        }
+        self.frames.last()
+    }
+}
+
+/// Container combining loaded metadata and its associated image pages
+pub struct Sprite<I: Asset, M: Asset>
+where
+    I::Error: Error,
+    M::Error: Error,
+{
+    /// The first image page, the only one of single-page sprites.
+    pub image: Option<I>,
+    /// Image pages after the first, in the order of [SpriteMeta::image_paths].
+    pub extra_pages: Vec<I>,
+    pub meta: M,
+}
+
+impl<I: Asset, M: SpriteMeta> Sprite<I, M>
+where
+    I::Error: Error,
+    M::Error: Error,
+{
+    /// Returns an image page by its index, `0` being [Sprite::image].
+    pub fn page(&self, index: usize) -> Option<&I> {
+        match index {
+            0 => self.image.as_ref(),
+            _ => self.extra_pages.get(index - 1),
+        }
+    }
+
+    /// Returns every image page in the order of [SpriteMeta::image_paths].
+    pub fn pages(&self) -> impl Iterator<Item = &I> {
+        self.image.iter().chain(&self.extra_pages)
+    }
+
+    /// Returns the animation of a tag by its name. See [Animation::from_tag].
+    pub fn animation(&self, tag: &str) -> Option<Animation> {
+        Animation::from_tag(&self.meta, tag)
+    }
+
+    /// Cuts a frame out of its image page, turning rotated frames back upright. See [Crop::crop_frame].
+    ///
+    /// # Returns
+    /// * `Some(I)` with the frame image.
+    /// * `None` if the frame or its page is missing.
+    pub fn frame_image(&self, index: usize) -> Option<I>
+    where
+        I: Crop,
+    {
+        let frame = self.meta.frame(index)?;
+        Some(self.page(frame.page)?.crop_frame(&frame))
+    }
+}
+
+/// Unified error type for sprite loading operations
+#[derive(Debug, Error)]
+pub enum SpriteError<M: Error, I: Error> {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Metadata error: {0}")]
+    Meta(M),
+
+    #[error("Image error: {0}")]
+    Image(I),
+}
+
+impl<M, I> From<SpriteError<M, I>> for crate::Error
+where
+    M: Error + Send + Sync + 'static,
+    I: Error + Send + Sync + 'static,
+{
+    fn from(err: SpriteError<M, I>) -> Self {
+        match err {
+            SpriteError::Io(err) => err.into(),
+            err => Self::new(ErrorKind::Asset, err),
+        }
+    }
+}
+
+impl<I: Asset, M: SpriteMeta> Asset for Sprite<I, M>
+where
+    I::Error: Error,
+    M::Error: Error,
+{
+    type Error = SpriteError<M::Error, I::Error>;
+
+    fn load(pack: &mut impl Pack, path: impl AsRef<Path>) -> AssetResult<Self> {
+        let meta = pack.get::<M>(&path).map_err(SpriteError::Meta)?;
+        let mut pages = meta
+            .image_paths(path)
+            .into_iter()
+            .map(|path| pack.get::<I>(path).map_err(SpriteError::Image))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter();
+
+        Ok(Self {
+            image: pages.next(),
+            extra_pages: pages.collect(),
+            meta,
+        })
+    }
+}
@@ -0,0 +1,662 @@
+/* This module loads maps made with the Tiled editor, in the XML (`.tmx`) and the JSON (`.tmj`) format.
+External tilesets (`.tsx`/`.tsj`) and images are resolved relative to the file that references them and
+read from the same pack. `Map` is the typed map alone, `TiledMap` also loads its images as any asset.
+Object templates (`.tx`) and tile collision shapes aren't read. */
+
+mod tmj;
+mod tmx;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    io::{self, Read},
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use thiserror::Error;
+
+use super::sprite::{Rect, SpriteError};
+use crate::{
+    Asset, AssetResult, Pack,
+    path::{PathError, VirtualPath},
+};
+
+/// Flag of global tile IDs: the tile is flipped horizontally.
+pub const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+/// Flag of global tile IDs: the tile is flipped vertically.
+pub const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+/// Flag of global tile IDs: the tile is flipped diagonally, or rotated by 60 degrees in hexagonal maps.
+pub const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+/// Flag of global tile IDs: the tile is rotated by 120 degrees in hexagonal maps.
+pub const ROTATED_HEXAGONAL_120: u32 = 0x1000_0000;
+
+/// Every flag stored in the highest bits of global tile IDs.
+pub const FLIP_FLAGS: u32 =
+    FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL_120;
+
+/// Custom properties of maps, tilesets, tiles, layers and objects.
+pub type Properties = BTreeMap<String, PropertyValue>;
+
+/// The value of a custom property.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue {
+    String(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    /// A color as written by Tiled, e.g. `#ff00ff00`.
+    Color(String),
+    /// A file path as written by Tiled, relative to the file the property is in.
+    File(String),
+    /// The ID of an object on the map, or 0 for none.
+    Object(u32),
+    /// The members of a custom class.
+    Class(Properties),
+}
+
+/// The projection of a map.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Orientation {
+    #[default]
+    Orthogonal,
+    Isometric,
+    Staggered,
+    Hexagonal,
+}
+
+/// A reference to an image file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ImageRef {
+    /// The path of the image, relative to the pack root.
+    pub source: PathBuf,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// A Tiled map.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Map {
+    pub orientation: Orientation,
+    /// The size of the map in tiles.
+    pub width: u32,
+    pub height: u32,
+    /// The size of a tile in pixels.
+    pub tile_width: u32,
+    pub tile_height: u32,
+    /// Whether the tile layers are stored in chunks, see [TileLayer::chunks].
+    pub infinite: bool,
+    pub background_color: Option<String>,
+    pub class: String,
+    pub properties: Properties,
+    pub tilesets: Vec<Tileset>,
+    pub layers: Vec<Layer>,
+}
+
+/// A tileset, embedded in the map or loaded from an external file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tileset {
+    /// The global ID of the first tile of the tileset.
+    pub first_gid: u32,
+    /// The path of the external tileset file relative to the pack root, if the tileset isn't embedded.
+    pub source: Option<PathBuf>,
+    pub name: String,
+    pub class: String,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub spacing: u32,
+    pub margin: u32,
+    pub tile_count: u32,
+    pub columns: u32,
+    /// The image all the tiles are cut out of, unless each tile has its own image.
+    pub image: Option<ImageRef>,
+    /// Tiles that have properties, images or animations.
+    pub tiles: Vec<Tile>,
+    pub properties: Properties,
+}
+
+/// A tile of a tileset with additional information.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tile {
+    /// The local ID of the tile in its tileset.
+    pub id: u32,
+    pub class: String,
+    /// The image of the tile in image collection tilesets.
+    pub image: Option<ImageRef>,
+    pub animation: Vec<AnimationFrame>,
+    pub properties: Properties,
+}
+
+/// A frame of a tile animation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AnimationFrame {
+    /// The local ID of the tile shown.
+    pub tile_id: u32,
+    pub duration: Duration,
+}
+
+/// A layer of a map.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    pub id: u32,
+    pub name: String,
+    pub class: String,
+    pub visible: bool,
+    pub opacity: f32,
+    /// The offset of the layer in pixels.
+    pub offset: (f32, f32),
+    pub properties: Properties,
+    pub kind: LayerKind,
+}
+
+/// The contents of a layer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayerKind {
+    Tiles(TileLayer),
+    Objects(Vec<Object>),
+    Image(Option<ImageRef>),
+    Group(Vec<Layer>),
+}
+
+/// A grid of global tile IDs, where 0 is an empty cell.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TileLayer {
+    pub width: u32,
+    pub height: u32,
+    /// Row-major tiles of finite maps.
+    pub tiles: Vec<u32>,
+    /// Chunks of tiles of infinite maps.
+    pub chunks: Vec<Chunk>,
+}
+
+/// A rectangular part of a tile layer of an infinite map.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Chunk {
+    /// The position of the chunk in tiles.
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    /// Row-major tiles of the chunk.
+    pub tiles: Vec<u32>,
+}
+
+/// An object placed on an object layer.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Object {
+    pub id: u32,
+    pub name: String,
+    pub class: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// The rotation in degrees, clockwise.
+    pub rotation: f32,
+    /// The global tile ID of tile objects.
+    pub gid: Option<u32>,
+    pub visible: bool,
+    pub shape: Shape,
+    pub properties: Properties,
+}
+
+/// The shape of an object.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Shape {
+    #[default]
+    Rectangle,
+    Ellipse,
+    Point,
+    /// Points relative to the object position.
+    Polygon(Vec<(f32, f32)>),
+    /// Points relative to the object position.
+    Polyline(Vec<(f32, f32)>),
+    Text(String),
+}
+
+impl Map {
+    /// Finds the tileset of a global tile ID, ignoring its flip flags.
+    ///
+    /// # Returns
+    /// * `Some((&Tileset, u32))` with the tileset and the local ID of the tile.
+    /// * `None` for empty cells and IDs outside every tileset.
+    pub fn tileset_of(&self, gid: u32) -> Option<(&Tileset, u32)> {
+        let gid = gid & !FLIP_FLAGS;
+        if gid == 0 {
+            return None;
+        }
+
+        let tileset = self
+            .tilesets
+            .iter()
+            .rev()
+            .find(|set| set.first_gid <= gid)?;
+        Some((tileset, gid - tileset.first_gid))
+    }
+
+    /// Returns the first layer with the given name, searching groups depth-first.
+    pub fn layer(&self, name: &str) -> Option<&Layer> {
+        fn find<'l>(layers: &'l [Layer], name: &str) -> Option<&'l Layer> {
+            layers.iter().find_map(|layer| match &layer.kind {
+                _ if layer.name == name => Some(layer),
+                LayerKind::Group(layers) => find(layers, name),
+                _ => None,
+            })
+        }
+        find(&self.layers, name)
+    }
+
+    /// Returns the paths of every image the map references, without duplicates.
+    pub fn image_paths(&self) -> Vec<PathBuf> {
+        fn layer_images<'l>(layers: &'l [Layer], images: &mut Vec<&'l ImageRef>) {
+            for layer in layers {
+                match &layer.kind {
+                    LayerKind::Image(Some(image)) => images.push(image),
+                    LayerKind::Group(layers) => layer_images(layers, images),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut images = Vec::new();
+        for tileset in &self.tilesets {
+            images.extend(&tileset.image);
+            images.extend(tileset.tiles.iter().filter_map(|tile| tile.image.as_ref()));
+        }
+        layer_images(&self.layers, &mut images);
+
+        let mut paths: Vec<PathBuf> = Vec::with_capacity(images.len());
+        for image in images {
+            if !paths.contains(&image.source) {
+                paths.push(image.source.clone());
+            }
+        }
+        paths
+    }
+}
+
+impl Tileset {
+    /// Returns the additional information of a tile by its local ID.
+    pub fn tile(&self, id: u32) -> Option<&Tile> {
+        self.tiles.iter().find(|tile| tile.id == id)
+    }
+
+    /// Returns where a tile is in the tileset image.
+    ///
+    /// # Returns
+    /// * `Some(Rect)` for tiles of single-image tilesets.
+    /// * `None` if the ID is out of range or the tileset has no columns.
+    pub fn tile_rect(&self, id: u32) -> Option<Rect> {
+        if self.columns == 0 || id >= self.tile_count {
+            return None;
+        }
+
+        let (column, row) = (id % self.columns, id / self.columns);
+        // Sizes come from the file, so they may be large enough to overflow
+        let offset = |index: u32, size: u32| {
+            let step = size.checked_add(self.spacing)?;
+            index.checked_mul(step)?.checked_add(self.margin)
+        };
+        Some(Rect {
+            x: offset(column, self.tile_width)?,
+            y: offset(row, self.tile_height)?,
+            width: self.tile_width,
+            height: self.tile_height,
+        })
+    }
+}
+
+impl TileLayer {
+    /// Returns the global tile ID at a cell, looking through the chunks of infinite maps.
+    ///
+    /// # Returns
+    /// * `Some(u32)` with the ID, which is 0 for empty cells.
+    /// * `None` if the cell is outside the layer.
+    pub fn gid(&self, x: i32, y: i32) -> Option<u32> {
+        let index = |x: i32, y: i32, width: u32, height: u32| {
+            let (x, y) = (u32::try_from(x).ok()?, u32::try_from(y).ok()?);
+            if x >= width || y >= height {
+                return None;
+            }
+            usize::try_from(y as u64 * width as u64 + x as u64).ok()
+        };
+
+        if self.chunks.is_empty() {
+            return self
+                .tiles
+                .get(index(x, y, self.width, self.height)?)
+                .copied();
+        }
+        self.chunks.iter().find_map(|chunk| {
+            let (x, y) = (x.checked_sub(chunk.x)?, y.checked_sub(chunk.y)?);
+            let index = index(x, y, chunk.width, chunk.height)?;
+            chunk.tiles.get(index).copied()
+        })
+    }
+}
+
+/// Error type for Tiled loading operations
+#[derive(Debug, Error)]
+pub enum TiledError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Path error: {0}")]
+    Path(#[from] PathError),
+
+    #[error("Invalid XML in {path:?}: {source}")]
+    Xml {
+        path: PathBuf,
+        source: roxmltree::Error,
+    },
+
+    #[error("Invalid JSON in {path:?}: {source}")]
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+
+    #[error("Invalid Tiled file {path:?}: {message}")]
+    Invalid { path: PathBuf, message: String },
+
+    #[error("Unknown Tiled file format: {0:?}")]
+    UnknownFormat(PathBuf),
+}
+
+impl TiledError {
+    /// Returns the path of the file the error occurred in, if known.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Io(_) | Self::Path(_) => None,
+            Self::Xml { path, .. }
+            | Self::Json { path, .. }
+            | Self::Invalid { path, .. }
+            | Self::UnknownFormat(path) => Some(path),
+        }
+    }
+}
+
+impl From<TiledError> for crate::Error {
+    fn from(err: TiledError) -> Self {
+        match err {
+            TiledError::Io(err) => err.into(),
+            TiledError::Path(err) => err.into(),
+            err => Self::asset(err.path().map(Path::to_path_buf), err),
+        }
+    }
+}
+
+/// A Tiled file being parsed, which resolves the paths it references.
+struct Source {
+    path: VirtualPath,
+}
+
+impl Source {
+    fn new(path: &Path) -> Result<Self, TiledError> {
+        Ok(Self {
+            path: VirtualPath::new(path)?,
+        })
+    }
+
+    /// Resolves a path relative to the directory of this file.
+    fn resolve(&self, path: &str) -> Result<PathBuf, TiledError> {
+        Ok(self.path.resolve_link(path)?.as_str().into())
+    }
+
+    fn invalid(&self, message: impl Into<String>) -> TiledError {
+        TiledError::Invalid {
+            path: self.path.as_str().into(),
+            message: message.into(),
+        }
+    }
+
+    /// Returns the number of tiles of a layer or a chunk, failing if it overflows.
+    fn tile_count(&self, width: u32, height: u32) -> Result<usize, TiledError> {
+        (width as usize)
+            .checked_mul(height as usize)
+            .ok_or_else(|| self.invalid(format!("{width}x{height} tiles are too many")))
+    }
+
+    /// Checks that `len` tiles were decoded.
+    fn check_tiles(&self, tiles: Vec<u32>, len: usize) -> Result<Vec<u32>, TiledError> {
+        match tiles.len() == len {
+            true => Ok(tiles),
+            false => Err(self.invalid(format!("{} tiles, expected {len}", tiles.len()))),
+        }
+    }
+
+    /// Decodes the tiles of a layer or a chunk.
+    ///
+    /// # Arguments
+    /// * `data` - The CSV or Base64 text.
+    /// * `encoding` - `csv` or `base64`.
+    /// * `compression` - `zlib`, `gzip` or `zstd` for Base64 data, if compressed.
+    /// * `len` - The number of tiles expected.
+    fn decode_tiles(
+        &self,
+        data: &str,
+        encoding: &str,
+        compression: Option<&str>,
+        len: usize,
+    ) -> Result<Vec<u32>, TiledError> {
+        let tiles = match encoding {
+            "csv" => data
+                .split(',')
+                .map(|gid| gid.trim().parse())
+                .collect::<Result<Vec<u32>, _>>()
+                .map_err(|err| self.invalid(format!("invalid CSV tile data: {err}")))?,
+            "base64" => {
+                use base64::Engine;
+
+                let data: String = data.split_whitespace().collect();
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(data)
+                    .map_err(|err| self.invalid(format!("invalid Base64 tile data: {err}")))?;
+                // One byte more than expected is enough to detect oversized data
+                let limit = (len as u64)
+                    .checked_mul(4)
+                    .and_then(|size| size.checked_add(1))
+                    .ok_or_else(|| self.invalid(format!("{len} tiles are too many")))?;
+                let bytes = self.decompress(bytes, compression, limit)?;
+
+                let chunks = bytes.chunks_exact(4);
+                let gids = chunks.map(|gid| u32::from_le_bytes(gid.try_into().unwrap()));
+                gids.collect()
+            }
+            encoding => return Err(self.invalid(format!("unknown tile encoding `{encoding}`"))),
+        };
+        self.check_tiles(tiles, len)
+    }
+
+    fn decompress(
+        &self,
+        bytes: Vec<u8>,
+        compression: Option<&str>,
+        limit: u64,
+    ) -> Result<Vec<u8>, TiledError> {
+        let mut out = Vec::new();
+        match compression {
+            None | Some("") => return Ok(bytes),
+            Some("zlib") => {
+                flate2::read::ZlibDecoder::new(bytes.as_slice())
+                    .take(limit)
+                    .read_to_end(&mut out)?;
+            }
+            Some("gzip") => {
+                flate2::read::GzDecoder::new(bytes.as_slice())
+                    .take(limit)
+                    .read_to_end(&mut out)?;
+            }
+            #[cfg(feature = "zstd")]
+            Some("zstd") => {
+                zstd::stream::Decoder::new(bytes.as_slice())?
+                    .take(limit)
+                    .read_to_end(&mut out)?;
+            }
+            Some(compression) => {
+                return Err(self.invalid(format!("unsupported compression `{compression}`")));
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// The format of a Tiled file, chosen by its extension.
+enum Format {
+    Xml,
+    Json,
+}
+
+impl Format {
+    fn of(path: &Path, xml: &str, json: &str) -> Result<Self, TiledError> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case(xml) => Ok(Self::Xml),
+            Some(ext) if ext.eq_ignore_ascii_case(json) || ext.eq_ignore_ascii_case("json") => {
+                Ok(Self::Json)
+            }
+            _ => Err(TiledError::UnknownFormat(path.to_path_buf())),
+        }
+    }
+}
+
+fn read_text(pack: &mut impl Pack, path: &Path) -> Result<String, TiledError> {
+    let mut raw = pack.get_raw(path)?;
+
+    let mut text = String::with_capacity(raw.size_hint.unwrap_or(0));
+    raw.read.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+impl Map {
+    /// Parses a map without loading its external tilesets, which keep only their `first_gid` and `source`.
+    ///
+    /// # Arguments
+    /// * `text` - The contents of the map file.
+    /// * `path` - The path of the map in the pack, which decides the format and resolves referenced paths.
+    pub fn parse(text: &str, path: impl AsRef<Path>) -> Result<Self, TiledError> {
+        let path = path.as_ref();
+        let source = Source::new(path)?;
+        match Format::of(path, "tmx", "tmj")? {
+            Format::Xml => tmx::parse_map(text, &source),
+            Format::Json => tmj::parse_map(text, &source),
+        }
+    }
+}
+
+impl Tileset {
+    /// Parses an external tileset file.
+    ///
+    /// # Arguments
+    /// * `text` - The contents of the tileset file.
+    /// * `path` - The path of the tileset in the pack, which decides the format and resolves referenced paths.
+    /// * `first_gid` - The global ID of the first tile, which is set by the map.
+    pub fn parse(text: &str, path: impl AsRef<Path>, first_gid: u32) -> Result<Self, TiledError> {
+        let path = path.as_ref();
+        let source = Source::new(path)?;
+        let tileset = match Format::of(path, "tsx", "tsj")? {
+            Format::Xml => tmx::parse_tileset_file(text, &source)?,
+            Format::Json => tmj::parse_tileset_file(text, &source)?,
+        };
+
+        Ok(Self {
+            first_gid,
+            source: Some(path.to_path_buf()),
+            ..tileset
+        })
+    }
+}
+
+impl Asset for Map {
+    type Error = TiledError;
+
+    fn load(pack: &mut impl Pack, path: impl AsRef<Path>) -> AssetResult<Self> {
+        let path = path.as_ref();
+        let mut map = Self::parse(&read_text(pack, path)?, path)?;
+
+        for tileset in &mut map.tilesets {
+            if let Some(source) = tileset.source.take() {
+                let text = read_text(pack, &source)?;
+                *tileset = Tileset::parse(&text, source, tileset.first_gid)?;
+            }
+        }
+        Ok(map)
+    }
+}
+
+/// Container combining a loaded map and the images it references
+pub struct TiledMap<I: Asset>
+where
+    I::Error: Error,
+{
+    pub map: Map,
+    /// Loaded images by their paths, see [Map::image_paths].
+    pub images: HashMap<PathBuf, I>,
+}
+
+impl<I: Asset> TiledMap<I>
+where
+    I::Error: Error,
+{
+    /// Returns the loaded image of a reference.
+    pub fn image(&self, image: &ImageRef) -> Option<&I> {
+        self.images.get(&image.source)
+    }
+
+    /// Returns the image of a tile and where the tile is in it.
+    ///
+    /// # Returns
+    /// * `Some((&I, Some(Rect)))` for tiles of single-image tilesets.
+    /// * `Some((&I, None))` for tiles with their own image.
+    /// * `None` for empty cells and unknown tiles.
+    pub fn tile_image(&self, gid: u32) -> Option<(&I, Option<Rect>)> {
+        let (tileset, id) = self.map.tileset_of(gid)?;
+        if let Some(image) = tileset.tile(id).and_then(|tile| tile.image.as_ref()) {
+            return Some((self.image(image)?, None));
+        }
+        Some((self.image(tileset.image.as_ref()?)?, tileset.tile_rect(id)))
+    }
+}
+
+impl<I: Asset> Deref for TiledMap<I>
+where
+    I::Error: Error,
+{
+    type Target = Map;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+impl<I: Asset> DerefMut for TiledMap<I>
+where
+    I::Error: Error,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.map
+    }
+}
+
+impl<I: Asset> Asset for TiledMap<I>
+where
+    I::Error: Error,
+{
+    type Error = SpriteError<TiledError, I::Error>;
+
+    fn load(pack: &mut impl Pack, path: impl AsRef<Path>) -> AssetResult<Self> {
+        let map = pack.get::<Map>(path).map_err(SpriteError::Meta)?;
+        let images = map
+            .image_paths()
+            .into_iter()
+            .map(|path| {
+                Ok((
+                    path.clone(),
+                    pack.get::<I>(path).map_err(SpriteError::Image)?,
+                ))
+            })
+            .collect::<Result<_, Self::Error>>()?;
+
+        Ok(Self { map, images })
+    }
+}
@@ -0,0 +1,479 @@
+/* Parser of the JSON formats: maps (`.tmj`) and tilesets (`.tsj`). The documents are deserialized into
+private structures mirroring the JSON, then converted into the shared types with paths resolved. */
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::time::Duration;
+
+use super::{
+    AnimationFrame, Chunk, ImageRef, Layer, LayerKind, Map, Object, Orientation, Properties,
+    PropertyValue, Shape, Source, Tile, TileLayer, TiledError, Tileset,
+};
+
+#[derive(Deserialize)]
+struct JsonMap {
+    #[serde(default)]
+    orientation: Option<String>,
+    #[serde(default)]
+    width: u32,
+    #[serde(default)]
+    height: u32,
+    #[serde(default)]
+    tilewidth: u32,
+    #[serde(default)]
+    tileheight: u32,
+    #[serde(default)]
+    infinite: bool,
+    backgroundcolor: Option<String>,
+    #[serde(default)]
+    class: String,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+    #[serde(default)]
+    tilesets: Vec<JsonTileset>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct JsonTileset {
+    firstgid: Option<u32>,
+    source: Option<String>,
+    name: String,
+    class: String,
+    tilewidth: u32,
+    tileheight: u32,
+    spacing: u32,
+    margin: u32,
+    tilecount: u32,
+    columns: u32,
+    image: Option<String>,
+    imagewidth: Option<u32>,
+    imageheight: Option<u32>,
+    tiles: Vec<JsonTile>,
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct JsonTile {
+    id: u32,
+    // `type` before Tiled 1.9
+    #[serde(alias = "type")]
+    class: String,
+    image: Option<String>,
+    imagewidth: Option<u32>,
+    imageheight: Option<u32>,
+    animation: Vec<JsonFrame>,
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Deserialize)]
+struct JsonFrame {
+    tileid: u32,
+    duration: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+struct JsonLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    id: u32,
+    name: String,
+    class: String,
+    visible: bool,
+    opacity: f32,
+    offsetx: f32,
+    offsety: f32,
+    properties: Vec<JsonProperty>,
+    width: Option<u32>,
+    height: Option<u32>,
+    data: Option<JsonData>,
+    encoding: Option<String>,
+    compression: Option<String>,
+    chunks: Vec<JsonChunk>,
+    objects: Vec<JsonObject>,
+    image: Option<String>,
+    imagewidth: Option<u32>,
+    imageheight: Option<u32>,
+    layers: Vec<JsonLayer>,
+}
+
+impl Default for JsonLayer {
+    fn default() -> Self {
+        Self {
+            kind: String::new(),
+            id: 0,
+            name: String::new(),
+            class: String::new(),
+            visible: true,
+            opacity: 1.0,
+            offsetx: 0.0,
+            offsety: 0.0,
+            properties: Vec::new(),
+            width: None,
+            height: None,
+            data: None,
+            encoding: None,
+            compression: None,
+            chunks: Vec::new(),
+            objects: Vec::new(),
+            image: None,
+            imagewidth: None,
+            imageheight: None,
+            layers: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonData {
+    Tiles(Vec<u32>),
+    Encoded(String),
+}
+
+#[derive(Deserialize)]
+struct JsonChunk {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    data: JsonData,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+struct JsonObject {
+    id: u32,
+    name: String,
+    // `type` before Tiled 1.9
+    #[serde(alias = "type")]
+    class: String,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    rotation: f32,
+    gid: Option<u32>,
+    visible: bool,
+    ellipse: bool,
+    point: bool,
+    polygon: Option<Vec<JsonPoint>>,
+    polyline: Option<Vec<JsonPoint>>,
+    text: Option<JsonText>,
+    properties: Vec<JsonProperty>,
+}
+
+impl Default for JsonObject {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            name: String::new(),
+            class: String::new(),
+            x: 0.0,
+            y: 0.0,
+            width: 0.0,
+            height: 0.0,
+            rotation: 0.0,
+            gid: None,
+            visible: true,
+            ellipse: false,
+            point: false,
+            polygon: None,
+            polyline: None,
+            text: None,
+            properties: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct JsonPoint {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Deserialize)]
+struct JsonText {
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct JsonProperty {
+    name: String,
+    #[serde(rename = "type", default)]
+    kind: Option<String>,
+    #[serde(default)]
+    value: Value,
+}
+
+pub(super) fn parse_map(text: &str, source: &Source) -> Result<Map, TiledError> {
+    let map: JsonMap = parse_document(text, source)?;
+
+    let orientation = match map.orientation.as_deref().unwrap_or("orthogonal") {
+        "orthogonal" => Orientation::Orthogonal,
+        "isometric" => Orientation::Isometric,
+        "staggered" => Orientation::Staggered,
+        "hexagonal" => Orientation::Hexagonal,
+        other => return Err(source.invalid(format!("unknown orientation `{other}`"))),
+    };
+
+    let tilesets = map.tilesets.into_iter().map(|tileset| {
+        let first_gid = tileset.firstgid.unwrap_or(1);
+        Ok(match &tileset.source {
+            // External tilesets are loaded by `Map::load`
+            Some(path) => Tileset {
+                first_gid,
+                source: Some(source.resolve(path)?),
+                ..Tileset::default()
+            },
+            None => Tileset {
+                first_gid,
+                ..convert_tileset(tileset, source)?
+            },
+        })
+    });
+    let tilesets = tilesets.collect::<Result<_, TiledError>>()?;
+
+    let (width, height, infinite) = (map.width, map.height, map.infinite);
+    let layers = map.layers.into_iter();
+    let layers = layers.map(|layer| convert_layer(layer, source, (width, height), infinite));
+
+    Ok(Map {
+        orientation,
+        width,
+        height,
+        tile_width: map.tilewidth,
+        tile_height: map.tileheight,
+        infinite,
+        background_color: map.backgroundcolor,
+        class: map.class,
+        properties: properties(map.properties, source)?,
+        tilesets,
+        layers: layers.collect::<Result<_, _>>()?,
+    })
+}
+
+pub(super) fn parse_tileset_file(text: &str, source: &Source) -> Result<Tileset, TiledError> {
+    convert_tileset(parse_document(text, source)?, source)
+}
+
+fn parse_document<T: for<'de> Deserialize<'de>>(
+    text: &str,
+    source: &Source,
+) -> Result<T, TiledError> {
+    serde_json::from_str(text).map_err(|err| TiledError::Json {
+        path: source.path.as_str().into(),
+        source: err,
+    })
+}
+
+fn convert_tileset(tileset: JsonTileset, source: &Source) -> Result<Tileset, TiledError> {
+    let tiles = tileset.tiles.into_iter().map(|tile| {
+        Ok(Tile {
+            id: tile.id,
+            class: tile.class,
+            image: image(tile.image, tile.imagewidth, tile.imageheight, source)?,
+            animation: (tile.animation.iter())
+                .map(|frame| AnimationFrame {
+                    tile_id: frame.tileid,
+                    duration: Duration::from_millis(frame.duration),
+                })
+                .collect(),
+            properties: properties(tile.properties, source)?,
+        })
+    });
+
+    Ok(Tileset {
+        first_gid: 0,
+        source: None,
+        name: tileset.name,
+        class: tileset.class,
+        tile_width: tileset.tilewidth,
+        tile_height: tileset.tileheight,
+        spacing: tileset.spacing,
+        margin: tileset.margin,
+        tile_count: tileset.tilecount,
+        columns: tileset.columns,
+        image: image(
+            tileset.image,
+            tileset.imagewidth,
+            tileset.imageheight,
+            source,
+        )?,
+        tiles: tiles.collect::<Result<_, TiledError>>()?,
+        properties: properties(tileset.properties, source)?,
+    })
+}
+
+fn convert_layer(
+    layer: JsonLayer,
+    source: &Source,
+    size: (u32, u32),
+    infinite: bool,
+) -> Result<Layer, TiledError> {
+    let compression = layer.compression.as_deref();
+    let decode = |data: JsonData, len: usize| match data {
+        JsonData::Tiles(tiles) => source.check_tiles(tiles, len),
+        JsonData::Encoded(data) => {
+            let encoding = layer.encoding.as_deref().unwrap_or("base64");
+            source.decode_tiles(&data, encoding, compression, len)
+        }
+    };
+
+    let kind = match layer.kind.as_str() {
+        "tilelayer" => {
+            let width = layer.width.unwrap_or(size.0);
+            let height = layer.height.unwrap_or(size.1);
+            let mut tiles = TileLayer {
+                width,
+                height,
+                ..TileLayer::default()
+            };
+
+            if infinite {
+                for chunk in layer.chunks {
+                    let len = source.tile_count(chunk.width, chunk.height)?;
+                    tiles.chunks.push(Chunk {
+                        x: chunk.x,
+                        y: chunk.y,
+                        width: chunk.width,
+                        height: chunk.height,
+                        tiles: decode(chunk.data, len)?,
+                    });
+                }
+            } else if let Some(data) = layer.data {
+                tiles.tiles = decode(data, source.tile_count(width, height)?)?;
+            }
+            LayerKind::Tiles(tiles)
+        }
+        "objectgroup" => LayerKind::Objects(
+            (layer.objects.into_iter())
+                .map(|object| convert_object(object, source))
+                .collect::<Result<_, _>>()?,
+        ),
+        "imagelayer" => {
+            let path = layer.image.filter(|path| !path.is_empty());
+            LayerKind::Image(image(path, layer.imagewidth, layer.imageheight, source)?)
+        }
+        "group" => LayerKind::Group(
+            (layer.layers.into_iter())
+                .map(|layer| convert_layer(layer, source, size, infinite))
+                .collect::<Result<_, _>>()?,
+        ),
+        kind => return Err(source.invalid(format!("unknown layer type `{kind}`"))),
+    };
+
+    Ok(Layer {
+        id: layer.id,
+        name: layer.name,
+        class: layer.class,
+        visible: layer.visible,
+        opacity: layer.opacity,
+        offset: (layer.offsetx, layer.offsety),
+        properties: properties(layer.properties, source)?,
+        kind,
+    })
+}
+
+fn convert_object(object: JsonObject, source: &Source) -> Result<Object, TiledError> {
+    let points = |points: Vec<JsonPoint>| points.into_iter().map(|p| (p.x, p.y)).collect();
+    let shape = if let Some(polygon) = object.polygon {
+        Shape::Polygon(points(polygon))
+    } else if let Some(polyline) = object.polyline {
+        Shape::Polyline(points(polyline))
+    } else if let Some(text) = object.text {
+        Shape::Text(text.text)
+    } else if object.ellipse {
+        Shape::Ellipse
+    } else if object.point {
+        Shape::Point
+    } else {
+        Shape::Rectangle
+    };
+
+    Ok(Object {
+        id: object.id,
+        name: object.name,
+        class: object.class,
+        x: object.x,
+        y: object.y,
+        width: object.width,
+        height: object.height,
+        rotation: object.rotation,
+        gid: object.gid,
+        visible: object.visible,
+        shape,
+        properties: properties(object.properties, source)?,
+    })
+}
+
+fn properties(properties: Vec<JsonProperty>, source: &Source) -> Result<Properties, TiledError> {
+    (properties.into_iter())
+        .map(|property| {
+            let invalid =
+                || source.invalid(format!("invalid value of property `{}`", property.name));
+            let string = || {
+                property
+                    .value
+                    .as_str()
+                    .map(str::to_owned)
+                    .ok_or_else(invalid)
+            };
+
+            let value = match property.kind.as_deref().unwrap_or("string") {
+                "int" => PropertyValue::Int(property.value.as_i64().ok_or_else(invalid)?),
+                "float" => PropertyValue::Float(property.value.as_f64().ok_or_else(invalid)?),
+                "bool" => PropertyValue::Bool(property.value.as_bool().ok_or_else(invalid)?),
+                "color" => PropertyValue::Color(string()?),
+                "file" => PropertyValue::File(string()?),
+                "object" => {
+                    let id = property.value.as_u64().and_then(|id| id.try_into().ok());
+                    PropertyValue::Object(id.ok_or_else(invalid)?)
+                }
+                "class" => class_members(&property.value),
+                _ => PropertyValue::String(string()?),
+            };
+            Ok((property.name, value))
+        })
+        .collect()
+}
+
+/// Converts the members of a class property, whose types aren't stored in JSON.
+fn class_members(value: &Value) -> PropertyValue {
+    match value {
+        Value::Object(members) => PropertyValue::Class(
+            (members.iter())
+                .map(|(name, value)| (name.clone(), class_members(value)))
+                .collect(),
+        ),
+        Value::Bool(value) => PropertyValue::Bool(*value),
+        Value::Number(number) => match number.as_i64() {
+            Some(value) => PropertyValue::Int(value),
+            None => PropertyValue::Float(number.as_f64().unwrap_or_default()),
+        },
+        Value::String(value) => PropertyValue::String(value.clone()),
+        Value::Null | Value::Array(_) => PropertyValue::Class(Properties::new()),
+    }
+}
+
+fn image(
+    path: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    source: &Source,
+) -> Result<Option<ImageRef>, TiledError> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+
+    Ok(Some(ImageRef {
+        source: source.resolve(&path)?,
+        width,
+        height,
+    }))
+}
@@ -0,0 +1,312 @@
+/* Parser of the XML formats: maps (`.tmx`) and tilesets (`.tsx`). */
+
+use roxmltree::{Document, Node};
+use std::{str::FromStr, time::Duration};
+
+use super::{
+    AnimationFrame, Chunk, ImageRef, Layer, LayerKind, Map, Object, Orientation, Properties,
+    PropertyValue, Shape, Source, Tile, TileLayer, TiledError, Tileset,
+};
+
+pub(super) fn parse_map(text: &str, source: &Source) -> Result<Map, TiledError> {
+    let document = parse_document(text, source)?;
+    let root = document.root_element();
+    if !root.has_tag_name("map") {
+        return Err(source.invalid(format!(
+            "expected <map>, found <{}>",
+            root.tag_name().name()
+        )));
+    }
+
+    let orientation = match root.attribute("orientation").unwrap_or("orthogonal") {
+        "orthogonal" => Orientation::Orthogonal,
+        "isometric" => Orientation::Isometric,
+        "staggered" => Orientation::Staggered,
+        "hexagonal" => Orientation::Hexagonal,
+        other => return Err(source.invalid(format!("unknown orientation `{other}`"))),
+    };
+
+    let mut map = Map {
+        orientation,
+        width: attr(source, root, "width")?.unwrap_or(0),
+        height: attr(source, root, "height")?.unwrap_or(0),
+        tile_width: attr(source, root, "tilewidth")?.unwrap_or(0),
+        tile_height: attr(source, root, "tileheight")?.unwrap_or(0),
+        infinite: root.attribute("infinite") == Some("1"),
+        background_color: root.attribute("backgroundcolor").map(str::to_owned),
+        class: class(root),
+        properties: properties(source, root)?,
+        ..Map::default()
+    };
+
+    for node in root.children().filter(Node::is_element) {
+        if node.has_tag_name("tileset") {
+            let first_gid = attr(source, node, "firstgid")?.unwrap_or(1);
+            map.tilesets.push(match node.attribute("source") {
+                // External tilesets are loaded by `Map::load`
+                Some(path) => Tileset {
+                    first_gid,
+                    source: Some(source.resolve(path)?),
+                    ..Tileset::default()
+                },
+                None => Tileset {
+                    first_gid,
+                    ..parse_tileset(node, source)?
+                },
+            });
+        } else if let Some(layer) = parse_layer(node, source, &map)? {
+            map.layers.push(layer);
+        }
+    }
+    Ok(map)
+}
+
+pub(super) fn parse_tileset_file(text: &str, source: &Source) -> Result<Tileset, TiledError> {
+    let document = parse_document(text, source)?;
+    let root = document.root_element();
+    match root.has_tag_name("tileset") {
+        true => parse_tileset(root, source),
+        false => Err(source.invalid(format!(
+            "expected <tileset>, found <{}>",
+            root.tag_name().name()
+        ))),
+    }
+}
+
+fn parse_document<'t>(text: &'t str, source: &Source) -> Result<Document<'t>, TiledError> {
+    Document::parse(text).map_err(|err| TiledError::Xml {
+        path: source.path.as_str().into(),
+        source: err,
+    })
+}
+
+fn parse_tileset(node: Node, source: &Source) -> Result<Tileset, TiledError> {
+    let mut tileset = Tileset {
+        name: node.attribute("name").unwrap_or_default().to_owned(),
+        class: class(node),
+        tile_width: attr(source, node, "tilewidth")?.unwrap_or(0),
+        tile_height: attr(source, node, "tileheight")?.unwrap_or(0),
+        spacing: attr(source, node, "spacing")?.unwrap_or(0),
+        margin: attr(source, node, "margin")?.unwrap_or(0),
+        tile_count: attr(source, node, "tilecount")?.unwrap_or(0),
+        columns: attr(source, node, "columns")?.unwrap_or(0),
+        image: image(source, node)?,
+        properties: properties(source, node)?,
+        ..Tileset::default()
+    };
+
+    for tile in children(node, "tile") {
+        let animation = children(tile, "animation").flat_map(|node| children(node, "frame"));
+        let animation = animation
+            .map(|frame| {
+                Ok(AnimationFrame {
+                    tile_id: attr(source, frame, "tileid")?.unwrap_or(0),
+                    duration: Duration::from_millis(attr(source, frame, "duration")?.unwrap_or(0)),
+                })
+            })
+            .collect::<Result<_, TiledError>>()?;
+
+        tileset.tiles.push(Tile {
+            id: attr(source, tile, "id")?.unwrap_or(0),
+            class: class(tile),
+            image: image(source, tile)?,
+            animation,
+            properties: properties(source, tile)?,
+        });
+    }
+    Ok(tileset)
+}
+
+/// Parses a layer element, or returns `None` for other elements.
+fn parse_layer(node: Node, source: &Source, map: &Map) -> Result<Option<Layer>, TiledError> {
+    let kind = match node.tag_name().name() {
+        "layer" => LayerKind::Tiles(parse_tiles(node, source, map)?),
+        "objectgroup" => LayerKind::Objects(
+            children(node, "object")
+                .map(|object| parse_object(object, source))
+                .collect::<Result<_, _>>()?,
+        ),
+        "imagelayer" => LayerKind::Image(image(source, node)?),
+        "group" => {
+            let mut layers = Vec::new();
+            for child in node.children().filter(Node::is_element) {
+                layers.extend(parse_layer(child, source, map)?);
+            }
+            LayerKind::Group(layers)
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(Layer {
+        id: attr(source, node, "id")?.unwrap_or(0),
+        name: node.attribute("name").unwrap_or_default().to_owned(),
+        class: class(node),
+        visible: node.attribute("visible") != Some("0"),
+        opacity: attr(source, node, "opacity")?.unwrap_or(1.0),
+        offset: (
+            attr(source, node, "offsetx")?.unwrap_or(0.0),
+            attr(source, node, "offsety")?.unwrap_or(0.0),
+        ),
+        properties: properties(source, node)?,
+        kind,
+    }))
+}
+
+fn parse_tiles(node: Node, source: &Source, map: &Map) -> Result<TileLayer, TiledError> {
+    let mut layer = TileLayer {
+        width: attr(source, node, "width")?.unwrap_or(map.width),
+        height: attr(source, node, "height")?.unwrap_or(map.height),
+        ..TileLayer::default()
+    };
+    let Some(data) = child(node, "data") else {
+        return Ok(layer);
+    };
+
+    let encoding = data.attribute("encoding");
+    let compression = data.attribute("compression");
+    let decode = |node: Node, len: usize| match encoding {
+        Some(encoding) => {
+            source.decode_tiles(node.text().unwrap_or_default(), encoding, compression, len)
+        }
+        // Without an encoding, every tile is an element
+        None => {
+            let tiles = children(node, "tile")
+                .map(|tile| Ok(attr(source, tile, "gid")?.unwrap_or(0)))
+                .collect::<Result<_, TiledError>>()?;
+            source.check_tiles(tiles, len)
+        }
+    };
+
+    if map.infinite {
+        for chunk in children(data, "chunk") {
+            let (width, height) = (
+                attr(source, chunk, "width")?,
+                attr(source, chunk, "height")?,
+            );
+            let (width, height) = (width.unwrap_or(0), height.unwrap_or(0));
+            layer.chunks.push(Chunk {
+                x: attr(source, chunk, "x")?.unwrap_or(0),
+                y: attr(source, chunk, "y")?.unwrap_or(0),
+                width,
+                height,
+                tiles: decode(chunk, source.tile_count(width, height)?)?,
+            });
+        }
+    } else {
+        layer.tiles = decode(data, source.tile_count(layer.width, layer.height)?)?;
+    }
+    Ok(layer)
+}
+
+fn parse_object(node: Node, source: &Source) -> Result<Object, TiledError> {
+    let points = |node: Node| {
+        let points = node.attribute("points").unwrap_or_default();
+        points
+            .split_whitespace()
+            .map(|point| {
+                let (x, y) = point.split_once(',')?;
+                Some((x.parse().ok()?, y.parse().ok()?))
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| source.invalid(format!("invalid points `{points}`")))
+    };
+
+    let mut shape = node.children().filter(Node::is_element);
+    let shape = match shape.find(|node| !node.has_tag_name("properties")) {
+        Some(shape) => match shape.tag_name().name() {
+            "ellipse" => Shape::Ellipse,
+            "point" => Shape::Point,
+            "polygon" => Shape::Polygon(points(shape)?),
+            "polyline" => Shape::Polyline(points(shape)?),
+            "text" => Shape::Text(shape.text().unwrap_or_default().to_owned()),
+            _ => Shape::Rectangle,
+        },
+        None => Shape::Rectangle,
+    };
+
+    Ok(Object {
+        id: attr(source, node, "id")?.unwrap_or(0),
+        name: node.attribute("name").unwrap_or_default().to_owned(),
+        class: class(node),
+        x: attr(source, node, "x")?.unwrap_or(0.0),
+        y: attr(source, node, "y")?.unwrap_or(0.0),
+        width: attr(source, node, "width")?.unwrap_or(0.0),
+        height: attr(source, node, "height")?.unwrap_or(0.0),
+        rotation: attr(source, node, "rotation")?.unwrap_or(0.0),
+        gid: attr(source, node, "gid")?,
+        visible: node.attribute("visible") != Some("0"),
+        shape,
+        properties: properties(source, node)?,
+    })
+}
+
+fn properties(source: &Source, node: Node) -> Result<Properties, TiledError> {
+    let Some(node) = child(node, "properties") else {
+        return Ok(Properties::new());
+    };
+
+    let mut values = Properties::new();
+    for property in children(node, "property") {
+        let name = property.attribute("name").unwrap_or_default();
+        // Multi-line strings are stored as text
+        let text = property.attribute("value").or_else(|| property.text());
+        let text = text.unwrap_or_default();
+        let invalid = || source.invalid(format!("invalid value of property `{name}`"));
+
+        let value = match property.attribute("type").unwrap_or("string") {
+            "int" => PropertyValue::Int(text.parse().map_err(|_| invalid())?),
+            "float" => PropertyValue::Float(text.parse().map_err(|_| invalid())?),
+            "bool" => PropertyValue::Bool(text == "true"),
+            "color" => PropertyValue::Color(text.to_owned()),
+            "file" => PropertyValue::File(text.to_owned()),
+            "object" => PropertyValue::Object(text.parse().map_err(|_| invalid())?),
+            "class" => PropertyValue::Class(properties(source, property)?),
+            _ => PropertyValue::String(text.to_owned()),
+        };
+        values.insert(name.to_owned(), value);
+    }
+    Ok(values)
+}
+
+fn image(source: &Source, node: Node) -> Result<Option<ImageRef>, TiledError> {
+    let Some(image) = child(node, "image") else {
+        return Ok(None);
+    };
+    let Some(path) = image.attribute("source") else {
+        return Err(source.invalid("embedded images aren't supported"));
+    };
+
+    Ok(Some(ImageRef {
+        source: source.resolve(path)?,
+        width: attr(source, image, "width")?,
+        height: attr(source, image, "height")?,
+    }))
+}
+
+/// Returns the class of an element, which Tiled before 1.9 called type.
+fn class(node: Node) -> String {
+    let class = node.attribute("class").or_else(|| node.attribute("type"));
+    class.unwrap_or_default().to_owned()
+}
+
+fn attr<T: FromStr>(source: &Source, node: Node, name: &str) -> Result<Option<T>, TiledError> {
+    let Some(value) = node.attribute(name) else {
+        return Ok(None);
+    };
+    match value.trim().parse() {
+        Ok(value) => Ok(Some(value)),
+        Err(_) => Err(source.invalid(format!(
+            "invalid `{name}` value `{value}` of <{}>",
+            node.tag_name().name()
+        ))),
+    }
+}
+
+fn child<'a, 'i>(node: Node<'a, 'i>, name: &str) -> Option<Node<'a, 'i>> {
+    children(node, name).next()
+}
+
+fn children<'a, 'i>(node: Node<'a, 'i>, name: &str) -> impl Iterator<Item = Node<'a, 'i>> {
+    node.children()
+        .filter(move |child| child.has_tag_name(name))
+}
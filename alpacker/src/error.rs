@@ -0,0 +1,339 @@
+/* This module defines the crate-wide error type. Every error produced while loading packs and assets
+converts into `Error`, which records what failed (pack name, entry path, backend and codec)
+and keeps the original error as its source, so it works with `Box<dyn Error>` and `anyhow`. */
+
+use std::{
+    error::Error as StdError,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "checksum")]
+use crate::checksum::ChecksumError;
+use crate::{JsonIoError, Pack, limits::LimitError, path::PathError, version::UnsupportedVersion};
+
+/// A type alias for results using [Error].
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The category of an [Error].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// An I/O error not covered by another kind.
+    Io,
+
+    /// The requested entry does not exist in the pack.
+    NotFound,
+
+    /// The requested pack is not listed in the manifest.
+    NoSuchPack,
+
+    /// Pack dependencies are missing or cyclic.
+    Dependency,
+
+    /// The data is malformed and can't be decoded.
+    InvalidData,
+
+    /// A manifest or pack is newer than supported.
+    Version,
+
+    /// A [LoadLimits](crate::limits::LoadLimits) limit was exceeded.
+    Limit,
+
+    /// A path is absolute or points outside of the pack.
+    Path,
+
+    /// A signature or hash check failed.
+    Signature,
+
+    /// An entry doesn't match its checksum.
+    Corrupt,
+
+    /// An asset type failed to decode its data.
+    Asset,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Io => "I/O error",
+            Self::NotFound => "Entry not found",
+            Self::NoSuchPack => "Pack not found",
+            Self::Dependency => "Invalid pack dependencies",
+            Self::InvalidData => "Invalid data",
+            Self::Version => "Unsupported format version",
+            Self::Limit => "Load limit exceeded",
+            Self::Path => "Invalid path",
+            Self::Signature => "Signature check failed",
+            Self::Corrupt => "Corrupt entry",
+            Self::Asset => "Failed to load asset",
+        })
+    }
+}
+
+/// Describes where an [Error] happened. Every field is optional.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// The name of the pack in the manifest.
+    pub pack: Option<String>,
+
+    /// The path of the entry inside the pack.
+    pub path: Option<PathBuf>,
+
+    /// The pack backend, e.g. `tar`.
+    pub backend: Option<&'static str>,
+
+    /// The codec the pack is encoded with, e.g. `zstd`.
+    pub codec: Option<&'static str>,
+}
+
+impl ErrorContext {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(pack) = &self.pack {
+            parts.push(format!("pack \"{pack}\""));
+        }
+        if let Some(path) = &self.path {
+            parts.push(format!("entry {path:?}"));
+        }
+        if let Some(backend) = self.backend {
+            parts.push(format!("backend {backend}"));
+        }
+        if let Some(codec) = self.codec {
+            parts.push(format!("codec {codec}"));
+        }
+        f.write_str(&parts.join(", "))
+    }
+}
+
+/// The unified error type of alpacker.
+///
+/// All other error types of this crate convert into it, so `?` works across pack loading
+/// and asset decoding. The original error is kept as the [source](StdError::source),
+/// and can be inspected with [Error::downcast_ref].
+#[derive(Debug)]
+pub struct Error {
+    kind: ErrorKind,
+    context: ErrorContext,
+    source: Box<dyn StdError + Send + Sync>,
+}
+
+impl Error {
+    /// Creates an error without context.
+    ///
+    /// # Arguments
+    /// * `kind` - The category of the error.
+    /// * `source` - The underlying error.
+    pub fn new(kind: ErrorKind, source: impl Into<Box<dyn StdError + Send + Sync>>) -> Self {
+        Self {
+            kind,
+            context: ErrorContext::default(),
+            source: source.into(),
+        }
+    }
+
+    pub const fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    pub const fn context(&self) -> &ErrorContext {
+        &self.context
+    }
+
+    /// Returns the name of the pack the error happened in, if known.
+    pub fn pack(&self) -> Option<&str> {
+        self.context.pack.as_deref()
+    }
+
+    /// Returns the path of the entry the error happened in, if known.
+    pub fn path(&self) -> Option<&Path> {
+        self.context.path.as_deref()
+    }
+
+    /// Creates an [ErrorKind::Asset] error for an asset that failed to load.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the asset, if the error knows it.
+    /// * `source` - The underlying error.
+    pub(crate) fn asset(
+        path: Option<PathBuf>,
+        source: impl Into<Box<dyn StdError + Send + Sync>>,
+    ) -> Self {
+        let err = Self::new(ErrorKind::Asset, source);
+        match path {
+            Some(path) => err.with_path(path),
+            None => err,
+        }
+    }
+
+    /// Sets the pack name, unless it's already known.
+    pub fn with_pack(mut self, pack: impl Into<String>) -> Self {
+        self.context.pack.get_or_insert_with(|| pack.into());
+        self
+    }
+
+    /// Sets the entry path, unless it's already known.
+    pub fn with_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.context.path.get_or_insert_with(|| path.into());
+        self
+    }
+
+    /// Sets the pack backend, unless it's already known.
+    pub fn with_backend(mut self, backend: &'static str) -> Self {
+        self.context.backend.get_or_insert(backend);
+        self
+    }
+
+    /// Sets the pack codec, unless it's already known.
+    pub fn with_codec(mut self, codec: &'static str) -> Self {
+        self.context.codec.get_or_insert(codec);
+        self
+    }
+
+    /// Sets the backend and codec from a pack type, unless they're already known.
+    pub fn with_pack_type<P: Pack>(self) -> Self {
+        let err = self.with_backend(P::BACKEND);
+        match P::CODEC {
+            Some(codec) => err.with_codec(codec),
+            None => err,
+        }
+    }
+
+    /// Returns the underlying error if it has type `E`.
+    ///
+    /// Errors wrapped in an [io::Error] are unwrapped as well,
+    /// so e.g. a [LimitError] returned by [Pack::load_with] can be found.
+    pub fn downcast_ref<E: StdError + 'static>(&self) -> Option<&E> {
+        let source: &(dyn StdError + 'static) = &*self.source;
+        source.downcast_ref::<E>().or_else(|| {
+            source
+                .downcast_ref::<io::Error>()
+                .and_then(io::Error::get_ref)
+                .and_then(|inner| inner.downcast_ref::<E>())
+        })
+    }
+
+    /// Consumes the error, returning the underlying error.
+    pub fn into_source(self) -> Box<dyn StdError + Send + Sync> {
+        self.source
+    }
+}
+
+/// Shows the kind and context only. The underlying error is the [source](StdError::source),
+/// so error reporters like `anyhow` don't print it twice.
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.context.is_empty() {
+            true => write!(f, "{}", self.kind),
+            false => write!(f, "{} ({})", self.kind, self.context),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(&*self.source)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        let kind = match err.kind {
+            ErrorKind::NotFound | ErrorKind::NoSuchPack => io::ErrorKind::NotFound,
+            ErrorKind::Path => io::ErrorKind::InvalidInput,
+            ErrorKind::Io => match err.source.downcast_ref::<io::Error>() {
+                Some(source) => source.kind(),
+                None => io::ErrorKind::Other,
+            },
+            _ => io::ErrorKind::InvalidData,
+        };
+        io::Error::new(kind, err)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        // Errors that were converted to `io::Error` earlier keep their context.
+        let err = match err.downcast::<Error>() {
+            Ok(err) => return err,
+            Err(err) => err,
+        };
+
+        let inner = err.get_ref();
+        let kind = if inner.is_some_and(|inner| inner.is::<LimitError>()) {
+            ErrorKind::Limit
+        } else if inner.is_some_and(|inner| inner.is::<PathError>()) {
+            ErrorKind::Path
+        } else if inner.is_some_and(|inner| inner.is::<UnsupportedVersion>()) {
+            ErrorKind::Version
+        } else if is_corrupt(inner) {
+            ErrorKind::Corrupt
+        } else {
+            match err.kind() {
+                io::ErrorKind::NotFound => ErrorKind::NotFound,
+                io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => ErrorKind::InvalidData,
+                _ => ErrorKind::Io,
+            }
+        };
+
+        Self::new(kind, err)
+    }
+}
+
+impl From<JsonIoError> for Error {
+    fn from(err: JsonIoError) -> Self {
+        match err {
+            JsonIoError::Io(err) => err.into(),
+            JsonIoError::Json(err) => Self::new(ErrorKind::InvalidData, err),
+            JsonIoError::Version(err) => err.into(),
+            #[cfg(feature = "sign")]
+            JsonIoError::Signature(err) => err.into(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::new(ErrorKind::InvalidData, err)
+    }
+}
+
+impl From<UnsupportedVersion> for Error {
+    fn from(err: UnsupportedVersion) -> Self {
+        Self::new(ErrorKind::Version, err)
+    }
+}
+
+#[cfg(feature = "checksum")]
+fn is_corrupt(inner: Option<&(dyn StdError + Send + Sync + 'static)>) -> bool {
+    inner.is_some_and(|inner| inner.is::<ChecksumError>())
+}
+
+#[cfg(not(feature = "checksum"))]
+fn is_corrupt(_: Option<&(dyn StdError + Send + Sync + 'static)>) -> bool {
+    false
+}
+
+impl From<LimitError> for Error {
+    fn from(err: LimitError) -> Self {
+        Self::new(ErrorKind::Limit, err)
+    }
+}
+
+impl From<PathError> for Error {
+    fn from(err: PathError) -> Self {
+        let path = match &err {
+            PathError::Traversal(path)
+            | PathError::Absolute(path)
+            | PathError::NonUtf8(path)
+            | PathError::Empty(path) => path.clone(),
+        };
+        Self::new(ErrorKind::Path, err).with_path(path)
+    }
+}
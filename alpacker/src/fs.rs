@@ -1,30 +1,96 @@
+#[cfg(feature = "sign")]
+use std::collections::BTreeMap;
 use std::{
     collections::HashMap,
     fs::{self, File},
     io,
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
 use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
 use thiserror::Error;
 
-use crate::{JsonIoError, Pack};
+use crate::{
+    JsonIoError, LoadOptions, Pack,
+    cache::PackCache,
+    error::{Error, ErrorKind},
+    limits::LoadLimits,
+    pack::codec::{Decode, EncodedPack},
+    progress::{Progress, ProgressRead},
+    version::{Migration, Versioned},
+};
+#[cfg(feature = "sign")]
+use crate::{
+    limits::LimitError,
+    sign::{self, Hashed, SIGNATURE_FILE, SignatureError, VerifyingKey},
+};
 
 /// The name of the manifest file that stores metadata about available asset packs.
 pub const MANIFEST_FILE: &str = "manifest.json";
 
+/// Error type for resolving and loading packs listed in [Assets].
+///
+/// [Assets] methods return it converted into [Error], which adds the pack name.
 #[derive(Debug, Error)]
-pub enum PackLoadError<'a> {
+pub enum PackLoadError {
     #[error("I/O error: {0}")]
     Io(#[from] io::Error),
 
     #[error("No pack \"{0}\" found")]
-    NoSuchPack(&'a str),
+    NoSuchPack(String),
+
+    #[error("Pack \"{pack}\" depends on \"{dependency}\", which doesn't exist")]
+    MissingDependency { pack: String, dependency: String },
+
+    #[error("Cyclic pack dependency: {}", .0.join(" -> "))]
+    CyclicDependency(Vec<String>),
+
+    #[cfg(feature = "sign")]
+    #[error("Signature error: {0}")]
+    Signature(#[from] SignatureError),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[repr(transparent)]
-pub struct PackMeta(pub PathBuf);
+impl From<PackLoadError> for Error {
+    fn from(err: PackLoadError) -> Self {
+        match err {
+            PackLoadError::Io(err) => err.into(),
+            PackLoadError::NoSuchPack(ref pack) => {
+                let pack = pack.clone();
+                Self::new(ErrorKind::NoSuchPack, err).with_pack(pack)
+            }
+            PackLoadError::MissingDependency { ref pack, .. } => {
+                let pack = pack.clone();
+                Self::new(ErrorKind::Dependency, err).with_pack(pack)
+            }
+            PackLoadError::CyclicDependency(_) => Self::new(ErrorKind::Dependency, err),
+            #[cfg(feature = "sign")]
+            PackLoadError::Signature(err) => err.into(),
+        }
+    }
+}
+
+/// Metadata of a single pack in the manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackMeta {
+    /// Path to the pack file, relative to the packs directory.
+    pub path: PathBuf,
+
+    /// Names of the packs that must be loaded before this one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends: Vec<String>,
+}
+
+impl PackMeta {
+    /// Creates pack metadata without dependencies.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            depends: Vec::new(),
+        }
+    }
+}
 
 /// Represents a collection of asset packs.
 ///
@@ -32,8 +98,42 @@ pub struct PackMeta(pub PathBuf);
 /// and a mapping of pack names to their metadata.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Assets {
-    packs_dir: PathBuf,
-    packs: HashMap<String, PackMeta>,
+    pub(crate) format_version: u32,
+    pub(crate) packs_dir: PathBuf,
+    pub(crate) packs: HashMap<String, PackMeta>,
+
+    /// Hex-encoded SHA-256 hashes of the pack files, covered by the manifest signature.
+    #[cfg(feature = "sign")]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) hashes: BTreeMap<String, String>,
+
+    /// The key packs are verified against, set only by [Assets::load_from_dir_signed].
+    #[cfg(feature = "sign")]
+    #[serde(skip)]
+    pub(crate) verifying_key: Option<VerifyingKey>,
+
+    /// Packs shared through [Assets::shared_pack].
+    #[serde(skip)]
+    pub(crate) cache: PackCache,
+}
+
+impl Versioned for Assets {
+    const KIND: &'static str = "Assets manifest";
+    const FORMAT_VERSION: u32 = 2;
+    const MIGRATIONS: &'static [Migration] = &[
+        // 0 -> 1: introduced `format_version`
+        |_| {},
+        // 1 -> 2: pack paths became `PackMeta` objects with dependencies
+        |object| {
+            if let Some(Value::Object(packs)) = object.get_mut("packs") {
+                for meta in packs.values_mut() {
+                    if let Value::String(path) = meta {
+                        *meta = json!({ "path": path });
+                    }
+                }
+            }
+        },
+    ];
 }
 
 impl Assets {
@@ -44,23 +144,90 @@ impl Assets {
     /// * `packs` - A mapping of pack names to their metadata.
     pub fn new(packs_dir: impl Into<PathBuf>, packs: HashMap<String, PackMeta>) -> Self {
         let packs_dir = packs_dir.into();
-        Self { packs_dir, packs }
+        Self {
+            format_version: Self::FORMAT_VERSION,
+            packs_dir,
+            packs,
+            #[cfg(feature = "sign")]
+            hashes: BTreeMap::new(),
+            #[cfg(feature = "sign")]
+            verifying_key: None,
+            cache: PackCache::default(),
+        }
     }
 
     /// Loads asset metadata from a directory containing a manifest file.
     ///
+    /// With the `sign` feature, manifests must be verified, so this always fails with
+    /// `SignatureError::KeyRequired`. Use `Assets::load_from_dir_signed` instead.
+    ///
     /// # Arguments
     /// * `path` - The directory containing the `manifest.json` file.
     ///
     /// # Returns
-    /// [Ok]
     /// * `Ok(Assets)` if the manifest is successfully loaded.
-    /// * `Err(JsonIoError)` if an I/O or deserialization error occurs.
+    /// * `Err(JsonIoError)` if an I/O or deserialization error occurs,
+    ///   or the manifest is newer than supported.
+    #[cfg(not(feature = "sign"))]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = ?path.as_ref()), err)
+    )]
     pub fn load_from_dir(path: impl AsRef<Path>) -> Result<Self, JsonIoError> {
         let path = path.as_ref().canonicalize()?;
+
         let buf = fs::read_to_string(path.join(MANIFEST_FILE))?;
+        let mut assets = Self::from_json(serde_json::from_str(&buf)?)?;
+        assets.packs_dir = path.join(assets.packs_dir);
+
+        Ok(assets)
+    }
+
+    /// Loads asset metadata from a directory containing a manifest file.
+    ///
+    /// With the `sign` feature, manifests must be verified, so this always fails with
+    /// [SignatureError::KeyRequired]. Use [Assets::load_from_dir_signed] instead.
+    #[cfg(feature = "sign")]
+    pub fn load_from_dir(path: impl AsRef<Path>) -> Result<Self, JsonIoError> {
+        let _ = path;
+        Err(SignatureError::KeyRequired.into())
+    }
 
-        let mut assets = serde_json::from_str::<Self>(&buf)?;
+    /// Loads asset metadata and verifies its signature against a public key.
+    ///
+    /// The signature covers the exact bytes of the manifest file, and is checked before
+    /// the manifest is parsed. Every pack loaded from the returned [Assets] is checked
+    /// against its signed hash, so [Assets::load_pack] fails on unsigned or modified packs.
+    ///
+    /// # Arguments
+    /// * `path` - The directory containing the `manifest.json` and `manifest.json.sig` files.
+    /// * `key` - The public key the manifest must be signed with.
+    ///
+    /// # Returns
+    /// * `Ok(Assets)` if the manifest is successfully loaded and its signature is valid.
+    /// * `Err(SignatureError)` if the manifest is unsigned, invalid or can't be read.
+    #[cfg(feature = "sign")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(path = ?path.as_ref()), err)
+    )]
+    pub fn load_from_dir_signed(
+        path: impl AsRef<Path>,
+        key: VerifyingKey,
+    ) -> Result<Self, SignatureError> {
+        let path = path.as_ref().canonicalize()?;
+
+        let manifest = fs::read(path.join(MANIFEST_FILE))?;
+        let signature = match fs::read_to_string(path.join(SIGNATURE_FILE)) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(SignatureError::Unsigned);
+            }
+            result => result?,
+        };
+        sign::verify_manifest(&manifest, &signature, &key)?;
+
+        let mut assets = Self::from_json(serde_json::from_slice(&manifest)?)?;
+        assets.verifying_key = Some(key);
         assets.packs_dir = path.join(assets.packs_dir);
 
         Ok(assets)
@@ -73,15 +240,199 @@ impl Assets {
     ///
     /// # Returns
     /// * `Ok(P)` if the pack is successfully loaded.
-    /// * `Err(PackLoadError)` if the pack is missing or fails to load.
-    pub fn load_pack<'a, P: Pack>(&self, name: &'a str) -> Result<P, PackLoadError<'a>> {
+    /// * `Err(Error)` if the pack is missing or fails to load.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip(self), fields(pack_type = std::any::type_name::<P>()), err)
+    )]
+    pub fn load_pack<P: Pack>(&self, name: &str) -> Result<P, Error> {
+        self.load_pack_using(name, None, &LoadLimits::default(), |read| P::load(read))
+            .map_err(Error::with_pack_type::<P>)
+    }
+
+    /// Loads an asset pack by name with the given options, such as [LoadLimits](crate::limits::LoadLimits).
+    ///
+    /// # Arguments
+    /// * `name` - The name of the asset pack to load.
+    /// * `options` - Options passed to [Pack::load_with].
+    ///
+    /// # Returns
+    /// * `Ok(P)` if the pack is successfully loaded.
+    /// * `Err(Error)` if the pack is missing or fails to load.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip(self, options), fields(pack_type = std::any::type_name::<P>()), err)
+    )]
+    pub fn load_pack_with<P: Pack>(&self, name: &str, options: &LoadOptions) -> Result<P, Error> {
+        self.load_pack_using(name, options.progress.as_ref(), &options.limits, |read| {
+            P::load_with(read, options)
+        })
+        .map_err(Error::with_pack_type::<P>)
+    }
+
+    /// Loads an encoded asset pack by name, using the given codec instance.
+    ///
+    /// This is needed for codecs that carry state, such as encryption keys.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the asset pack to load.
+    /// * `codec` - The codec used to decode the pack.
+    /// * `options` - Options passed to [Pack::load_with].
+    ///
+    /// # Returns
+    /// * `Ok(EncodedPack<P, C>)` if the pack is successfully loaded.
+    /// * `Err(Error)` if the pack is missing or fails to load.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip(self, codec, options),
+            fields(pack_type = std::any::type_name::<P>(), codec = C::NAME),
+            err
+        )
+    )]
+    pub fn load_encoded_pack<P: Pack, C: Decode>(
+        &self,
+        name: &str,
+        codec: &C,
+        options: &LoadOptions,
+    ) -> Result<EncodedPack<P, C>, Error> {
+        self.load_pack_using(name, options.progress.as_ref(), &options.limits, |read| {
+            EncodedPack::load_with_codec(read, codec, options)
+        })
+        .map_err(|err| err.with_pack_type::<P>().with_codec(C::NAME))
+    }
+
+    /// Resolves the dependency closure of a pack.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the asset pack.
+    ///
+    /// # Returns
+    /// * `Ok(Vec<&str>)` with pack names in load order: dependencies first, `name` last.
+    /// * `Err(Error)` if a pack is missing or the dependencies form a cycle.
+    pub fn dependency_order(&self, name: &str) -> Result<Vec<&str>, Error> {
+        let Some((name, _)) = self.packs.get_key_value(name) else {
+            return Err(PackLoadError::NoSuchPack(name.to_string()).into());
+        };
+
+        let mut order = Vec::new();
+        let mut stack = Vec::new();
+        self.visit_dependencies(name, &mut stack, &mut order)?;
+        Ok(order)
+    }
+
+    /// Depth-first traversal for [Assets::dependency_order]. `stack` holds the current path.
+    fn visit_dependencies<'s>(
+        &'s self,
+        name: &'s str,
+        stack: &mut Vec<&'s str>,
+        order: &mut Vec<&'s str>,
+    ) -> Result<(), PackLoadError> {
+        if order.contains(&name) {
+            return Ok(());
+        }
+
+        if let Some(start) = stack.iter().position(|pack| *pack == name) {
+            let mut cycle = stack[start..]
+                .iter()
+                .map(|pack| pack.to_string())
+                .collect::<Vec<_>>();
+            cycle.push(name.to_string());
+            return Err(PackLoadError::CyclicDependency(cycle));
+        }
+
+        stack.push(name);
+        for dependency in &self.packs[name].depends {
+            if !self.packs.contains_key(dependency) {
+                return Err(PackLoadError::MissingDependency {
+                    pack: name.to_string(),
+                    dependency: dependency.clone(),
+                });
+            }
+            self.visit_dependencies(dependency, stack, order)?;
+        }
+        stack.pop();
+
+        order.push(name);
+        Ok(())
+    }
+
+    /// Loads a pack together with all of its dependencies.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the asset pack to load.
+    ///
+    /// # Returns
+    /// * `Ok(Vec<P>)` with the loaded packs in [Assets::dependency_order].
+    /// * `Err(Error)` if any pack is missing, fails to load, or the dependencies form a cycle.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip(self), fields(pack_type = std::any::type_name::<P>()), err)
+    )]
+    pub fn load_group<P: Pack>(&self, name: &str) -> Result<Vec<P>, Error> {
+        self.dependency_order(name)?
+            .into_iter()
+            .map(|name| self.load_pack(name))
+            .collect()
+    }
+
+    /// Opens the file of the named pack and loads it with `load`, attaching the name to errors.
+    fn load_pack_using<P>(
+        &self,
+        name: &str,
+        progress: Option<&Arc<dyn Progress>>,
+        limits: &LoadLimits,
+        load: impl FnOnce(&mut dyn io::Read) -> io::Result<P>,
+    ) -> Result<P, Error> {
         let Some(meta) = self.packs.get(name) else {
-            return Err(PackLoadError::NoSuchPack(name));
+            return Err(PackLoadError::NoSuchPack(name.to_string()).into());
         };
 
-        let path = self.packs_dir.join(&meta.0).canonicalize()?;
+        self.open_pack(name, meta, progress, limits, load)
+            .map_err(|err| Error::from(err).with_pack(name))
+    }
+
+    /// Loads a pack which is known to exist, verifying its hash if needed.
+    #[cfg_attr(not(feature = "sign"), allow(unused_variables))]
+    fn open_pack<P>(
+        &self,
+        name: &str,
+        meta: &PackMeta,
+        progress: Option<&Arc<dyn Progress>>,
+        limits: &LoadLimits,
+        load: impl FnOnce(&mut dyn io::Read) -> io::Result<P>,
+    ) -> Result<P, PackLoadError> {
+        let path = self.packs_dir.join(&meta.path).canonicalize()?;
         let file = File::open(path)?;
+        let size = file.metadata()?.len();
+        if let Some(progress) = progress {
+            progress.set_total_bytes(size);
+        }
+
+        #[cfg(feature = "sign")]
+        if let Some(expected) = self.expected_hash(name)? {
+            // The pack is buffered and authenticated before it's decompressed or parsed,
+            // so untrusted data never reaches `load`. The buffer is bounded by the total size limit.
+            // Progress is reported while the buffer is parsed, as hashing only reads the file.
+            let limit = limits.max_total_size;
+            if size > limit {
+                return Err(io::Error::from(LimitError::TotalSize { limit }).into());
+            }
+
+            let mut read = Hashed::new(io::Read::take(file, limit.saturating_add(1)));
+            let mut data = Vec::with_capacity(size as usize);
+            io::Read::read_to_end(&mut read, &mut data)?;
+            if data.len() as u64 > limit {
+                return Err(io::Error::from(LimitError::TotalSize { limit }).into());
+            }
+            if read.finish() != expected {
+                return Err(SignatureError::HashMismatch(name.to_string()).into());
+            }
+            let mut data = ProgressRead::encoded(data.as_slice(), progress.cloned());
+            return load(&mut data).map_err(PackLoadError::Io);
+        }
 
-        P::load(file).map_err(PackLoadError::Io)
+        let mut file = ProgressRead::encoded(file, progress.cloned());
+        load(&mut file).map_err(PackLoadError::Io)
     }
 }
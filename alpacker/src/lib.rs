@@ -1,14 +1,37 @@
+#[cfg(feature = "checksum")]
+pub mod checksum;
 pub mod data;
+pub mod error;
+pub mod limits;
 pub mod pack;
+pub mod path;
+pub mod progress;
+pub mod stats;
+pub mod version;
 
 #[cfg(feature = "fs")]
 mod fs;
 #[cfg(feature = "fs")]
 pub use fs::*;
+
+#[cfg(feature = "fs")]
+pub mod cache;
+
+#[cfg(feature = "sign")]
+pub mod sign;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+pub use error::Error;
 use serde::{Deserialize, Serialize};
 
-use std::{hash, io, path::Path};
+use limits::LoadLimits;
+use progress::Progress;
+use stats::PackStats;
+use std::{collections::BTreeMap, hash, io, path::Path, sync::Arc};
 use thiserror::Error;
+use version::{Migration, UnsupportedVersion, Versioned};
 
 // Defines the default hasher to use for hash maps.
 // Uses `twox-hash` if the feature is enabled, otherwise falls back to `RandomState`.
@@ -29,12 +52,114 @@ pub enum JsonIoError {
 
     #[error("Deserializer error: {0}")]
     Json(#[from] serde_json::Error),
+
+    #[error("Version error: {0}")]
+    Version(#[from] UnsupportedVersion),
+
+    #[cfg(feature = "sign")]
+    #[error("Signature error: {0}")]
+    Signature(#[from] sign::SignatureError),
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+impl From<JsonIoError> for io::Error {
+    fn from(err: JsonIoError) -> Self {
+        match err {
+            JsonIoError::Io(err) => err,
+            JsonIoError::Json(err) => err.into(),
+            JsonIoError::Version(err) => io::Error::new(io::ErrorKind::InvalidData, err),
+            #[cfg(feature = "sign")]
+            JsonIoError::Signature(err) => io::Error::new(io::ErrorKind::InvalidData, err),
+        }
+    }
+}
+
+/// Metadata stored inside each pack, written by the packer.
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PackManifest {
+    pub format_version: u32,
     pub entry_count: usize,
     pub file_count: usize,
+
+    /// Checksums of the files, keyed by their normalized paths.
+    /// Computed with `checksum::checksum` if the "checksum" feature is enabled.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub checksums: BTreeMap<String, u64>,
+}
+
+impl Default for PackManifest {
+    fn default() -> Self {
+        Self {
+            format_version: Self::FORMAT_VERSION,
+            entry_count: 0,
+            file_count: 0,
+            checksums: BTreeMap::new(),
+        }
+    }
+}
+
+impl Versioned for PackManifest {
+    const KIND: &'static str = "Pack manifest";
+    const FORMAT_VERSION: u32 = 1;
+    const MIGRATIONS: &'static [Migration] = &[
+        // 0 -> 1: introduced `format_version`
+        |_| {},
+    ];
+}
+
+/// Options controlling how a [Pack] is loaded.
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    /// Limits protecting against hostile or corrupt packs.
+    pub limits: LoadLimits,
+
+    /// Whether lookups ignore case, for assets authored on case-insensitive filesystems.
+    pub case_insensitive: bool,
+
+    /// What to do with entries whose normalized paths collide.
+    pub duplicates: DuplicatePolicy,
+
+    /// Whether entries over [LoadLimits::max_entry_size] are skipped instead of failing the load.
+    pub skip_oversized: bool,
+
+    /// Whether links pointing inside the pack are loaded as aliases of their targets.
+    /// Otherwise, they are skipped. Disabled by default.
+    pub resolve_links: bool,
+
+    /// When entries are compared to the checksums in the [PackManifest].
+    pub checksums: ChecksumPolicy,
+
+    /// An observer notified about loading progress, e.g. [LoadProgress](progress::LoadProgress).
+    pub progress: Option<Arc<dyn Progress>>,
+}
+
+/// Decides when entries are verified against their checksums.
+///
+/// Verification requires the "checksum" feature, without it checksums are ignored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChecksumPolicy {
+    /// Never verify checksums.
+    Ignore,
+
+    /// Verify an entry the first time it's read, failing the read with a `ChecksumError`.
+    #[default]
+    OnRead,
+
+    /// Verify every entry while loading, skipping corrupt ones as [Skipped::Corrupt](pack::Skipped::Corrupt).
+    OnLoad,
+}
+
+/// Decides which entry is kept when a pack contains the same path more than once.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Keep the first entry and skip the later ones.
+    FirstWins,
+
+    /// Replace earlier entries with the later ones, like extracting the archive would.
+    #[default]
+    LastWins,
+
+    /// Fail loading with [Skipped::Duplicate](pack::Skipped::Duplicate).
+    Error,
 }
 
 /// Represents a raw file inside an asset pack.
@@ -44,6 +169,51 @@ pub struct Raw<'p, R: io::Read + io::Seek> {
     pub read: R,
 }
 
+/// The contents of a file, shared with the pack instead of borrowing it.
+///
+/// Unlike [Raw], shared bytes are `'static`, so they can back readers that outlive the pack borrow,
+/// e.g. audio decoders that are played on another thread.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedBytes(Arc<Vec<u8>>);
+
+impl SharedBytes {
+    /// Wraps the bytes in a reader.
+    pub fn cursor(self) -> io::Cursor<Self> {
+        io::Cursor::new(self)
+    }
+
+    /// Returns the bytes as a vector, copying them only if they're still shared.
+    pub fn into_vec(self) -> Vec<u8> {
+        Arc::unwrap_or_clone(self.0)
+    }
+}
+
+impl From<Vec<u8>> for SharedBytes {
+    fn from(data: Vec<u8>) -> Self {
+        Self(Arc::new(data))
+    }
+}
+
+impl From<Arc<Vec<u8>>> for SharedBytes {
+    fn from(data: Arc<Vec<u8>>) -> Self {
+        Self(data)
+    }
+}
+
+impl AsRef<[u8]> for SharedBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl std::ops::Deref for SharedBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
 /// Trait for types that can be loaded from asset packs
 pub trait Asset: Sized {
     type Error;
@@ -62,6 +232,12 @@ pub trait Asset: Sized {
 
 /// Trait defining package loading and asset access operations
 pub trait Pack: Sized {
+    /// The name of the pack format, e.g. `tar`, reported in [Error] context.
+    const BACKEND: &'static str = "custom";
+
+    /// The name of the codec the pack is encoded with, if any.
+    const CODEC: Option<&'static str> = None;
+
     /// Loads package data from a readable stream.
     ///
     /// # Arguments
@@ -72,6 +248,24 @@ pub trait Pack: Sized {
     /// * `Err(io::Error)` if an error occurs.
     fn load(read: impl io::Read) -> io::Result<Self>;
 
+    /// Loads package data from a readable stream with the given options.
+    ///
+    /// The default implementation ignores the options, including [LoadLimits], and calls [Pack::load].
+    /// Custom packs that read untrusted data must override it to enforce the limits themselves,
+    /// e.g. with [LoadLimits::check_entry] and [LoadLimits::check_entries].
+    ///
+    /// # Arguments
+    /// * `read` - The input stream containing the package data.
+    /// * `options` - Options such as [LoadLimits].
+    ///
+    /// # Returns
+    /// * `Ok(Self)` if the package is successfully loaded.
+    /// * `Err(io::Error)` if an error occurs, including a [limits::LimitError] if a limit is exceeded.
+    fn load_with(read: impl io::Read, options: &LoadOptions) -> io::Result<Self> {
+        let _ = options;
+        Self::load(read)
+    }
+
     /// Retrieves a [Raw] object representing a file inside the archive.
     ///
     /// # Arguments
@@ -82,6 +276,21 @@ pub trait Pack: Sized {
     /// * `Err(io::Error)` if the file is missing.
     fn get_raw<'p>(&mut self, path: &'p Path) -> io::Result<Raw<'p, impl io::Read + io::Seek>>;
 
+    /// Retrieves the contents of a file as [SharedBytes], which don't borrow the pack.
+    ///
+    /// Packs that keep their entries in memory share them without copying,
+    /// the default implementation reads the file into a new buffer.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the file to retrieve.
+    ///
+    /// # Returns
+    /// * `Ok(SharedBytes)` if the file is found.
+    /// * `Err(io::Error)` if the file is missing or can't be read.
+    fn get_shared(&mut self, path: &Path) -> io::Result<SharedBytes> {
+        self.get::<Vec<u8>>(path).map(SharedBytes::from)
+    }
+
     /// Retrieves and constructs a typed asset.
     ///
     /// This is a convenience method that calls `A::load(self, path)`.
@@ -92,9 +301,43 @@ pub trait Pack: Sized {
     /// # Returns
     /// * `Ok(A)` if the asset is successfully loaded.
     /// * `Err(A::Error)` if an error occurs.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(asset = std::any::type_name::<A>(), path = ?path.as_ref())
+        )
+    )]
     fn get<A: Asset>(&mut self, path: impl AsRef<Path>) -> Result<A, A::Error> {
         A::load(self, path)
     }
 
+    /// Retrieves and constructs a typed asset, like [Pack::get],
+    /// but converts the error into [Error] with the entry path and pack type attached.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the asset to retrieve.
+    ///
+    /// # Returns
+    /// * `Ok(A)` if the asset is successfully loaded.
+    /// * `Err(Error)` if an error occurs.
+    fn load_asset<A: Asset>(&mut self, path: impl AsRef<Path>) -> Result<A, Error>
+    where
+        A::Error: Into<Error>,
+    {
+        let path = path.as_ref();
+        self.get::<A>(path)
+            .map_err(|err| err.into().with_path(path).with_pack_type::<Self>())
+    }
+
     fn exists(&self, path: impl AsRef<Path>) -> bool;
+
+    /// Returns statistics such as the number of entries and the memory they hold.
+    ///
+    /// # Returns
+    /// * `Some(PackStats)` if the pack keeps track of them.
+    /// * `None` by default.
+    fn stats(&self) -> Option<PackStats> {
+        None
+    }
 }
@@ -0,0 +1,195 @@
+/* This module protects pack loading from hostile or corrupt input. Mods and downloaded content
+are untrusted, so pack implementations check sizes and counts against configurable limits
+before allocating memory, and codecs stop decoding when the data expands suspiciously. */
+
+use std::{
+    cell::Cell,
+    io::{self, Read},
+    path::PathBuf,
+    rc::Rc,
+};
+
+use thiserror::Error;
+
+/// Decoded bytes allowed before the decompression ratio is checked,
+/// since decoders read their input ahead in large blocks.
+const RATIO_GRACE: u64 = 1024 * 1024;
+
+/// Limits applied while loading a pack.
+///
+/// The defaults are generous for game assets, but keep a malicious pack
+/// from exhausting memory. Use [LoadLimits::UNLIMITED] for fully trusted input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadLimits {
+    /// Maximum size of a single entry, in bytes.
+    pub max_entry_size: u64,
+
+    /// Maximum size of all entries combined, in bytes.
+    pub max_total_size: u64,
+
+    /// Maximum number of entries in the pack, including directories and other skipped entries.
+    pub max_entries: usize,
+
+    /// Maximum ratio of decoded to encoded bytes for codecs.
+    pub max_ratio: u64,
+}
+
+impl LoadLimits {
+    /// Limits that never trigger.
+    pub const UNLIMITED: Self = Self {
+        max_entry_size: u64::MAX,
+        max_total_size: u64::MAX,
+        max_entries: usize::MAX,
+        max_ratio: u64::MAX,
+    };
+
+    /// Checks the size of an entry and adds it to the running total.
+    pub fn check_entry(
+        &self,
+        path: impl Into<PathBuf>,
+        size: u64,
+        total: &mut u64,
+    ) -> Result<(), LimitError> {
+        if size > self.max_entry_size {
+            return Err(LimitError::EntrySize {
+                path: path.into(),
+                size,
+                limit: self.max_entry_size,
+            });
+        }
+
+        *total = total.saturating_add(size);
+        match *total > self.max_total_size {
+            true => Err(LimitError::TotalSize {
+                limit: self.max_total_size,
+            }),
+            false => Ok(()),
+        }
+    }
+
+    /// Checks the number of entries read so far.
+    pub fn check_entries(&self, count: usize) -> Result<(), LimitError> {
+        match count > self.max_entries {
+            true => Err(LimitError::Entries {
+                limit: self.max_entries,
+            }),
+            false => Ok(()),
+        }
+    }
+}
+
+impl Default for LoadLimits {
+    fn default() -> Self {
+        Self {
+            max_entry_size: 1 << 30, // 1 GiB
+            max_total_size: 4 << 30, // 4 GiB
+            max_entries: 1 << 20,
+            max_ratio: 1000,
+        }
+    }
+}
+
+/// Error type for [LoadLimits] violations.
+///
+/// Pack loading returns it wrapped into an [io::Error] of kind [io::ErrorKind::InvalidData].
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LimitError {
+    #[error("Entry {path:?} is {size} bytes, which exceeds the limit of {limit} bytes")]
+    EntrySize {
+        path: PathBuf,
+        size: u64,
+        limit: u64,
+    },
+
+    #[error("Pack contents exceed the limit of {limit} bytes")]
+    TotalSize { limit: u64 },
+
+    #[error("Pack has more than {limit} entries")]
+    Entries { limit: usize },
+
+    #[error("Decompression ratio exceeds the limit of {limit}")]
+    Ratio { limit: u64 },
+}
+
+impl From<LimitError> for io::Error {
+    fn from(err: LimitError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// A reader that counts the bytes read from the encoded stream.
+pub(crate) struct Counted<R> {
+    read: R,
+    count: Rc<Cell<u64>>,
+}
+
+impl<R> Counted<R> {
+    /// Wraps a stream, returning the counter shared with the reader.
+    pub fn new(read: R) -> (Self, Rc<Cell<u64>>) {
+        let count = Rc::new(Cell::new(0));
+        let counted = Self {
+            read,
+            count: count.clone(),
+        };
+        (counted, count)
+    }
+}
+
+impl<R: Read> Read for Counted<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.read.read(buf)?;
+        self.count.set(self.count.get() + len as u64);
+        Ok(len)
+    }
+}
+
+/// A reader over a decoded stream that fails once it expands more than `max_ratio` times.
+pub(crate) struct RatioLimited<R> {
+    read: R,
+    encoded: Rc<Cell<u64>>,
+    decoded: u64,
+    max_ratio: u64,
+}
+
+impl<R: Read> RatioLimited<R> {
+    /// Wraps an encoded stream in a counter, and passes it to `decode`.
+    pub fn new<E: Read>(
+        encoded: E,
+        max_ratio: u64,
+        decode: impl FnOnce(Counted<E>) -> io::Result<R>,
+    ) -> io::Result<Self> {
+        let (encoded, count) = Counted::new(encoded);
+        let read = decode(encoded)?;
+
+        Ok(Self {
+            read,
+            encoded: count,
+            decoded: 0,
+            max_ratio,
+        })
+    }
+}
+
+impl<R> RatioLimited<R> {
+    /// Returns the counter of bytes read from the encoded stream.
+    pub fn encoded(&self) -> Rc<Cell<u64>> {
+        self.encoded.clone()
+    }
+}
+
+impl<R: Read> Read for RatioLimited<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.read.read(buf)?;
+        self.decoded += len as u64;
+
+        let allowed = self.encoded.get().saturating_mul(self.max_ratio);
+        if self.decoded > RATIO_GRACE && self.decoded > allowed {
+            return Err(LimitError::Ratio {
+                limit: self.max_ratio,
+            }
+            .into());
+        }
+
+        Ok(len)
+    }
+}
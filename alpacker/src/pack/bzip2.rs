@@ -7,13 +7,16 @@ use crate::Pack;
 /// A codec implementation for decoding Bzip2-compressed input streams.
 /// Used with [`EncodedPack`] to support `.bz2` asset packs.
 #[non_exhaustive]
+#[derive(Debug, Default, Clone, Copy)]
 pub struct Bzip2Codec;
 
 #[allow(type_alias_bounds)]
 pub type Bzip2<P: Pack> = EncodedPack<P, Bzip2Codec>;
 
 impl Decode for Bzip2Codec {
-    fn decode(read: impl Read) -> io::Result<impl Read> {
+    const NAME: &'static str = "bzip2";
+
+    fn decode(&self, read: impl Read) -> io::Result<impl Read> {
         Ok(BzDecoder::new(read))
     }
 }
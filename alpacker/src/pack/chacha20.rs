@@ -0,0 +1,158 @@
+use chacha20poly1305::{
+    ChaCha20Poly1305, Key,
+    aead::{generic_array::GenericArray, stream::DecryptorBE32},
+};
+use std::io::{self, Read};
+
+use super::codec::{Decode, EncodedPack};
+use crate::Pack;
+
+/// Magic bytes at the start of every encrypted stream.
+pub const MAGIC: [u8; 4] = *b"ALPE";
+
+/// Size of the plaintext in each encrypted chunk, except the last one.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Size of the authentication tag appended to each chunk.
+pub const TAG_SIZE: usize = 16;
+
+/// Size of the random nonce prefix following [MAGIC].
+pub const NONCE_SIZE: usize = 7;
+
+/// Supplies the 256-bit key used to encrypt or decrypt packs.
+///
+/// Implemented for closures, so keys can be fetched from a keychain, a server, etc.
+pub trait KeyProvider {
+    fn key(&self) -> io::Result<[u8; 32]>;
+}
+
+impl<F: Fn() -> io::Result<[u8; 32]>> KeyProvider for F {
+    fn key(&self) -> io::Result<[u8; 32]> {
+        self()
+    }
+}
+
+/// A [KeyProvider] that always returns the same key.
+#[derive(Clone)]
+pub struct StaticKey(pub [u8; 32]);
+
+impl KeyProvider for StaticKey {
+    fn key(&self) -> io::Result<[u8; 32]> {
+        Ok(self.0)
+    }
+}
+
+/// A codec implementation for decoding ChaCha20-Poly1305 encrypted input streams.
+/// Used with [`EncodedPack`] to support `.enc` asset packs.
+///
+/// The stream is split into authenticated chunks of [CHUNK_SIZE] bytes, so it can be
+/// decrypted without buffering the whole pack, while truncation and reordering are still detected.
+#[derive(Debug, Default, Clone)]
+pub struct ChaCha20Codec<K> {
+    pub keys: K,
+}
+
+impl<K> ChaCha20Codec<K> {
+    pub const fn new(keys: K) -> Self {
+        Self { keys }
+    }
+}
+
+/// A pack encrypted with [ChaCha20Codec].
+///
+/// [Pack::load] creates the codec with [Default], so it only works with key providers
+/// that don't carry state, e.g. a unit struct returning an embedded key.
+/// Otherwise, load encrypted packs with `Assets::load_encoded_pack` or [EncodedPack::load_with_codec],
+/// which take the codec with its keys.
+#[allow(type_alias_bounds)]
+pub type Encrypted<P: Pack, K> = EncodedPack<P, ChaCha20Codec<K>>;
+
+impl<K: KeyProvider> Decode for ChaCha20Codec<K> {
+    const NAME: &'static str = "chacha20";
+
+    fn decode(&self, read: impl Read) -> io::Result<impl Read> {
+        DecryptReader::new(read, &self.keys.key()?)
+    }
+}
+
+fn auth_error(_: chacha20poly1305::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "Encrypted chunk failed authentication")
+}
+
+/// A reader that decrypts a chunked ChaCha20-Poly1305 stream.
+pub struct DecryptReader<R> {
+    read: R,
+    decryptor: Option<DecryptorBE32<ChaCha20Poly1305>>,
+    /// Decrypted contents of the current chunk.
+    buf: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read> DecryptReader<R> {
+    /// Reads the stream header and prepares decryption with `key`.
+    pub fn new(mut read: R, key: &[u8; 32]) -> io::Result<Self> {
+        let mut header = [0; MAGIC.len() + NONCE_SIZE];
+        read.read_exact(&mut header)?;
+
+        if header[..MAGIC.len()] != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not an encrypted stream",
+            ));
+        }
+
+        let nonce = GenericArray::from_slice(&header[MAGIC.len()..]);
+        let decryptor = DecryptorBE32::new(Key::from_slice(key), nonce);
+
+        Ok(Self {
+            read,
+            decryptor: Some(decryptor),
+            buf: Vec::with_capacity(CHUNK_SIZE + TAG_SIZE),
+            pos: 0,
+        })
+    }
+
+    /// Reads and decrypts the next chunk. A chunk shorter than the full size is the last one.
+    fn next_chunk(&mut self) -> io::Result<()> {
+        let Some(decryptor) = &mut self.decryptor else {
+            return Ok(());
+        };
+
+        self.buf.clear();
+        self.pos = 0;
+        (&mut self.read)
+            .take((CHUNK_SIZE + TAG_SIZE) as u64)
+            .read_to_end(&mut self.buf)?;
+
+        let result = if self.buf.len() == CHUNK_SIZE + TAG_SIZE {
+            decryptor.decrypt_next_in_place(&[], &mut self.buf)
+        } else {
+            // Truncated streams end up here too and fail authentication.
+            let decryptor = self.decryptor.take().unwrap();
+            decryptor.decrypt_last_in_place(&[], &mut self.buf)
+        };
+
+        // Never hand out unauthenticated data, even if the caller keeps reading.
+        if result.is_err() {
+            self.buf.clear();
+            self.decryptor = None;
+        }
+        result.map_err(auth_error)
+    }
+}
+
+impl<R: Read> Read for DecryptReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buf.len() {
+            if self.decryptor.is_none() {
+                return Ok(0);
+            }
+            self.next_chunk()?;
+        }
+
+        let len = buf.len().min(self.buf.len() - self.pos);
+        buf[..len].copy_from_slice(&self.buf[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
@@ -3,16 +3,26 @@ use std::{
     marker::PhantomData,
     ops::{Deref, DerefMut},
     path::Path,
+    time::{Duration, Instant},
 };
 
-use crate::{Pack, Raw};
+use super::{Diagnostics, Skipped};
+use crate::{
+    LoadOptions, Pack, Raw, SharedBytes, limits::RatioLimited, progress::ProgressRead,
+    stats::PackStats,
+};
 
 /// A trait for types that can decode compressed input streams (e.g. decompressors).
 ///
 /// Used by [`EncodedPack`] to implement runtime decompression before loading a [`Pack`].
+/// Codecs are instances, so they can carry state such as decryption keys.
+/// Stateless codecs implement [`Default`], which makes [`EncodedPack`] loadable through [`Pack::load`].
 pub trait Decode {
+    /// The name of the codec, e.g. `zstd`, reported in [Error](crate::Error) context.
+    const NAME: &'static str = "custom";
+
     /// Returns a decoder over the provided input stream.
-    fn decode(read: impl io::Read) -> io::Result<impl io::Read>;
+    fn decode(&self, read: impl io::Read) -> io::Result<impl io::Read>;
 }
 
 /// A generic wrapper around a [`Pack`] that transparently decodes its input stream
@@ -23,28 +33,87 @@ pub trait Decode {
 /// # Type Parameters
 /// - `P`: The base pack implementation (e.g. `TarPack`)
 /// - `C`: A codec that implements [`Decode`]
+#[derive(Debug)]
 pub struct EncodedPack<P, C> {
     pub pack: P,
+    /// Bytes read from the encoded input while loading.
+    compressed_size: u64,
+    /// Time spent loading, including decoding.
+    load_duration: Duration,
     _d: PhantomData<C>,
 }
 
-impl<P: Pack, C: Decode> Pack for EncodedPack<P, C> {
-    fn load(read: impl io::Read) -> io::Result<Self> {
+impl<P: Pack, C: Decode> EncodedPack<P, C> {
+    /// Loads the pack, decoding its input stream with the given codec instance.
+    ///
+    /// Unlike [`Pack::load_with`], this doesn't require the codec to implement [`Default`].
+    /// The decoded stream is limited by [`LoadLimits::max_ratio`](crate::limits::LoadLimits::max_ratio).
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "decode", skip_all, fields(codec = C::NAME))
+    )]
+    pub fn load_with_codec(
+        read: impl io::Read,
+        codec: &C,
+        options: &LoadOptions,
+    ) -> io::Result<Self> {
+        let start = Instant::now();
+        let max_ratio = options.limits.max_ratio;
+        let read = RatioLimited::new(read, max_ratio, |read| codec.decode(read))?;
+        let compressed_size = read.encoded();
+        let read = ProgressRead::decoded(read, options.progress.clone());
+
         Ok(Self {
-            pack: P::load(C::decode(read)?)?,
+            pack: P::load_with(read, options)?,
+            compressed_size: compressed_size.get(),
+            load_duration: start.elapsed(),
             _d: PhantomData,
         })
     }
+}
+
+impl<P: Pack, C: Decode + Default> Pack for EncodedPack<P, C> {
+    const BACKEND: &'static str = P::BACKEND;
+    const CODEC: Option<&'static str> = Some(C::NAME);
+
+    fn load(read: impl io::Read) -> io::Result<Self> {
+        Self::load_with(read, &LoadOptions::default())
+    }
+
+    fn load_with(read: impl io::Read, options: &LoadOptions) -> io::Result<Self> {
+        Self::load_with_codec(read, &C::default(), options)
+    }
 
     #[inline(always)]
     fn get_raw<'p>(&mut self, path: &'p Path) -> io::Result<Raw<'p, impl io::Read + io::Seek>> {
         self.pack.get_raw(path)
     }
 
+    #[inline(always)]
+    fn get_shared(&mut self, path: &Path) -> io::Result<SharedBytes> {
+        self.pack.get_shared(path)
+    }
+
     #[inline(always)]
     fn exists(&self, path: impl AsRef<Path>) -> bool {
         self.pack.exists(path)
     }
+
+    fn stats(&self) -> Option<PackStats> {
+        let stats = self.pack.stats()?;
+        Some(PackStats {
+            compressed_size: Some(self.compressed_size),
+            load_duration: Some(self.load_duration),
+            ..stats
+        })
+    }
+}
+
+impl<P: Diagnostics, C> Diagnostics for EncodedPack<P, C> {
+    #[inline(always)]
+    fn skipped(&self) -> &[Skipped] {
+        self.pack.skipped()
+    }
 }
 
 impl<P: Pack, C> Deref for EncodedPack<P, C> {
@@ -1,3 +1,9 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::path::PathError;
+
 pub mod codec;
 
 macro_rules! pack_type {
@@ -21,9 +27,80 @@ pack_type!("tar", tar, TarPack);
 pack_type!("zstd", zstd, Zstd);
 pack_type!("bzip2", bzip2, Bzip2);
 pack_type!("lz4", lz4, Lz4);
+pack_type!("chacha20", chacha20, Encrypted);
 
 pack_alchemy!(
     "tar", "zstd" => TarZstPack = Zstd<TarPack> ["Zstandard compressed TAR pack"];
     "tar", "bzip2" => TarBz2Pack = Bzip2<TarPack> ["Bzip2 compressed TAR pack"];
     "tar", "lz4" => TarLz4Pack = Lz4<TarPack> ["LZ4 compressed TAR pack"];
 );
+
+/// Enum representing the reasons why an entry was skipped while loading a pack.
+///
+/// [Skipped::Duplicate] is also returned as an error by [DuplicatePolicy::Error](crate::DuplicatePolicy::Error).
+#[derive(Debug, Error, PartialEq, Clone)]
+pub enum Skipped {
+    /// The path does not point to a valid file (e.g., it could be a directory).
+    #[error("Entry {0:?} is not a file")]
+    NotAFile(PathBuf),
+
+    /// The file is a manifest located at [MANIFEST_FILE](crate::MANIFEST_FILE).
+    #[error("Entry is the pack manifest")]
+    Manifest,
+
+    /// The path is absolute or points outside of the archive.
+    #[error("Entry {0:?} has an invalid path: {1}")]
+    InvalidPath(PathBuf, PathError),
+
+    /// The entry is a symbolic link.
+    #[error("Entry {0:?} is a symbolic link")]
+    Symlink(PathBuf),
+
+    /// The entry is a hard link.
+    #[error("Entry {0:?} is a hard link")]
+    HardLink(PathBuf),
+
+    /// Another entry with the same normalized path exists.
+    #[error("Entry {0:?} is duplicated")]
+    Duplicate(PathBuf),
+
+    /// The entry has a type the pack doesn't support, e.g. a device or a FIFO.
+    #[error("Entry {0:?} has an unsupported type")]
+    Unsupported(PathBuf),
+
+    /// The entry doesn't match its checksum, see [ChecksumPolicy::OnLoad](crate::ChecksumPolicy::OnLoad).
+    #[error("Entry {0:?} doesn't match its checksum")]
+    Corrupt(PathBuf),
+
+    /// The link target is missing, outside of the archive, or part of a link cycle.
+    #[error("Link {path:?} to {target:?} can't be resolved")]
+    BrokenLink { path: PathBuf, target: PathBuf },
+
+    /// The entry exceeds [LoadLimits::max_entry_size](crate::limits::LoadLimits::max_entry_size),
+    /// and [LoadOptions::skip_oversized](crate::LoadOptions::skip_oversized) is set.
+    #[error("Entry {path:?} is {size} bytes, which exceeds the size limit")]
+    Oversize { path: PathBuf, size: u64 },
+}
+
+impl Skipped {
+    /// Returns `true` for entries that are expected in a well-formed pack,
+    /// i.e. directories and the manifest.
+    pub const fn is_expected(&self) -> bool {
+        matches!(self, Self::NotAFile(_) | Self::Manifest)
+    }
+}
+
+/// Trait for packs that record entries skipped while loading.
+///
+/// Skipped entries are only recorded with the "collect-errors" feature.
+pub trait Diagnostics {
+    /// Returns the entries skipped while loading, in archive order.
+    fn skipped(&self) -> &[Skipped];
+
+    /// Returns `true` if nothing unexpected was skipped, see [Skipped::is_expected].
+    ///
+    /// Asset pipelines can use this to reject archives with links, duplicates or invalid paths.
+    fn is_clean(&self) -> bool {
+        self.skipped().iter().all(Skipped::is_expected)
+    }
+}
@@ -1,47 +1,150 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     hash::BuildHasher,
     io::{self, Read, Seek},
     path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
-use crate::{DefaultHasher, MANIFEST_FILE, Pack, PackManifest, Raw};
+use super::Diagnostics;
+pub use super::Skipped;
+#[cfg(feature = "checksum")]
+use crate::{
+    ChecksumPolicy,
+    checksum::{self, ChecksumError},
+};
+use crate::{
+    DefaultHasher, DuplicatePolicy, LoadOptions, MANIFEST_FILE, Pack, PackManifest, Raw, SharedBytes,
+    limits::Counted,
+    path::{PathError, VirtualPath},
+    stats::PackStats,
+    version::Versioned,
+};
+
+/// Maximum capacity allocated up front for an entry. Sizes come from untrusted headers,
+/// so larger entries grow their buffer as the data actually arrives.
+const MAX_ENTRY_PREALLOC: u64 = 64 * 1024;
+
+/// Maximum number of entries reserved up front from the counts in the untrusted manifest.
+const MAX_ENTRIES_PREALLOC: usize = 1 << 20;
 
 /// TAR archive implementation of the Pack trait
 #[derive(Debug)]
 pub struct TarPack<S = DefaultHasher> {
     /// Stores the contents of the files in the archive as a hash map,
-    /// where the key is the file path and the value is the file content.
-    contents: HashMap<PathBuf, Vec<u8>, S>,
+    /// where the key is the normalized file path and the value is the file content.
+    /// Resolved links share the content of their targets.
+    contents: HashMap<VirtualPath, Arc<Vec<u8>>, S>,
 
     /// Keeps track of files that were skipped during extraction.
     skipped: Vec<Skipped>,
+
+    /// Whether paths are stored and looked up in folded case.
+    case_insensitive: bool,
+
+    /// Checksums of the entries that haven't been verified yet.
+    #[cfg(feature = "checksum")]
+    checksums: HashMap<VirtualPath, u64, S>,
+
+    /// Targets of resolved links, since checksums are stored for the targets only.
+    #[cfg(feature = "checksum")]
+    link_targets: HashMap<VirtualPath, VirtualPath, S>,
+
+    /// Number of bytes read from the archive stream.
+    archive_size: u64,
+
+    /// Size of the entry buffers, computed once since the contents never change after loading.
+    bytes_held: u64,
+
+    /// Time spent reading the archive.
+    load_duration: Duration,
 }
 
-/// Enum representing the reasons why a file was skipped.
-#[derive(Debug, PartialEq, Clone)]
-pub enum Skipped {
-    /// The path does not point to a valid file (e.g., it could be a directory).
-    NotAFile(PathBuf),
+/// A link entry, resolved once the whole archive is read.
+#[cfg_attr(not(feature = "collect-errors"), allow(dead_code))]
+struct Link {
+    /// Position of the entry in the archive, to report broken links in archive order.
+    index: usize,
+    path: PathBuf,
+    target: PathBuf,
+    resolved: VirtualPath,
+}
 
-    /// The file is a manifest located at [MANIFEST_FILE].
-    Manifest,
+/// Follows a chain of links to the file it ends at, returning its key and contents.
+fn resolve_link<'c, S: BuildHasher>(
+    contents: &'c HashMap<VirtualPath, Arc<Vec<u8>>, S>,
+    links: &HashMap<VirtualPath, Link>,
+    link: &Link,
+) -> Option<(&'c VirtualPath, &'c Arc<Vec<u8>>)> {
+    let mut target = &link.resolved;
+    // A chain longer than the number of links must contain a cycle.
+    for _ in 0..links.len() {
+        if let Some(entry) = contents.get_key_value(target) {
+            return Some(entry);
+        }
+        target = &links.get(target)?.resolved;
+    }
+    None
 }
 
-impl TarPack {
-    /// Returns a reference to the list of skipped files.
-    pub const fn skipped(&self) -> &Vec<Skipped> {
+impl<S> Diagnostics for TarPack<S> {
+    fn skipped(&self) -> &[Skipped] {
         &self.skipped
     }
 }
 
+impl<S: BuildHasher> TarPack<S> {
+    /// Normalizes a path the same way entry paths were normalized on load.
+    fn key(&self, path: &Path) -> Result<VirtualPath, PathError> {
+        VirtualPath::with_case(path, self.case_insensitive)
+    }
+
+    /// Verifies every entry that hasn't been verified yet against its checksum.
+    ///
+    /// With [ChecksumPolicy::OnRead], entries are otherwise verified the first time they're read.
+    ///
+    /// # Returns
+    /// * `Ok(())` if all entries match their checksums.
+    /// * `Err(ChecksumError)` for the first corrupt entry.
+    #[cfg(feature = "checksum")]
+    pub fn verify(&mut self) -> Result<(), ChecksumError> {
+        let mut keys = self.checksums.keys().cloned().collect::<Vec<_>>();
+        // Sorted, so the same corrupt entry is reported every time.
+        keys.sort_unstable();
+        keys.iter()
+            .try_for_each(|key| self.verify_entry(key, key.as_ref()))
+    }
+
+    /// Verifies a single entry, if its checksum is known and it hasn't been verified yet.
+    /// Links are verified through the entry they point to.
+    #[cfg(feature = "checksum")]
+    fn verify_entry(&mut self, key: &VirtualPath, path: &Path) -> Result<(), ChecksumError> {
+        let key = self.link_targets.get(key).unwrap_or(key);
+        if let Some(&expected) = self.checksums.get(key)
+            && let Some(data) = self.contents.get(key)
+        {
+            checksum::verify(path, data, expected)?;
+            // Entries are immutable, so they're never hashed again.
+            self.checksums.remove(key);
+        }
+        Ok(())
+    }
+}
+
 impl<S: BuildHasher + Default> Pack for TarPack<S> {
+    const BACKEND: &'static str = "tar";
+
     fn get_raw<'p>(&mut self, path: &'p Path) -> io::Result<Raw<'p, impl Read + Seek>> {
-        match self.contents.get(path) {
+        let key = self.key(path)?;
+        #[cfg(feature = "checksum")]
+        self.verify_entry(&key, path)?;
+
+        match self.contents.get(&key) {
             Some(raw) => Ok(Raw {
                 path,
                 size_hint: Some(raw.len()), // Provide an estimated file size
-                read: io::Cursor::new(raw), // Wrap the file contents in an in-memory reader
+                read: io::Cursor::new(raw.as_slice()), // Wrap the file contents in an in-memory reader
             }),
             None => Err(io::Error::new(
                 io::ErrorKind::NotFound,
@@ -50,50 +153,271 @@ impl<S: BuildHasher + Default> Pack for TarPack<S> {
         }
     }
 
+    fn get_shared(&mut self, path: &Path) -> io::Result<SharedBytes> {
+        let key = self.key(path)?;
+        #[cfg(feature = "checksum")]
+        self.verify_entry(&key, path)?;
+
+        match self.contents.get(&key) {
+            // Share the entry instead of copying it
+            Some(raw) => Ok(SharedBytes::from(Arc::clone(raw))),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No such file: {path:?}"),
+            )),
+        }
+    }
+
     fn load(read: impl Read) -> io::Result<Self> {
+        Self::load_with(read, &LoadOptions::default())
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "load_tar", skip_all))]
+    fn load_with(read: impl Read, options: &LoadOptions) -> io::Result<Self> {
+        let start = Instant::now();
+        let limits = &options.limits;
+        let progress = options.progress.as_deref();
+        let (read, archive_size) = Counted::new(read);
         let mut tar = tar::Archive::new(read);
+        // Total size of the file contents read so far.
+        let mut total_size = 0;
 
         // Create a hash map for storing file contents with the specified hasher.
         let mut contents = HashMap::with_hasher(S::default());
+        // Links are resolved after reading the archive, since they may precede their targets.
+        let mut links = HashMap::new();
+        // Checksums from the pack manifest.
+        #[cfg(feature = "checksum")]
+        let mut checksums = HashMap::with_hasher(S::default());
         // List of skipped files (only used if the "collect-errors" feature is enabled).
         #[allow(unused_mut)]
         let mut skipped = Vec::new();
 
         // Iterate over each entry in the TAR archive.
-        for entry in tar.entries()? {
+        for (index, entry) in tar.entries()?.enumerate() {
             let entry = entry?;
+            limits.check_entries(index + 1)?;
 
             let header = entry.header();
             let path = header.path()?.to_path_buf();
+            let entry_type = header.entry_type();
+            if let Some(progress) = progress {
+                progress.entry(&path);
+            }
+            let is_link = entry_type.is_symlink() || entry_type.is_hard_link();
 
-            if path == Path::new(MANIFEST_FILE) {
-                let manifest: PackManifest = serde_json::from_reader(entry)?;
-                contents.reserve(manifest.file_count);
+            let supported = entry_type.is_file() || (is_link && options.resolve_links);
+            if !supported {
+                #[allow(unused_variables)]
+                let reason = match entry_type {
+                    _ if entry_type.is_dir() => Skipped::NotAFile(path),
+                    _ if entry_type.is_symlink() => Skipped::Symlink(path),
+                    _ if entry_type.is_hard_link() => Skipped::HardLink(path),
+                    _ => Skipped::Unsupported(path),
+                };
+                #[cfg(feature = "collect-errors")]
+                skipped.push(reason);
+                continue;
+            }
+
+            let key = match VirtualPath::with_case(&path, options.case_insensitive) {
+                Ok(key) => key,
+                Err(_err) => {
+                    #[cfg(feature = "collect-errors")]
+                    skipped.push(Skipped::InvalidPath(path, _err));
+                    continue;
+                }
+            };
+
+            if !is_link && key.as_str() == MANIFEST_FILE {
+                // The manifest counts towards the limits like any other entry.
+                let size = entry.size();
+                limits.check_entry(&path, size, &mut total_size)?;
+                let manifest = PackManifest::from_json(serde_json::from_reader(entry.take(size))?)?;
+                if let Some(progress) = progress {
+                    progress.set_total_entries(manifest.entry_count);
+                }
+                // The manifest is untrusted too, so never reserve more than the limits allow.
+                let max_reserved = limits.max_entries.min(MAX_ENTRIES_PREALLOC);
+                contents.reserve(manifest.file_count.min(max_reserved));
                 if cfg!(feature = "collect-errors") {
-                    skipped.reserve(manifest.entry_count - manifest.file_count + 1);
+                    let skipped_count = manifest.entry_count.saturating_sub(manifest.file_count);
+                    skipped.reserve(skipped_count.min(max_reserved) + 1);
                     skipped.push(Skipped::Manifest);
                 }
+
+                #[cfg(feature = "checksum")]
+                if options.checksums != ChecksumPolicy::Ignore {
+                    for (path, checksum) in manifest.checksums {
+                        if let Ok(key) = VirtualPath::with_case(path, options.case_insensitive) {
+                            checksums.insert(key, checksum);
+                        }
+                    }
+                }
                 continue;
             }
 
-            if !header.entry_type().is_file() {
+            let duplicate = contents.contains_key(&key) || links.contains_key(&key);
+            if duplicate {
+                match options.duplicates {
+                    DuplicatePolicy::Error => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            Skipped::Duplicate(path),
+                        ));
+                    }
+                    DuplicatePolicy::FirstWins => {
+                        #[cfg(feature = "collect-errors")]
+                        skipped.push(Skipped::Duplicate(path));
+                        continue;
+                    }
+                    DuplicatePolicy::LastWins => {}
+                }
+            }
+
+            if is_link {
+                let target = entry.link_name()?.unwrap_or_default().into_owned();
+                // Symbolic links are relative to their directory, hard links to the archive root.
+                let resolved = match entry_type.is_symlink() {
+                    true => key.resolve_link(&target),
+                    false => VirtualPath::new(&target),
+                };
+
+                let Ok(resolved) = resolved else {
+                    #[cfg(feature = "collect-errors")]
+                    skipped.push(Skipped::BrokenLink { path, target });
+                    continue;
+                };
+
+                // The replaced entry is the one that gets skipped.
                 #[cfg(feature = "collect-errors")]
-                skipped.push(Skipped::NotAFile(path));
+                if duplicate {
+                    skipped.push(Skipped::Duplicate(path.clone()));
+                }
+                let resolved = match options.case_insensitive {
+                    true => resolved.fold_case(),
+                    false => resolved,
+                };
+                contents.remove(&key);
+                links.insert(
+                    key,
+                    Link {
+                        index,
+                        path,
+                        target,
+                        resolved,
+                    },
+                );
                 continue;
             }
 
-            // Read the file contents into a buffer.
+            // Read the file contents into a buffer, after checking the declared size.
             let size = entry.size();
-            let mut buf = Vec::with_capacity(size as usize);
+            if options.skip_oversized && size > limits.max_entry_size {
+                #[cfg(feature = "collect-errors")]
+                skipped.push(Skipped::Oversize { path, size });
+                continue;
+            }
+            limits.check_entry(&path, size, &mut total_size)?;
+            let mut buf = Vec::with_capacity(size.min(MAX_ENTRY_PREALLOC) as usize);
             entry.take(size).read_to_end(&mut buf)?;
+            if buf.len() as u64 != size {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("Entry {path:?} is truncated"),
+                ));
+            }
+
+            // The replaced entry is the one that gets skipped.
+            #[cfg(feature = "collect-errors")]
+            if duplicate {
+                skipped.push(Skipped::Duplicate(path));
+            }
+            links.remove(&key);
+            contents.insert(key, Arc::new(buf));
+        }
+
+        // Corrupt entries are dropped before resolving links, so links to them are broken too.
+        #[cfg(feature = "checksum")]
+        if options.checksums == ChecksumPolicy::OnLoad {
+            // Sorted, so corrupt entries are reported in a stable order.
+            let mut pending = checksums.drain().collect::<Vec<_>>();
+            pending.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+            for (key, expected) in pending {
+                if let Some(data) = contents.get(&key)
+                    && checksum::verify(key.as_str(), data, expected).is_err()
+                {
+                    contents.remove(&key);
+                    #[cfg(feature = "collect-errors")]
+                    skipped.push(Skipped::Corrupt(key.as_str().into()));
+                }
+            }
+        }
 
-            contents.insert(path, buf);
+        // Links share the contents of their targets instead of copying them.
+        let mut ordered = links.iter().collect::<Vec<_>>();
+        ordered.sort_unstable_by_key(|(_, link)| link.index);
+        let mut aliases = Vec::with_capacity(links.len());
+        #[cfg(feature = "checksum")]
+        let mut link_targets = HashMap::with_hasher(S::default());
+        for (key, link) in ordered {
+            let Some((_target, data)) = resolve_link(&contents, &links, link) else {
+                #[cfg(feature = "collect-errors")]
+                skipped.push(Skipped::BrokenLink {
+                    path: link.path.clone(),
+                    target: link.target.clone(),
+                });
+                continue;
+            };
+            #[cfg(feature = "checksum")]
+            if checksums.contains_key(_target) {
+                link_targets.insert(key.clone(), _target.clone());
+            }
+            aliases.push((key.clone(), data.clone()));
         }
+        contents.extend(aliases);
 
-        Ok(Self { contents, skipped })
+        // Links share the contents of their targets, so each buffer is counted once.
+        let mut buffers = HashSet::with_capacity(contents.len());
+        let bytes_held = contents
+            .values()
+            .filter(|data| buffers.insert(Arc::as_ptr(data)))
+            .map(|data| data.len() as u64)
+            .sum();
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            entries = contents.len(),
+            skipped = skipped.len(),
+            "Loaded TAR pack"
+        );
+
+        Ok(Self {
+            contents,
+            skipped,
+            case_insensitive: options.case_insensitive,
+            #[cfg(feature = "checksum")]
+            checksums,
+            #[cfg(feature = "checksum")]
+            link_targets,
+            archive_size: archive_size.get(),
+            bytes_held,
+            load_duration: start.elapsed(),
+        })
     }
 
     fn exists(&self, path: impl AsRef<Path>) -> bool {
-        self.contents.contains_key(path.as_ref())
+        self.key(path.as_ref())
+            .is_ok_and(|key| self.contents.contains_key(&key))
+    }
+
+    fn stats(&self) -> Option<PackStats> {
+        Some(PackStats {
+            entries: self.contents.len(),
+            bytes_held: self.bytes_held,
+            uncompressed_size: Some(self.archive_size),
+            compressed_size: None,
+            load_duration: Some(self.load_duration),
+        })
     }
 }
@@ -6,13 +6,16 @@ use crate::Pack;
 /// A codec implementation for decoding Zstandard-compressed input streams.
 /// Used with [`EncodedPack`] to transparently decompress `.zst` files.
 #[non_exhaustive]
+#[derive(Debug, Default, Clone, Copy)]
 pub struct ZstdCodec;
 
 #[allow(type_alias_bounds)]
 pub type Zstd<P: Pack> = EncodedPack<P, ZstdCodec>;
 
 impl Decode for ZstdCodec {
-    fn decode(read: impl Read) -> io::Result<impl Read> {
+    const NAME: &'static str = "zstd";
+
+    fn decode(&self, read: impl Read) -> io::Result<impl Read> {
         zstd::Decoder::new(read)
     }
 }
@@ -0,0 +1,170 @@
+/* This module defines the normalized path type used by packs for storing and looking up entries.
+Paths inside packs are virtual: they always use forward slashes, never contain `.` or `..`,
+and can't point outside the pack, regardless of the platform the pack was built on. */
+
+use std::{
+    borrow::Borrow,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+/// Error type for paths that can't be normalized.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum PathError {
+    #[error("Path {0:?} points outside of the pack")]
+    Traversal(PathBuf),
+
+    #[error("Path {0:?} is absolute")]
+    Absolute(PathBuf),
+
+    #[error("Path {0:?} is not valid UTF-8")]
+    NonUtf8(PathBuf),
+
+    #[error("Path {0:?} is empty")]
+    Empty(PathBuf),
+}
+
+impl From<PathError> for io::Error {
+    fn from(err: PathError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidInput, err)
+    }
+}
+
+/// A normalized, traversal-safe path inside a pack.
+///
+/// Both `/` and `\` are accepted as separators, `.` components are removed and `..` components
+/// are resolved. The result is joined with `/`, e.g. `./dir\sub/../file.png` becomes `dir/file.png`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct VirtualPath(String);
+
+impl VirtualPath {
+    /// Normalizes a path.
+    ///
+    /// # Returns
+    /// * `Ok(VirtualPath)` if the path is valid.
+    /// * `Err(PathError)` if the path is absolute, escapes the root, is empty or not UTF-8.
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, PathError> {
+        let path = path.as_ref();
+        let Some(str) = path.to_str() else {
+            return Err(PathError::NonUtf8(path.to_path_buf()));
+        };
+
+        if is_absolute(str) {
+            return Err(PathError::Absolute(path.to_path_buf()));
+        }
+
+        let mut components = Vec::new();
+        for component in str.split(['/', '\\']) {
+            match component {
+                "" | "." => {}
+                ".." => {
+                    if components.pop().is_none() {
+                        return Err(PathError::Traversal(path.to_path_buf()));
+                    }
+                }
+                component => components.push(component),
+            }
+        }
+
+        match components.is_empty() {
+            true => Err(PathError::Empty(path.to_path_buf())),
+            false => Ok(Self(components.join("/"))),
+        }
+    }
+
+    /// Normalizes a path, folding its case if `case_insensitive` is set.
+    pub fn with_case(path: impl AsRef<Path>, case_insensitive: bool) -> Result<Self, PathError> {
+        let path = Self::new(path)?;
+        Ok(match case_insensitive {
+            true => path.fold_case(),
+            false => path,
+        })
+    }
+
+    /// Returns the lowercase form of the path, used for case-insensitive matching.
+    pub fn fold_case(&self) -> Self {
+        Self(self.0.to_lowercase())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the parent directory of the path, or `None` for top-level entries.
+    pub fn parent(&self) -> Option<&str> {
+        self.0.rsplit_once('/').map(|(parent, _)| parent)
+    }
+
+    /// Resolves a symbolic link target, which is relative to the directory containing this path.
+    ///
+    /// # Returns
+    /// * `Ok(VirtualPath)` if the target stays inside the pack.
+    /// * `Err(PathError)` if the target is absolute or escapes the root.
+    pub fn resolve_link(&self, target: impl AsRef<Path>) -> Result<Self, PathError> {
+        let target = target.as_ref();
+        let Some(str) = target.to_str() else {
+            return Err(PathError::NonUtf8(target.to_path_buf()));
+        };
+
+        if is_absolute(str) {
+            return Err(PathError::Absolute(target.to_path_buf()));
+        }
+
+        match self.parent() {
+            Some(parent) => Self::new(format!("{parent}/{str}")),
+            None => Self::new(str),
+        }
+    }
+}
+
+/// Checks for a root or a Windows drive prefix, on any platform.
+fn is_absolute(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    match bytes {
+        [b'/' | b'\\', ..] => true,
+        [drive, b':', ..] => drive.is_ascii_alphabetic(),
+        _ => false,
+    }
+}
+
+impl fmt::Display for VirtualPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for VirtualPath {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for VirtualPath {
+    fn as_ref(&self) -> &Path {
+        Path::new(&self.0)
+    }
+}
+
+impl Borrow<str> for VirtualPath {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<&str> for VirtualPath {
+    type Error = PathError;
+
+    fn try_from(path: &str) -> Result<Self, Self::Error> {
+        Self::new(path)
+    }
+}
+
+impl TryFrom<&Path> for VirtualPath {
+    type Error = PathError;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        Self::new(path)
+    }
+}
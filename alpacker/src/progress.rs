@@ -0,0 +1,174 @@
+/* This module reports pack loading progress, e.g. for loading screens. An observer passed in
+`LoadOptions` is told the totals as soon as they're known (the file size and the manifest entry count),
+and then receives the bytes read from the file, the bytes decoded by codecs and every processed entry. */
+
+use std::{
+    fmt,
+    io::{self, Read},
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+};
+
+/// An observer of pack loading progress.
+///
+/// All methods have empty default implementations, so observers only implement what they need.
+/// Methods take `&self` and may be called from the loading thread while the observer is read elsewhere.
+pub trait Progress: Send + Sync {
+    /// Called once the size of the pack file is known.
+    fn set_total_bytes(&self, bytes: u64) {
+        let _ = bytes;
+    }
+
+    /// Called once the number of entries is known from the [PackManifest](crate::PackManifest).
+    fn set_total_entries(&self, entries: usize) {
+        let _ = entries;
+    }
+
+    /// Called with the number of bytes just read from the pack file.
+    fn bytes_read(&self, bytes: u64) {
+        let _ = bytes;
+    }
+
+    /// Called with the number of bytes just decoded by a codec.
+    fn bytes_decoded(&self, bytes: u64) {
+        let _ = bytes;
+    }
+
+    /// Called for every entry of the pack, including the ones that are skipped.
+    fn entry(&self, path: &Path) {
+        let _ = path;
+    }
+}
+
+impl fmt::Debug for dyn Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Progress")
+    }
+}
+
+/// A [Progress] implementation that accumulates counters, for polling from another thread.
+#[derive(Debug, Default)]
+pub struct LoadProgress {
+    total_bytes: AtomicU64,
+    total_entries: AtomicUsize,
+    bytes_read: AtomicU64,
+    bytes_decoded: AtomicU64,
+    entries: AtomicUsize,
+}
+
+impl LoadProgress {
+    /// Creates a shared counter, ready to be put into [LoadOptions::progress](crate::LoadOptions::progress).
+    pub fn new() -> Arc<Self> {
+        Arc::default()
+    }
+
+    /// Returns the size of the pack file, or 0 if unknown.
+    pub fn total_bytes(&self) -> u64 {
+        self.total_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of entries in the pack, or 0 if unknown.
+    pub fn total_entries(&self) -> usize {
+        self.total_entries.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes_decoded(&self) -> u64 {
+        self.bytes_decoded.load(Ordering::Relaxed)
+    }
+
+    pub fn entries(&self) -> usize {
+        self.entries.load(Ordering::Relaxed)
+    }
+
+    /// Returns the loaded fraction in the range `0.0..=1.0`.
+    ///
+    /// Bytes are preferred over entries, since a single large entry may take most of the time.
+    ///
+    /// # Returns
+    /// * `Some(f32)` if either total is known.
+    /// * `None` otherwise.
+    pub fn fraction(&self) -> Option<f32> {
+        let (done, total) = match (self.total_bytes(), self.total_entries()) {
+            (0, 0) => return None,
+            (0, total) => (self.entries() as f64, total as f64),
+            (total, _) => (self.bytes_read() as f64, total as f64),
+        };
+        Some((done / total).min(1.0) as f32)
+    }
+}
+
+impl Progress for LoadProgress {
+    fn set_total_bytes(&self, bytes: u64) {
+        self.total_bytes.store(bytes, Ordering::Relaxed);
+    }
+
+    fn set_total_entries(&self, entries: usize) {
+        self.total_entries.store(entries, Ordering::Relaxed);
+    }
+
+    fn bytes_read(&self, bytes: u64) {
+        self.bytes_read.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    fn bytes_decoded(&self, bytes: u64) {
+        self.bytes_decoded.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    fn entry(&self, _: &Path) {
+        self.entries.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// A reader that reports the bytes passing through it to a [Progress].
+///
+/// [Assets](crate::Assets) wraps pack files in it automatically;
+/// use it directly when calling [Pack::load_with](crate::Pack::load_with) on other sources.
+pub struct ProgressRead<R> {
+    read: R,
+    progress: Option<Arc<dyn Progress>>,
+    decoded: bool,
+}
+
+impl<R: Read> ProgressRead<R> {
+    /// Wraps a reader over a pack file, reporting [Progress::bytes_read].
+    pub fn new(read: R, progress: Arc<dyn Progress>) -> Self {
+        Self::encoded(read, Some(progress))
+    }
+
+    /// Wraps a reader over a pack file, reporting [Progress::bytes_read] if `progress` is set.
+    pub(crate) fn encoded(read: R, progress: Option<Arc<dyn Progress>>) -> Self {
+        Self {
+            read,
+            progress,
+            decoded: false,
+        }
+    }
+
+    /// Wraps a reader over a decoded stream, reporting [Progress::bytes_decoded] if `progress` is set.
+    pub(crate) fn decoded(read: R, progress: Option<Arc<dyn Progress>>) -> Self {
+        Self {
+            read,
+            progress,
+            decoded: true,
+        }
+    }
+}
+
+impl<R: Read> Read for ProgressRead<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.read.read(buf)?;
+        match (&self.progress, self.decoded) {
+            (Some(progress), false) => progress.bytes_read(len as u64),
+            (Some(progress), true) => progress.bytes_decoded(len as u64),
+            (None, _) => {}
+        }
+        Ok(len)
+    }
+}
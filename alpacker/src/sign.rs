@@ -0,0 +1,182 @@
+/* This module authenticates shipped asset packs. The packer records a SHA-256 hash of every pack
+in the manifest and signs the exact bytes of the manifest file using an Ed25519 key. The runtime checks
+the signature against a public key embedded into the game before the manifest is parsed, and the hash
+of each pack before it's loaded. */
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Read, Write},
+};
+
+use ed25519_dalek::{Signature, Signer, Verifier};
+pub use ed25519_dalek::{SigningKey, VerifyingKey};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::{Assets, JsonIoError, error::ErrorKind, version::UnsupportedVersion};
+
+/// Error type for signature creation and verification.
+#[derive(Debug, Error)]
+pub enum SignatureError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Deserializer error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Version error: {0}")]
+    Version(#[from] UnsupportedVersion),
+
+    #[error("Signed builds must load the manifest with `Assets::load_from_dir_signed`")]
+    KeyRequired,
+
+    #[error("The manifest is not signed")]
+    Unsigned,
+
+    #[error("Pack \"{0}\" is not covered by the manifest signature")]
+    UnsignedPack(String),
+
+    #[error("Malformed signature: {0}")]
+    Malformed(#[from] hex::FromHexError),
+
+    #[error("The manifest signature does not match the public key")]
+    BadSignature(#[from] ed25519_dalek::SignatureError),
+
+    #[error("Pack \"{0}\" does not match its signed hash")]
+    HashMismatch(String),
+}
+
+impl From<JsonIoError> for SignatureError {
+    fn from(err: JsonIoError) -> Self {
+        match err {
+            JsonIoError::Io(err) => Self::Io(err),
+            JsonIoError::Json(err) => Self::Json(err),
+            JsonIoError::Version(err) => Self::Version(err),
+            JsonIoError::Signature(err) => err,
+        }
+    }
+}
+
+impl From<SignatureError> for crate::Error {
+    fn from(err: SignatureError) -> Self {
+        match err {
+            SignatureError::Io(err) => err.into(),
+            SignatureError::Json(err) => err.into(),
+            SignatureError::Version(err) => err.into(),
+            SignatureError::UnsignedPack(ref pack) | SignatureError::HashMismatch(ref pack) => {
+                let pack = pack.clone();
+                Self::new(ErrorKind::Signature, err).with_pack(pack)
+            }
+            err => Self::new(ErrorKind::Signature, err),
+        }
+    }
+}
+
+/// The file next to the manifest that holds the hex-encoded signature of its exact bytes.
+pub const SIGNATURE_FILE: &str = "manifest.json.sig";
+
+/// A serialized manifest with its detached signature, see [Assets::sign].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedManifest {
+    /// The contents of the manifest file.
+    pub manifest: Vec<u8>,
+    /// The contents of [SIGNATURE_FILE].
+    pub signature: String,
+}
+
+/// Checks the detached signature of a manifest file against `key`.
+///
+/// # Arguments
+/// * `manifest` - The exact bytes of the manifest file.
+/// * `signature` - The contents of [SIGNATURE_FILE].
+pub(crate) fn verify_manifest(
+    manifest: &[u8],
+    signature: &str,
+    key: &VerifyingKey,
+) -> Result<(), SignatureError> {
+    let mut bytes = [0; Signature::BYTE_SIZE];
+    hex::decode_to_slice(signature.trim(), &mut bytes)?;
+    key.verify(manifest, &Signature::from_bytes(&bytes))?;
+    Ok(())
+}
+
+/// A reader or writer adapter that computes the SHA-256 hash of all data passing through it.
+pub struct Hashed<T> {
+    inner: T,
+    hasher: Sha256,
+}
+
+impl<T> Hashed<T> {
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// Returns the hex-encoded hash of the data processed so far.
+    pub fn finish(self) -> String {
+        hex::encode(self.hasher.finalize())
+    }
+}
+
+impl<R: Read> Read for Hashed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.hasher.update(&buf[..len]);
+        Ok(len)
+    }
+}
+
+impl<W: Write> Write for Hashed<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.inner.write(buf)?;
+        self.hasher.update(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Assets {
+    /// Records the hashes of the packs in the manifest, serializes it and signs the resulting bytes.
+    ///
+    /// # Arguments
+    /// * `hashes` - Hex-encoded SHA-256 hashes of the pack files, keyed by pack name.
+    /// * `key` - The private key used for signing.
+    ///
+    /// # Returns
+    /// * `Ok(SignedManifest)` with the contents of the manifest and [SIGNATURE_FILE].
+    /// * `Err(SignatureError::UnsignedPack)` if a pack has no hash.
+    pub fn sign(
+        &mut self,
+        hashes: BTreeMap<String, String>,
+        key: &SigningKey,
+    ) -> Result<SignedManifest, SignatureError> {
+        if let Some(name) = self.packs.keys().find(|name| !hashes.contains_key(*name)) {
+            return Err(SignatureError::UnsignedPack(name.clone()));
+        }
+
+        self.hashes = hashes;
+        let manifest = serde_json::to_vec(self)?;
+        let signature = hex::encode(key.sign(&manifest).to_bytes());
+        Ok(SignedManifest {
+            manifest,
+            signature,
+        })
+    }
+
+    /// Returns the signed hash of a pack, if signature verification is enabled.
+    pub(crate) fn expected_hash(&self, name: &str) -> Result<Option<&str>, SignatureError> {
+        if self.verifying_key.is_none() {
+            return Ok(None);
+        }
+
+        match self.hashes.get(name) {
+            Some(hash) => Ok(Some(hash)),
+            None => Err(SignatureError::UnsignedPack(name.to_string())),
+        }
+    }
+}
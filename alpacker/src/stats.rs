@@ -0,0 +1,203 @@
+/* This module reports what loaded packs cost, for profiling and memory dashboards. Packs describe
+themselves through `Pack::stats`, and `Measured` wraps any pack to time its loading and count the assets
+loaded from it by type. `Assets::loaded_packs` includes the statistics of every cached pack. */
+
+use std::{
+    any::type_name,
+    collections::HashMap,
+    io,
+    ops::{Deref, DerefMut},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use crate::{Asset, LoadOptions, Pack, Raw, SharedBytes, pack::Diagnostics, pack::Skipped};
+
+/// Statistics of a loaded pack.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackStats {
+    /// The number of entries that can be read, including resolved links.
+    pub entries: usize,
+
+    /// The memory held by entry contents, in bytes. Contents shared by links are counted once.
+    pub bytes_held: u64,
+
+    /// The number of bytes read from the decoded pack stream, e.g. the TAR archive, if known.
+    pub uncompressed_size: Option<u64>,
+
+    /// The size of the encoded input, if the pack was decoded by a codec.
+    pub compressed_size: Option<u64>,
+
+    /// The time it took to load the pack, including decoding, if known.
+    pub load_duration: Option<Duration>,
+}
+
+impl PackStats {
+    /// Returns how many times the pack shrank when encoded.
+    ///
+    /// # Returns
+    /// * `Some(f64)` if both sizes are known and the compressed size isn't zero.
+    /// * `None` otherwise.
+    pub fn compression_ratio(&self) -> Option<f64> {
+        match (self.uncompressed_size, self.compressed_size) {
+            (Some(uncompressed), Some(compressed)) if compressed > 0 => {
+                Some(uncompressed as f64 / compressed as f64)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Statistics of the assets of one type loaded from a [Measured] pack.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AssetStats {
+    /// The number of calls to [Asset::load], successful or not.
+    pub loads: u64,
+
+    /// The number of loads that returned an error.
+    pub failures: u64,
+
+    /// The time spent in [Asset::load], including assets it loaded from the same pack.
+    pub total_time: Duration,
+}
+
+impl AssetStats {
+    /// Returns the mean time of a single load, or `None` if nothing was loaded.
+    pub fn average_time(&self) -> Option<Duration> {
+        let loads = u32::try_from(self.loads).ok().filter(|&loads| loads > 0)?;
+        Some(self.total_time / loads)
+    }
+}
+
+/// A wrapper pack that measures loading and counts the assets retrieved through [Pack::get].
+///
+/// # Example
+/// ```ignore
+/// let mut pack = assets.load_pack::<Measured<TarZstPack>>("main")?;
+/// let sprite = pack.get::<Sprite>("player.json")?;
+/// println!("{:?}", pack.asset_stats());
+/// ```
+#[derive(Debug)]
+pub struct Measured<P> {
+    pub pack: P,
+    load_duration: Duration,
+    assets: HashMap<&'static str, AssetStats>,
+}
+
+impl<P> Measured<P> {
+    /// Wraps an already loaded pack. Its load duration is unknown, so it's reported as zero.
+    pub fn new(pack: P) -> Self {
+        Self {
+            pack,
+            load_duration: Duration::ZERO,
+            assets: HashMap::new(),
+        }
+    }
+
+    /// Returns the time it took to load the inner pack.
+    pub const fn load_duration(&self) -> Duration {
+        self.load_duration
+    }
+
+    /// Returns the statistics of loaded assets, keyed by the asset type name.
+    pub const fn asset_stats(&self) -> &HashMap<&'static str, AssetStats> {
+        &self.assets
+    }
+
+    /// Returns the statistics of assets of type `A`, if any were loaded.
+    pub fn asset_stats_of<A: Asset>(&self) -> Option<AssetStats> {
+        self.assets.get(type_name::<A>()).copied()
+    }
+
+    /// Clears the asset statistics, e.g. between frames.
+    pub fn reset_asset_stats(&mut self) {
+        self.assets.clear();
+    }
+
+    pub fn into_inner(self) -> P {
+        self.pack
+    }
+}
+
+impl<P: Pack> Pack for Measured<P> {
+    const BACKEND: &'static str = P::BACKEND;
+    const CODEC: Option<&'static str> = P::CODEC;
+
+    fn load(read: impl io::Read) -> io::Result<Self> {
+        Self::load_with(read, &LoadOptions::default())
+    }
+
+    fn load_with(read: impl io::Read, options: &LoadOptions) -> io::Result<Self> {
+        let start = Instant::now();
+        let pack = P::load_with(read, options)?;
+
+        Ok(Self {
+            load_duration: start.elapsed(),
+            ..Self::new(pack)
+        })
+    }
+
+    #[inline(always)]
+    fn get_raw<'p>(&mut self, path: &'p Path) -> io::Result<Raw<'p, impl io::Read + io::Seek>> {
+        self.pack.get_raw(path)
+    }
+
+    #[inline(always)]
+    fn get_shared(&mut self, path: &Path) -> io::Result<SharedBytes> {
+        self.pack.get_shared(path)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(asset = std::any::type_name::<A>(), path = ?path.as_ref())
+        )
+    )]
+    fn get<A: Asset>(&mut self, path: impl AsRef<Path>) -> Result<A, A::Error> {
+        let start = Instant::now();
+        let result = A::load(self, path);
+        let elapsed = start.elapsed();
+
+        let stats = self.assets.entry(type_name::<A>()).or_default();
+        stats.loads += 1;
+        stats.failures += u64::from(result.is_err());
+        stats.total_time += elapsed;
+        result
+    }
+
+    #[inline(always)]
+    fn exists(&self, path: impl AsRef<Path>) -> bool {
+        self.pack.exists(path)
+    }
+
+    fn stats(&self) -> Option<PackStats> {
+        let stats = self.pack.stats()?;
+        Some(PackStats {
+            load_duration: Some(self.load_duration),
+            ..stats
+        })
+    }
+}
+
+impl<P: Diagnostics> Diagnostics for Measured<P> {
+    #[inline(always)]
+    fn skipped(&self) -> &[Skipped] {
+        self.pack.skipped()
+    }
+}
+
+impl<P> Deref for Measured<P> {
+    type Target = P;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.pack
+    }
+}
+
+impl<P> DerefMut for Measured<P> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.pack
+    }
+}
@@ -0,0 +1,199 @@
+/* This module is a conformance test kit for custom pack formats and codecs. It generates a tree
+of tricky files, builds a pack from it with a user-provided function, and checks that the pack
+returns the same files, hides the manifest, and rejects corrupt input. Checks panic on failure,
+like `assert!`, so they can be called directly from tests. */
+
+use std::{
+    env, fs,
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{MANIFEST_FILE, Pack};
+
+/// Size of the large sample file, bigger than typical codec block sizes.
+pub const LARGE_FILE_SIZE: usize = 3 * 1024 * 1024;
+
+/// A file of the generated sample tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampleFile {
+    /// Path relative to the tree root, separated with `/`.
+    pub path: &'static str,
+    pub data: Vec<u8>,
+}
+
+/// Generates the sample tree: nested directories, an empty file, unicode names and a large file.
+pub fn sample_files() -> Vec<SampleFile> {
+    // A xorshift sequence, so the large file doesn't compress to nothing.
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let large = (0..LARGE_FILE_SIZE)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect();
+
+    let file = |path, data: &[u8]| SampleFile {
+        path,
+        data: data.to_vec(),
+    };
+
+    vec![
+        file("top.txt", b"Hello, World!\n"),
+        file("empty.txt", b""),
+        file("a/b/c/d/nested.txt", b"Deeply nested\n"),
+        file("a/sibling.bin", &[0, 1, 2, 255]),
+        file("dir with spaces/file name.txt", b"Spaces\n"),
+        file("ünïcødé/日本語.txt", "Unicode ✓\n".as_bytes()),
+        SampleFile {
+            path: "large.bin",
+            data: large,
+        },
+    ]
+}
+
+/// Writes files into a directory, creating parent directories as needed.
+pub fn write_files(root: impl AsRef<Path>, files: &[SampleFile]) -> io::Result<()> {
+    let root = root.as_ref();
+    for file in files {
+        let path = root.join(file.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, &file.data)?;
+    }
+    Ok(())
+}
+
+/// A temporary directory removed on drop.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new() -> io::Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let dir = env::temp_dir().join(format!(
+            "alpacker-conformance-{}-{time}-{id}",
+            std::process::id()
+        ));
+        fs::create_dir(&dir)?;
+        Ok(Self(dir))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Runs every check against a pack format.
+///
+/// # Arguments
+/// * `make` - Builds a pack from a directory, returning its bytes.
+///   For [MakePack](https://docs.rs/alpacker-packer) implementations, this is `P::make`.
+///
+/// # Panics
+/// If building the pack fails, or the pack doesn't pass a check.
+pub fn check_pack<P: Pack>(make: impl FnOnce(&Path) -> io::Result<Vec<u8>>) {
+    let files = sample_files();
+    let root = TempDir::new().expect("Failed to create a temporary directory");
+    write_files(&root.0, &files).expect("Failed to write the sample files");
+
+    let data = make(&root.0).expect("Failed to build the pack");
+    let mut pack = P::load(data.as_slice()).expect("Failed to load the pack");
+
+    check_files(&mut pack, &files);
+    check_missing(&mut pack);
+    check_corrupt::<P>(&data);
+}
+
+/// Checks that the pack returns every file with its exact contents.
+///
+/// # Panics
+/// If a file is missing, differs, or [Pack::get_raw] reports wrong metadata.
+pub fn check_files<P: Pack>(pack: &mut P, files: &[SampleFile]) {
+    for file in files {
+        assert!(pack.exists(file.path), "{:?} must exist", file.path);
+        assert!(
+            pack.exists(format!("./{}", file.path)),
+            "{:?} must be found through a non-normalized path",
+            file.path
+        );
+
+        let path = Path::new(file.path);
+        let mut raw = pack
+            .get_raw(path)
+            .unwrap_or_else(|err| panic!("Failed to get {:?}: {err}", file.path));
+        assert_eq!(raw.path, path, "Raw path of {:?}", file.path);
+        if let Some(size_hint) = raw.size_hint {
+            assert_eq!(size_hint, file.data.len(), "Size hint of {:?}", file.path);
+        }
+
+        let mut data = Vec::new();
+        raw.read
+            .read_to_end(&mut data)
+            .unwrap_or_else(|err| panic!("Failed to read {:?}: {err}", file.path));
+        assert!(data == file.data, "Contents of {:?} differ", file.path);
+
+        let end = raw.read.seek(SeekFrom::End(0)).unwrap();
+        assert_eq!(
+            end,
+            file.data.len() as u64,
+            "Seeking to the end of {:?}",
+            file.path
+        );
+        raw.read.rewind().unwrap();
+    }
+}
+
+/// Checks that missing files, directories and the manifest are not exposed.
+///
+/// # Panics
+/// If any of them is found.
+pub fn check_missing<P: Pack>(pack: &mut P) {
+    for path in ["missing.txt", "a/b", "a/missing.txt", MANIFEST_FILE] {
+        assert!(!pack.exists(path), "{path:?} must not exist");
+
+        match pack.get_raw(Path::new(path)) {
+            Ok(_) => panic!("{path:?} must not be returned"),
+            Err(err) => assert_eq!(
+                err.kind(),
+                io::ErrorKind::NotFound,
+                "Missing {path:?} must fail with NotFound"
+            ),
+        }
+    }
+}
+
+/// Checks that garbage and truncated input are rejected.
+///
+/// # Arguments
+/// * `data` - A valid pack, which is truncated to produce corrupt input.
+///
+/// # Panics
+/// If corrupt input loads successfully.
+pub fn check_corrupt<P: Pack>(data: &[u8]) {
+    let garbage = b"This is not a pack. ".repeat(64);
+    assert!(
+        P::load(garbage.as_slice()).is_err(),
+        "Garbage input must be rejected"
+    );
+
+    // The large file makes up most of the pack, so the cut lands inside its data.
+    let len = data.len() / 2;
+    assert!(
+        P::load(&data[..len]).is_err(),
+        "Input truncated to {len} of {} bytes must be rejected",
+        data.len()
+    );
+}
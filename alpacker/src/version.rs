@@ -0,0 +1,71 @@
+/* This module keeps manifests readable across releases. Every manifest stores a `format_version`;
+older documents are upgraded step by step on the raw JSON before deserialization,
+and documents newer than the runtime are rejected with a clear error. */
+
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use crate::JsonIoError;
+
+/// The name of the field storing the format version. Documents without it are version 0.
+pub const VERSION_FIELD: &str = "format_version";
+
+/// Error returned when a document was written by a newer version of the format than supported.
+#[derive(Debug, Error)]
+#[error("{kind} format version {found} is newer than the supported version {supported}")]
+pub struct UnsupportedVersion {
+    pub kind: &'static str,
+    pub found: u64,
+    pub supported: u32,
+}
+
+/// A migration step that upgrades a raw JSON object from one format version to the next.
+pub type Migration = fn(&mut Map<String, Value>);
+
+/// Trait for documents with a versioned JSON schema.
+pub trait Versioned: DeserializeOwned {
+    /// Human-readable name of the document, used in errors.
+    const KIND: &'static str;
+
+    /// The latest format version, written by this version of the library.
+    const FORMAT_VERSION: u32;
+
+    /// Migration steps, where the step at index `i` upgrades version `i` to `i + 1`.
+    /// Must contain exactly [Versioned::FORMAT_VERSION] steps.
+    const MIGRATIONS: &'static [Migration];
+
+    /// Upgrades a raw JSON document to the latest version and deserializes it.
+    ///
+    /// # Returns
+    /// * `Ok(Self)` if the document is successfully migrated and deserialized.
+    /// * `Err(JsonIoError::Version)` if the document is newer than [Versioned::FORMAT_VERSION].
+    /// * `Err(JsonIoError::Json)` if the document doesn't match the schema,
+    ///   or its version isn't an unsigned integer.
+    fn from_json(mut value: Value) -> Result<Self, JsonIoError> {
+        debug_assert_eq!(Self::MIGRATIONS.len(), Self::FORMAT_VERSION as usize);
+
+        if let Value::Object(object) = &mut value {
+            let found = match object.get(VERSION_FIELD) {
+                Some(version) => u64::deserialize(version)?,
+                None => 0,
+            };
+
+            if found > Self::FORMAT_VERSION as u64 {
+                return Err(UnsupportedVersion {
+                    kind: Self::KIND,
+                    found,
+                    supported: Self::FORMAT_VERSION,
+                }
+                .into());
+            }
+
+            for (version, migrate) in Self::MIGRATIONS.iter().enumerate().skip(found as usize) {
+                migrate(object);
+                object.insert(VERSION_FIELD.to_string(), (version + 1).into());
+            }
+        }
+
+        Ok(serde_json::from_value(value)?)
+    }
+}
@@ -0,0 +1,321 @@
+mod common;
+use common::*;
+
+use std::{io::Write, time::Duration};
+
+use alpacker::{
+    Pack,
+    data::ase::{AsepriteError, AsepriteFile, BlendMode, CelContent, Direction, LayerKind},
+    pack::TarPack,
+};
+use flate2::{Compression, write::ZlibEncoder};
+
+const RED: [u8; 4] = [255, 0, 0, 255];
+const BLUE: [u8; 4] = [0, 0, 255, 255];
+const GREEN: [u8; 4] = [0, 255, 0, 255];
+const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+fn string(name: &str) -> Vec<u8> {
+    let mut data = (name.len() as u16).to_le_bytes().to_vec();
+    data.extend_from_slice(name.as_bytes());
+    data
+}
+
+fn chunk(kind: u16, data: &[u8]) -> Vec<u8> {
+    let mut chunk = ((data.len() + 6) as u32).to_le_bytes().to_vec();
+    chunk.extend_from_slice(&kind.to_le_bytes());
+    chunk.extend_from_slice(data);
+    chunk
+}
+
+fn layer(name: &str, flags: u16, kind: u16, level: u16, opacity: u8) -> Vec<u8> {
+    let mut data = Vec::new();
+    for word in [flags, kind, level, 0, 0, 0] {
+        data.extend_from_slice(&word.to_le_bytes());
+    }
+    data.extend_from_slice(&[opacity, 0, 0, 0]);
+    data.extend(string(name));
+    chunk(0x2004, &data)
+}
+
+fn cel(layer: u16, (x, y): (i16, i16), opacity: u8, kind: u16, content: &[u8]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&layer.to_le_bytes());
+    data.extend_from_slice(&x.to_le_bytes());
+    data.extend_from_slice(&y.to_le_bytes());
+    data.push(opacity);
+    data.extend_from_slice(&kind.to_le_bytes());
+    data.extend_from_slice(&[0; 7]);
+    data.extend_from_slice(content);
+    chunk(0x2005, &data)
+}
+
+fn image(width: u16, height: u16, pixels: &[u8], compressed: bool) -> Vec<u8> {
+    let mut data = width.to_le_bytes().to_vec();
+    data.extend_from_slice(&height.to_le_bytes());
+    match compressed {
+        true => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(pixels).unwrap();
+            data.extend(encoder.finish().unwrap());
+        }
+        false => data.extend_from_slice(pixels),
+    }
+    data
+}
+
+fn fill(color: [u8; 4], count: usize) -> Vec<u8> {
+    color.repeat(count)
+}
+
+fn file(size: (u16, u16), depth: u16, frames: &[(u16, Vec<Vec<u8>>)]) -> Vec<u8> {
+    let mut header = vec![0; 128];
+    header[4..6].copy_from_slice(&0xA5E0u16.to_le_bytes());
+    header[6..8].copy_from_slice(&(frames.len() as u16).to_le_bytes());
+    header[8..10].copy_from_slice(&size.0.to_le_bytes());
+    header[10..12].copy_from_slice(&size.1.to_le_bytes());
+    header[12..14].copy_from_slice(&depth.to_le_bytes());
+    // Layer opacity is valid
+    header[14] = 1;
+
+    let mut data = header;
+    for (duration, chunks) in frames {
+        let body = chunks.concat();
+        data.extend_from_slice(&((body.len() + 16) as u32).to_le_bytes());
+        data.extend_from_slice(&0xF1FAu16.to_le_bytes());
+        data.extend_from_slice(&(chunks.len() as u16).to_le_bytes());
+        data.extend_from_slice(&duration.to_le_bytes());
+        data.extend_from_slice(&[0; 2]);
+        data.extend_from_slice(&(chunks.len() as u32).to_le_bytes());
+        data.extend(body);
+    }
+    let len = data.len() as u32;
+    data[..4].copy_from_slice(&len.to_le_bytes());
+    data
+}
+
+/// A 4x4 RGBA sprite with two frames, hidden layers, a tag, a palette and a slice.
+fn sprite() -> Vec<u8> {
+    let mut tags = 1u16.to_le_bytes().to_vec();
+    tags.extend_from_slice(&[0; 8]);
+    tags.extend_from_slice(&0u16.to_le_bytes());
+    tags.extend_from_slice(&1u16.to_le_bytes());
+    tags.push(2);
+    tags.extend_from_slice(&3u16.to_le_bytes());
+    tags.extend_from_slice(&[0; 10]);
+    tags.extend(string("walk"));
+
+    let mut palette = Vec::new();
+    for dword in [2u32, 0, 1] {
+        palette.extend_from_slice(&dword.to_le_bytes());
+    }
+    palette.extend_from_slice(&[0; 8]);
+    for color in [RED, BLUE] {
+        palette.extend_from_slice(&0u16.to_le_bytes());
+        palette.extend_from_slice(&color);
+    }
+
+    let mut slice = Vec::new();
+    for dword in [1u32, 2, 0] {
+        slice.extend_from_slice(&dword.to_le_bytes());
+    }
+    slice.extend(string("hitbox"));
+    for long in [1i32, 0, 1, 2, 3, 1, 2] {
+        slice.extend_from_slice(&long.to_le_bytes());
+    }
+
+    let first = vec![
+        layer("background", 1 | 8, 0, 0, 255),
+        layer("top", 1, 0, 0, 255),
+        layer("hidden", 0, 0, 0, 255),
+        layer("group", 0, 1, 0, 255),
+        layer("child", 1, 0, 1, 255),
+        chunk(0x2019, &palette),
+        chunk(0x2018, &tags),
+        chunk(0x2022, &slice),
+        cel(0, (0, 0), 255, 2, &image(4, 4, &fill(RED, 16), true)),
+        cel(1, (1, 1), 255, 0, &image(2, 2, &fill(BLUE, 4), false)),
+        cel(2, (0, 0), 255, 0, &image(4, 4, &fill(GREEN, 16), false)),
+        cel(4, (0, 0), 255, 0, &image(4, 4, &fill(GREEN, 16), false)),
+    ];
+    let second = vec![
+        cel(0, (0, 0), 255, 1, &0u16.to_le_bytes()),
+        cel(1, (3, 3), 128, 0, &image(2, 2, &fill(WHITE, 4), false)),
+    ];
+
+    file((4, 4), 32, &[(100, first), (150, second)])
+}
+
+fn pixel(pixels: &[u8], width: usize, (x, y): (usize, usize)) -> [u8; 4] {
+    let offset = (y * width + x) * 4;
+    pixels[offset..offset + 4].try_into().unwrap()
+}
+
+/// Test that the structure of a file is parsed.
+#[test]
+fn test_parse() -> TestResult {
+    let file = AsepriteFile::parse(sprite().as_slice())?;
+
+    assert_eq!((file.width, file.height), (4, 4));
+    let names = file.layers.iter().map(|layer| layer.name.as_str());
+    assert!(names.eq(["background", "top", "hidden", "group", "child"]));
+    assert_eq!(file.layers[3].kind, LayerKind::Group);
+    assert_eq!(file.layers[1].blend_mode, BlendMode::Normal);
+
+    assert_eq!(file.frames.len(), 2);
+    assert_eq!(file.frames[1].duration, Duration::from_millis(150));
+    assert_eq!(file.frames[1].cels[0].content, CelContent::Linked(0));
+
+    let tag = file.tag("walk").unwrap();
+    assert_eq!((tag.from, tag.to, tag.repeat), (0, 1, 3));
+    assert_eq!(tag.direction, Direction::PingPong);
+
+    assert_eq!(file.palette, [RED, BLUE]);
+
+    let key = file.slice("hitbox").unwrap().keys[0];
+    assert_eq!((key.frame, key.bounds.y, key.bounds.height), (1, 1, 3));
+    assert_eq!(key.pivot, Some((1, 2)));
+    assert_eq!(key.center, None);
+
+    Ok(())
+}
+
+/// Test that frames are composited from visible layers only.
+#[test]
+fn test_composite() -> TestResult {
+    let file = AsepriteFile::parse(sprite().as_slice())?;
+
+    let first = file.frame_rgba(0).unwrap();
+    assert_eq!(
+        pixel(&first, 4, (0, 0)),
+        RED,
+        "Hidden layers must not be drawn"
+    );
+    assert_eq!(pixel(&first, 4, (1, 1)), BLUE);
+    assert_eq!(pixel(&first, 4, (2, 2)), BLUE);
+    assert_eq!(pixel(&first, 4, (3, 3)), RED);
+
+    // The background is linked to the first frame, and the top cel is half transparent and clipped
+    let second = file.frame_rgba(1).unwrap();
+    assert_eq!(pixel(&second, 4, (0, 0)), RED);
+    assert_eq!(pixel(&second, 4, (3, 3)), [255, 128, 128, 255]);
+
+    assert!(file.frame_rgba(2).is_none());
+
+    Ok(())
+}
+
+/// Test that indexed pixels use the palette and the transparent index.
+#[test]
+fn test_indexed() -> TestResult {
+    let mut palette = Vec::new();
+    for dword in [2u32, 0, 1] {
+        palette.extend_from_slice(&dword.to_le_bytes());
+    }
+    palette.extend_from_slice(&[0; 8]);
+    for color in [WHITE, GREEN] {
+        palette.extend_from_slice(&0u16.to_le_bytes());
+        palette.extend_from_slice(&color);
+    }
+
+    let chunks = vec![
+        layer("layer", 1, 0, 0, 255),
+        chunk(0x2019, &palette),
+        cel(0, (0, 0), 255, 0, &image(2, 1, &[0, 1], false)),
+    ];
+    let file = AsepriteFile::parse(file((2, 1), 8, &[(100, chunks)]).as_slice())?;
+
+    let pixels = file.frame_rgba(0).unwrap();
+    assert_eq!(pixel(&pixels, 2, (0, 0)), [0; 4]);
+    assert_eq!(pixel(&pixels, 2, (1, 0)), GREEN);
+
+    Ok(())
+}
+
+/// Test that files are loaded from packs, and malformed files are rejected.
+#[test]
+fn test_load() -> TestResult {
+    let sprite = sprite();
+    let mut truncated = sprite.clone();
+    truncated.truncate(sprite.len() - 10);
+
+    let archive = tar_archive(&[
+        ("hero.aseprite", &sprite),
+        ("truncated.aseprite", &truncated),
+        (
+            "text.ase",
+            b"Not an Aseprite file, but long enough to have a header"
+                .repeat(4)
+                .as_slice(),
+        ),
+    ]);
+    let mut pack: TarPack = TarPack::load(archive.as_slice())?;
+
+    let file = pack.get::<AsepriteFile>("hero.aseprite")?;
+    assert_eq!(file.frames.len(), 2);
+
+    let err = pack.get::<AsepriteFile>("truncated.aseprite").unwrap_err();
+    assert!(matches!(err, AsepriteError::Io(_)), "{err}");
+    let err = pack.get::<AsepriteFile>("text.ase").unwrap_err();
+    assert!(matches!(err, AsepriteError::Magic(_)), "{err}");
+
+    Ok(())
+}
+
+/// Test that huge canvases and cels are rejected before they're allocated.
+#[test]
+fn test_too_large() {
+    let err = AsepriteFile::parse(file((u16::MAX, u16::MAX), 32, &[]).as_slice()).unwrap_err();
+    assert!(matches!(err, AsepriteError::TooLarge { .. }), "{err}");
+
+    // A tiny compressed cel claiming to be 65535x65535
+    let chunks = vec![
+        layer("layer", 1, 0, 0, 255),
+        cel(0, (0, 0), 255, 2, &image(u16::MAX, u16::MAX, &[], true)),
+    ];
+    let err = AsepriteFile::parse(file((4, 4), 32, &[(100, chunks)]).as_slice()).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            AsepriteError::TooLarge {
+                width: u16::MAX,
+                height: u16::MAX
+            }
+        ),
+        "{err}"
+    );
+}
+
+/// Test that cels within the size limit still can't add up past the total budget.
+#[test]
+fn test_too_much_data() {
+    // Each indexed 8192x8192 cel takes 64 MiB, so the fifth one exceeds 256 MiB
+    let cel = cel(
+        0,
+        (0, 0),
+        255,
+        2,
+        &image(8192, 8192, &vec![0; 1 << 26], true),
+    );
+    let frames = vec![(100, vec![cel]); 5];
+
+    let err = AsepriteFile::parse(file((4, 4), 8, &frames).as_slice()).unwrap_err();
+    assert!(
+        matches!(err, AsepriteError::TooMuchData { frame: 4 }),
+        "{err}"
+    );
+}
+
+/// Test that frames are converted into images.
+#[cfg(feature = "image")]
+#[test]
+fn test_frame_images() -> TestResult {
+    let file = AsepriteFile::parse(sprite().as_slice())?;
+
+    let images = file.frame_images();
+    assert_eq!(images.len(), 2);
+    assert_eq!((images[0].width(), images[0].height()), (4, 4));
+    assert_eq!(images[0].to_rgba8().get_pixel(1, 1).0, BLUE);
+
+    Ok(())
+}
@@ -0,0 +1,305 @@
+mod common;
+use common::*;
+
+use alpacker::{
+    Error, Pack,
+    data::{
+        atlas::{AtlasError, GdxAtlas, TexturePackerAtlas},
+        sprite::{Rect, Rotation, SpriteMeta},
+    },
+    error::ErrorKind,
+    pack::TarPack,
+};
+use rstest::{fixture, rstest};
+use std::path::Path;
+
+const HASH: &str = r#"{
+  "frames": {
+    "hero.png": { "frame": { "x": 0, "y": 0, "w": 8, "h": 8 }, "rotated": false },
+    "sword.png": { "frame": { "x": 8, "y": 0, "w": 4, "h": 8 }, "rotated": true },
+    "coin_0.png": { "frame": { "x": 0, "y": 8, "w": 4, "h": 4 } },
+    "coin_1.png": { "frame": { "x": 4, "y": 8, "w": 4, "h": 4 } }
+  },
+  "meta": {
+    "image": "hash.png",
+    "size": { "w": 16, "h": 16 },
+    "animations": { "coin": ["coin_0.png", "coin_1.png"] }
+  }
+}"#;
+
+const ARRAY: &str = r#"{
+  "frames": [
+    { "filename": "hero.png", "frame": { "x": 0, "y": 0, "w": 8, "h": 8 } },
+    { "filename": "tree.png", "frame": { "x": 8, "y": 0, "w": 8, "h": 16 } }
+  ],
+  "meta": { "image": "array.png" }
+}"#;
+
+const MULTI_0: &str = r#"{
+  "frames": [{ "filename": "a.png", "frame": { "x": 0, "y": 0, "w": 8, "h": 8 } }],
+  "meta": { "image": "multi-0.png", "related_multi_packs": ["multi-1.json"] }
+}"#;
+
+const MULTI_1: &str = r#"{
+  "frames": [{ "filename": "b.png", "frame": { "x": 8, "y": 0, "w": 8, "h": 8 } }],
+  "meta": { "image": "multi-1.png", "related_multi_packs": ["multi-0.json"] }
+}"#;
+
+/// The legacy libGDX layout, with indented region fields.
+const GDX_LEGACY: &str = "
+legacy.png
+size: 32, 16
+format: RGBA8888
+filter: Nearest,Nearest
+repeat: none
+hero
+  rotate: false
+  xy: 0, 0
+  size: 8, 8
+  orig: 8, 8
+  offset: 0, 0
+  index: -1
+walk
+  rotate: true
+  xy: 8, 0
+  size: 4, 8
+  orig: 4, 8
+  offset: 0, 0
+  index: 0
+walk
+  rotate: false
+  xy: 16, 0
+  size: 4, 8
+  orig: 4, 8
+  offset: 0, 0
+  index: 1
+";
+
+/// The compact libGDX layout with two pages.
+const GDX_COMPACT: &str = "compact-0.png
+size:16,16
+filter:Linear,Linear
+hero
+bounds:0,0,8,8
+
+compact-1.png
+size:16,16
+tree
+bounds:4,0,8,16
+rotate:90
+";
+
+#[fixture]
+fn atlas_pack() -> TarPack {
+    pack_of(&[
+        ("atlases/hash.json", HASH.as_bytes()),
+        ("atlases/array.json", ARRAY.as_bytes()),
+        ("atlases/multi-0.json", MULTI_0.as_bytes()),
+        ("atlases/multi-1.json", MULTI_1.as_bytes()),
+        ("atlases/legacy.atlas", GDX_LEGACY.as_bytes()),
+        ("atlases/compact.atlas", GDX_COMPACT.as_bytes()),
+        ("atlases/broken.atlas", b"page.png\nhero\nbounds:0,0,x,8\n"),
+        ("atlases/broken.json", b"{ \"frames\": 5 }"),
+    ])
+}
+
+const fn rect(x: u32, y: u32, width: u32, height: u32) -> Rect {
+    Rect {
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+/// Test that the hash layout keeps the frame order, and rotated frames are turned.
+#[rstest]
+fn test_texture_packer_hash(mut atlas_pack: TarPack) -> TestResult {
+    let atlas = atlas_pack.get::<TexturePackerAtlas>("atlases/hash.json")?;
+
+    let names: Vec<_> = atlas.regions.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, ["hero.png", "sword.png", "coin_0.png", "coin_1.png"]);
+    assert_eq!(atlas.pages[0].size, Some((16, 16)));
+
+    let sword = atlas.region("sword.png").unwrap();
+    assert_eq!(sword.rotated, Rotation::Clockwise);
+    assert_eq!(sword.rect, rect(8, 0, 8, 4));
+
+    assert_eq!(
+        atlas.image_paths("atlases/hash.json"),
+        [Path::new("atlases/hash.png")]
+    );
+    let coin = atlas.tag("coin").unwrap();
+    assert_eq!((coin.from, coin.to), (2, 3));
+    assert_eq!(atlas.frame(3).unwrap().rect, rect(4, 8, 4, 4));
+
+    Ok(())
+}
+
+/// Test the array layout.
+#[rstest]
+fn test_texture_packer_array(mut atlas_pack: TarPack) -> TestResult {
+    let atlas = atlas_pack.get::<TexturePackerAtlas>("atlases/array.json")?;
+
+    assert_eq!(atlas.frame_count(), 2);
+    assert_eq!(atlas.region("tree.png").unwrap().rect, rect(8, 0, 8, 16));
+    assert!(atlas.tags().is_empty());
+
+    Ok(())
+}
+
+/// Test that related multi-pack files are loaded as further pages.
+#[rstest]
+fn test_texture_packer_multi_pack(mut atlas_pack: TarPack) -> TestResult {
+    let atlas = atlas_pack.get::<TexturePackerAtlas>("atlases/multi-0.json")?;
+
+    assert_eq!(atlas.pages.len(), 2);
+    assert_eq!(atlas.frame(1).unwrap().page, 1);
+    let paths = atlas.image_paths("atlases/multi-0.json");
+    assert_eq!(paths[1], Path::new("atlases/multi-1.png"));
+
+    Ok(())
+}
+
+/// Test the legacy libGDX layout, with an animation from indexed regions.
+#[rstest]
+fn test_gdx_legacy(mut atlas_pack: TarPack) -> TestResult {
+    let atlas = atlas_pack.get::<GdxAtlas>("atlases/legacy.atlas")?;
+
+    assert_eq!(atlas.pages.len(), 1);
+    assert_eq!(atlas.pages[0].size, Some((32, 16)));
+    assert_eq!(atlas.frame_count(), 3);
+
+    let hero = &atlas.regions[0];
+    assert_eq!((hero.rect, hero.index), (rect(0, 0, 8, 8), None));
+    // Rotated regions list the size of the sprite
+    assert_eq!(atlas.regions[1].rect, rect(8, 0, 8, 4));
+    assert_eq!(atlas.regions[2].index, Some(1));
+
+    assert_eq!(atlas.tags().len(), 1);
+    let walk = atlas.tag("walk").unwrap();
+    assert_eq!((walk.from, walk.to), (1, 2));
+
+    Ok(())
+}
+
+/// Test the compact libGDX layout with several pages.
+#[rstest]
+fn test_gdx_compact(mut atlas_pack: TarPack) -> TestResult {
+    let atlas = atlas_pack.get::<GdxAtlas>("atlases/compact.atlas")?;
+
+    assert_eq!(atlas.pages.len(), 2);
+    let tree = atlas.region("tree").unwrap();
+    assert_eq!(
+        (tree.page, tree.rect, tree.rotated),
+        (1, rect(4, 0, 16, 8), Rotation::CounterClockwise)
+    );
+    assert_eq!(
+        atlas.image_paths("atlases/compact.atlas")[1],
+        Path::new("atlases/compact-1.png")
+    );
+
+    Ok(())
+}
+
+/// Test that syntax errors report the line and convert into [Error] with the path.
+#[rstest]
+fn test_errors(mut atlas_pack: TarPack) {
+    let err = atlas_pack
+        .get::<GdxAtlas>("atlases/broken.atlas")
+        .unwrap_err();
+    assert!(matches!(err, AtlasError::Syntax { line: 3, .. }), "{err}");
+
+    let err: Error = atlas_pack
+        .load_asset::<GdxAtlas>("atlases/broken.atlas")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Asset);
+    assert_eq!(err.path(), Some("atlases/broken.atlas".as_ref()));
+
+    let err = atlas_pack
+        .get::<TexturePackerAtlas>("atlases/broken.json")
+        .unwrap_err();
+    assert!(matches!(err, AtlasError::Json { .. }), "{err}");
+
+    let err: Error = atlas_pack
+        .load_asset::<TexturePackerAtlas>("atlases/missing.json")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+/// Test that sprites load every page, and frames are cut out of their own page.
+#[cfg(feature = "image")]
+#[rstest]
+fn test_multi_page_sprite() -> TestResult {
+    use alpacker::data::sprite::Sprite;
+    use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+    use std::io::Cursor;
+
+    // Each page is filled with its index
+    let page = |index: u8| -> Result<Vec<u8>, image::ImageError> {
+        let image = RgbaImage::from_pixel(16, 8, Rgba([index, 0, 0, 255]));
+        let mut png = Vec::new();
+        DynamicImage::ImageRgba8(image).write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+        Ok(png)
+    };
+    let (png_0, png_1) = (page(0)?, page(1)?);
+
+    let mut pack = pack_of(&[
+        ("atlases/multi-0.json", MULTI_0.as_bytes()),
+        ("atlases/multi-1.json", MULTI_1.as_bytes()),
+        ("atlases/multi-0.png", &png_0),
+        ("atlases/multi-1.png", &png_1),
+    ]);
+    let sprite = pack.get::<Sprite<DynamicImage, TexturePackerAtlas>>("atlases/multi-0.json")?;
+
+    assert_eq!(sprite.pages().count(), 2);
+    assert!(sprite.image.is_some());
+    let frame = sprite.frame_image(1).unwrap();
+    assert_eq!((frame.width(), frame.height()), (8, 8));
+    assert_eq!(frame.to_rgba8().get_pixel(0, 0).0, [1, 0, 0, 255]);
+
+    Ok(())
+}
+
+/// Test that rotated frames are cut out upright, whichever way they were turned.
+#[cfg(feature = "image")]
+#[rstest]
+fn test_rotated_frame_image() -> TestResult {
+    use alpacker::data::sprite::Sprite;
+    use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+    use std::io::Cursor;
+
+    // The top-left pixel of the upright 4x8 sprite is marked
+    let page = |x: u32, y: u32| -> Result<Vec<u8>, image::ImageError> {
+        let mut image = RgbaImage::new(16, 16);
+        image.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+        let mut png = Vec::new();
+        DynamicImage::ImageRgba8(image).write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+        Ok(png)
+    };
+    // Turned clockwise, the top-left corner of the sprite is the top-right one of the region
+    let (clockwise, counter_clockwise) = (page(15, 0)?, page(8, 3)?);
+
+    let mut pack = pack_of(&[
+        ("atlases/hash.json", HASH.as_bytes()),
+        ("atlases/hash.png", &clockwise),
+        (
+            "atlases/gdx.atlas",
+            b"gdx.png\nsize:16,16\nsword\nbounds:8,0,4,8\nrotate:90\n",
+        ),
+        ("atlases/gdx.png", &counter_clockwise),
+    ]);
+
+    let sprite = pack.get::<Sprite<DynamicImage, TexturePackerAtlas>>("atlases/hash.json")?;
+    let index = sprite.meta.region_index("sword.png").unwrap();
+    let sword = sprite.frame_image(index).unwrap().to_rgba8();
+    assert_eq!(sword.dimensions(), (4, 8));
+    assert_eq!(sword.get_pixel(0, 0).0, [255, 0, 0, 255]);
+
+    let sprite = pack.get::<Sprite<DynamicImage, GdxAtlas>>("atlases/gdx.atlas")?;
+    let sword = sprite.frame_image(0).unwrap().to_rgba8();
+    assert_eq!(sword.dimensions(), (4, 8));
+    assert_eq!(sword.get_pixel(0, 0).0, [255, 0, 0, 255]);
+
+    Ok(())
+}
@@ -0,0 +1,167 @@
+mod common;
+use common::*;
+
+use alpacker::{
+    Error, Pack,
+    data::audio::{AudioError, AudioFormat, PcmAudio, Samples},
+    error::ErrorKind,
+    pack::TarPack,
+};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use rstest::{fixture, rstest};
+use std::{io::Cursor, path::Path, time::Duration};
+
+/// Writes a WAV file with hound.
+fn wav<S: hound::Sample + Copy>(spec: WavSpec, samples: &[S]) -> Vec<u8> {
+    let mut data = Cursor::new(Vec::new());
+    let mut writer = WavWriter::new(&mut data, spec).unwrap();
+    for &sample in samples {
+        writer.write_sample(sample).unwrap();
+    }
+    writer.finalize().unwrap();
+    data.into_inner()
+}
+
+#[fixture]
+fn audio_pack() -> TarPack {
+    let spec = |channels, bits_per_sample, sample_format| WavSpec {
+        channels,
+        sample_rate: 8000,
+        bits_per_sample,
+        sample_format,
+    };
+
+    let stereo = wav(spec(2, 16, SampleFormat::Int), &[0i16, 100, -200, 300]);
+    let byte = wav(spec(1, 8, SampleFormat::Int), &[-128i8, 0, 127]);
+    let float = wav(spec(1, 32, SampleFormat::Float), &[0.5f32, -0.25]);
+    let flac = flac(44100, &[&[1, 2, 3], &[-1, -2, -3]]);
+
+    pack_of(&[
+        ("sounds/stereo.wav", &stereo),
+        ("sounds/byte.wav", &byte),
+        ("sounds/float.wav", &float),
+        ("sounds/wav.bin", &stereo),
+        ("music/theme.flac", &flac),
+        ("music/broken.ogg", b"OggS and nothing else"),
+        ("music/broken.flac", b"not audio"),
+        ("music/theme.mp3", b"not audio"),
+    ])
+}
+
+/// Test that formats are detected by magic bytes and extensions.
+#[test]
+fn test_detect() {
+    assert_eq!(
+        AudioFormat::from_magic(b"RIFF\0\0\0\0WAVEfmt "),
+        Some(AudioFormat::Wav)
+    );
+    assert_eq!(
+        AudioFormat::from_magic(b"OggS\0\x02"),
+        Some(AudioFormat::Vorbis)
+    );
+    assert_eq!(
+        AudioFormat::from_magic(b"fLaC\0\0\0\x22"),
+        Some(AudioFormat::Flac)
+    );
+    assert_eq!(AudioFormat::from_magic(b"RIFF\0\0\0\0AVI "), None);
+    assert_eq!(AudioFormat::from_magic(b"fL"), None);
+
+    assert_eq!(
+        AudioFormat::from_extension("a/b.WAV"),
+        Some(AudioFormat::Wav)
+    );
+    assert_eq!(
+        AudioFormat::from_extension("a/b.ogg"),
+        Some(AudioFormat::Vorbis)
+    );
+    assert_eq!(
+        AudioFormat::from_extension("a/b.flac"),
+        Some(AudioFormat::Flac)
+    );
+    assert_eq!(AudioFormat::from_extension("a/b.mp3"), None);
+}
+
+/// Test decoding WAV files of different sample formats.
+#[rstest]
+fn test_wav(mut audio_pack: TarPack) -> TestResult {
+    let audio = audio_pack.get::<PcmAudio>("sounds/stereo.wav")?;
+    assert_eq!((audio.sample_rate, audio.channels), (8000, 2));
+    assert_eq!(audio.samples, Samples::I16(vec![0, 100, -200, 300]));
+    assert_eq!(audio.frames(), 2);
+    assert_eq!(audio.duration(), Duration::from_micros(250));
+
+    // 8-bit samples are scaled to the range of `i16`
+    let audio = audio_pack.get::<PcmAudio>("sounds/byte.wav")?;
+    assert_eq!(audio.samples, Samples::I16(vec![-32768, 0, 32512]));
+
+    let audio = audio_pack.get::<PcmAudio>("sounds/float.wav")?;
+    assert_eq!(audio.samples.clone().into_f32(), vec![0.5, -0.25]);
+    assert_eq!(audio.samples.into_i16(), vec![16384, -8192]);
+
+    // Conversions use the same scale both ways, and clip out-of-range samples
+    let samples = vec![i16::MIN, -8192, 0, 16384, i16::MAX];
+    let round_trip = Samples::F32(Samples::I16(samples.clone()).into_f32()).into_i16();
+    assert_eq!(round_trip, samples);
+    let clipped = Samples::F32(vec![1.0, 2.0, -2.0]).into_i16();
+    assert_eq!(clipped, vec![i16::MAX, i16::MAX, i16::MIN]);
+
+    // Magic bytes take priority over the extension
+    let audio = audio_pack.get::<PcmAudio>("sounds/wav.bin")?;
+    assert_eq!(audio.channels, 2);
+
+    Ok(())
+}
+
+/// Test decoding FLAC files.
+#[rstest]
+fn test_flac(mut audio_pack: TarPack) -> TestResult {
+    let audio = audio_pack.get::<PcmAudio>("music/theme.flac")?;
+    assert_eq!((audio.sample_rate, audio.channels), (44100, 2));
+    assert_eq!(audio.samples, Samples::I16(vec![1, -1, 2, -2, 3, -3]));
+    assert_eq!(audio.frames(), 3);
+
+    Ok(())
+}
+
+/// Test that invalid and unknown files report the format and the path.
+#[rstest]
+fn test_errors(mut audio_pack: TarPack) {
+    let err = audio_pack.get::<PcmAudio>("music/broken.ogg").unwrap_err();
+    assert!(
+        matches!(
+            err,
+            AudioError::Decode {
+                format: AudioFormat::Vorbis,
+                ..
+            }
+        ),
+        "{err}"
+    );
+
+    // Falls back to the extension if the magic bytes are unknown
+    let err = audio_pack.get::<PcmAudio>("music/broken.flac").unwrap_err();
+    assert!(
+        matches!(
+            err,
+            AudioError::Decode {
+                format: AudioFormat::Flac,
+                ..
+            }
+        ),
+        "{err}"
+    );
+
+    let err = audio_pack.get::<PcmAudio>("music/theme.mp3").unwrap_err();
+    assert!(matches!(err, AudioError::UnknownFormat(_)), "{err}");
+
+    let err: Error = audio_pack
+        .load_asset::<PcmAudio>("music/broken.ogg")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Asset);
+    assert_eq!(err.path(), Some(Path::new("music/broken.ogg")));
+
+    let err: Error = audio_pack
+        .load_asset::<PcmAudio>("music/missing.ogg")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
@@ -0,0 +1,154 @@
+mod common;
+use common::*;
+
+use std::{
+    io,
+    path::Path,
+    sync::{Arc, Barrier},
+};
+
+use alpacker::{Pack, Raw, pack::TarZstPack};
+
+/// Test that shared packs are loaded once and released when no longer used.
+#[test]
+fn test_shared_pack() -> TestResult {
+    // A separate instance, so other tests don't share the cache
+    let assets = load_samples()?;
+
+    let ui = assets.shared_pack::<TarZstPack>("test")?;
+    let hud = assets.shared_pack::<TarZstPack>("test")?;
+    assert!(
+        Arc::ptr_eq(&ui, &hud),
+        "Expected the cached pack to be reused"
+    );
+
+    let loaded = assets.loaded_packs();
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].name, "test");
+
+    let data = ui.lock().unwrap().get::<String>("myfile.txt")?;
+    assert_eq!(data, "Hello, World!\n");
+
+    // Reloading updates the pack in place
+    let reloaded = assets.reload::<TarZstPack>("test")?;
+    assert!(Arc::ptr_eq(&reloaded, &ui));
+
+    drop((ui, hud, reloaded));
+    assert!(
+        assets.loaded_packs().is_empty(),
+        "Expected the cache not to keep packs alive"
+    );
+
+    Ok(())
+}
+
+/// Test that unloading makes the next request load a fresh pack.
+#[test]
+fn test_unload() -> TestResult {
+    let assets = load_samples()?;
+
+    let old = assets.shared_pack::<TarZstPack>("test")?;
+    assert!(assets.unload("test"), "Expected the pack to be in use");
+    assert!(!assets.unload("test"), "Expected the pack to be unloaded");
+
+    let new = assets.shared_pack::<TarZstPack>("test")?;
+    assert!(
+        !Arc::ptr_eq(&old, &new),
+        "Expected the pack to be loaded again"
+    );
+
+    assert!(assets.shared_pack::<TarZstPack>("invalid").is_err());
+    assert_eq!(assets.loaded_packs().len(), 1);
+
+    Ok(())
+}
+
+/// Signaled once [GatedPack] starts loading.
+static STARTED: Barrier = Barrier::new(2);
+/// Lets [GatedPack] finish loading.
+static RELEASED: Barrier = Barrier::new(2);
+
+/// A pack that waits for the test while it loads.
+struct GatedPack(TarZstPack);
+
+impl Pack for GatedPack {
+    fn load(read: impl io::Read) -> io::Result<Self> {
+        STARTED.wait();
+        RELEASED.wait();
+        TarZstPack::load(read).map(Self)
+    }
+
+    fn get_raw<'p>(&mut self, path: &'p Path) -> io::Result<Raw<'p, impl io::Read + io::Seek>> {
+        self.0.get_raw(path)
+    }
+
+    fn exists(&self, path: impl AsRef<Path>) -> bool {
+        self.0.exists(path)
+    }
+}
+
+/// Test that unloading a pack while it loads keeps it cached.
+#[test]
+fn test_unload_while_loading() -> TestResult {
+    let assets = load_samples()?;
+
+    let pack = std::thread::scope(|scope| {
+        let thread = scope.spawn(|| assets.shared_pack::<GatedPack>("test").unwrap());
+        STARTED.wait();
+        assert!(
+            !assets.unload("test"),
+            "Expected the pack not to be in use yet"
+        );
+        RELEASED.wait();
+        thread.join().unwrap()
+    });
+
+    assert_eq!(assets.loaded_packs().len(), 1);
+    assert!(Arc::ptr_eq(
+        &pack,
+        &assets.shared_pack::<GatedPack>("test")?
+    ));
+
+    Ok(())
+}
+
+/// Test that concurrent requests for the same pack share a single load.
+#[test]
+fn test_concurrent_load() -> TestResult {
+    let assets = load_samples()?;
+
+    let packs = std::thread::scope(|scope| {
+        let threads = (0..4)
+            .map(|_| scope.spawn(|| assets.shared_pack::<TarZstPack>("test").unwrap()))
+            .collect::<Vec<_>>();
+        threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    assert!(
+        packs.iter().all(|pack| Arc::ptr_eq(pack, &packs[0])),
+        "Expected every thread to get the same pack"
+    );
+    assert_eq!(assets.loaded_packs().len(), 1);
+
+    Ok(())
+}
+
+/// Test that listing packs doesn't wait for a pack that is locked, even by the caller.
+#[test]
+fn test_loaded_packs_locked() -> TestResult {
+    let assets = load_samples()?;
+    let pack = assets.shared_pack::<TarZstPack>("test")?;
+
+    let unlocked = assets.loaded_packs();
+    let guard = pack.lock().unwrap();
+    let locked = assets.loaded_packs();
+    drop(guard);
+
+    // The statistics from loading are reported instead
+    assert!(locked[0].stats.is_some());
+    assert_eq!(locked, unlocked);
+
+    Ok(())
+}
@@ -0,0 +1,137 @@
+mod common;
+use common::*;
+
+use std::path::PathBuf;
+
+use alpacker::{
+    ChecksumPolicy, Error, LoadOptions, MANIFEST_FILE, Pack, PackManifest,
+    checksum::{ChecksumError, checksum},
+    error::ErrorKind,
+    pack::{Diagnostics, Skipped, TarPack},
+};
+
+/// Builds a pack whose manifest records a wrong checksum for `bad.txt`.
+fn corrupt_pack() -> Vec<u8> {
+    let mut manifest = PackManifest::default();
+    manifest
+        .checksums
+        .insert("good.txt".into(), checksum(b"good"));
+    manifest
+        .checksums
+        .insert("dir/bad.txt".into(), checksum(b"original"));
+    let manifest = serde_json::to_vec(&manifest).unwrap();
+
+    tar_archive(&[
+        (MANIFEST_FILE, &manifest),
+        ("good.txt", b"good"),
+        ("dir/bad.txt", b"flipped"),
+    ])
+}
+
+fn options(checksums: ChecksumPolicy) -> LoadOptions {
+    LoadOptions {
+        checksums,
+        ..Default::default()
+    }
+}
+
+/// Test that corrupt entries fail when they're read.
+#[test]
+fn test_verify_on_read() -> TestResult {
+    let mut pack = <TarPack>::load(corrupt_pack().as_slice())?;
+    assert_eq!(pack.get::<String>("good.txt")?, "good");
+
+    let err = pack.get::<String>("dir/bad.txt").unwrap_err();
+    assert_eq!(
+        inner_error::<ChecksumError>(&err).map(|err| &err.path),
+        Some(&PathBuf::from("dir/bad.txt"))
+    );
+    assert_eq!(Error::from(err).kind(), ErrorKind::Corrupt);
+
+    assert!(pack.verify().is_err(), "Eager verification must fail too");
+
+    Ok(())
+}
+
+/// Test that corrupt entries are skipped when verified on load.
+#[test]
+fn test_verify_on_load() -> TestResult {
+    let data = corrupt_pack();
+    let mut pack = <TarPack>::load_with(data.as_slice(), &options(ChecksumPolicy::OnLoad))?;
+
+    assert!(!pack.exists("dir/bad.txt"));
+    assert!(
+        pack.skipped()
+            .contains(&Skipped::Corrupt(PathBuf::from("dir/bad.txt")))
+    );
+    assert_eq!(pack.get::<String>("good.txt")?, "good");
+    pack.verify()?;
+
+    Ok(())
+}
+
+/// Test that checksums can be ignored.
+#[test]
+fn test_ignore_checksums() -> TestResult {
+    let data = corrupt_pack();
+    let mut pack = <TarPack>::load_with(data.as_slice(), &options(ChecksumPolicy::Ignore))?;
+
+    assert_eq!(pack.get::<String>("dir/bad.txt")?, "flipped");
+    pack.verify()?;
+
+    Ok(())
+}
+
+/// Builds a pack with corrupt entries `c.txt`, `a.txt` and `b.txt` and a link to `a.txt`.
+fn corrupt_links_pack() -> Vec<u8> {
+    let mut manifest = PackManifest::default();
+    for path in ["a.txt", "b.txt", "c.txt"] {
+        manifest
+            .checksums
+            .insert(path.into(), checksum(b"original"));
+    }
+    let manifest = serde_json::to_vec(&manifest).unwrap();
+
+    let mut tar = tar::Builder::new(tar_archive(&[
+        (MANIFEST_FILE, &manifest),
+        ("c.txt", b"flipped"),
+        ("a.txt", b"flipped"),
+        ("b.txt", b"flipped"),
+    ]));
+    // Drop the end-of-archive marker written by `tar_archive`.
+    let data = tar.get_mut();
+    data.truncate(data.len() - 1024);
+
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Symlink);
+    header.set_size(0);
+    tar.append_link(&mut header, "alias.txt", "a.txt").unwrap();
+    tar.into_inner().unwrap()
+}
+
+/// Test that links are verified through their targets, and corrupt entries are reported in order.
+#[test]
+fn test_verify_links() -> TestResult {
+    let data = corrupt_links_pack();
+    let options = LoadOptions {
+        resolve_links: true,
+        ..Default::default()
+    };
+    let mut pack = <TarPack>::load_with(data.as_slice(), &options)?;
+
+    let err = pack.get::<String>("alias.txt").unwrap_err();
+    assert_eq!(Error::from(err).kind(), ErrorKind::Corrupt);
+
+    let options = LoadOptions {
+        checksums: ChecksumPolicy::OnLoad,
+        ..options
+    };
+    let pack = <TarPack>::load_with(data.as_slice(), &options)?;
+    let corrupt = pack.skipped().iter().filter_map(|skipped| match skipped {
+        Skipped::Corrupt(path) => Some(path.to_str().unwrap()),
+        _ => None,
+    });
+    assert_eq!(corrupt.collect::<Vec<_>>(), ["a.txt", "b.txt", "c.txt"]);
+
+    Ok(())
+}
@@ -1,8 +1,11 @@
 #![allow(unused)]
 
-use std::error::Error;
+use std::{collections::BTreeMap, error::Error, io};
 
-use alpacker::{Assets, pack::TarZstPack};
+use alpacker::{
+    Assets, Pack,
+    pack::{TarPack, TarZstPack},
+};
 use rstest::fixture;
 
 pub type TestResult = Result<(), Box<dyn Error>>;
@@ -11,12 +14,68 @@ pub const SAMPLES_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/sample
 
 pub const IMAGE: &str = "cat.png";
 
+/// The key the samples are signed with when the `sign` feature is enabled.
+#[cfg(feature = "sign")]
+pub const SIGNING_KEY: [u8; 32] = [7; 32];
+
+/// Loads the manifest of the test samples directory.
+#[cfg(not(feature = "sign"))]
+pub fn load_samples() -> Result<Assets, Box<dyn Error>> {
+    Ok(Assets::load_from_dir(SAMPLES_DIR)?)
+}
+
+/// Loads the manifest of the test samples directory.
+///
+/// Signed builds only load signed manifests, so the samples are signed into a temporary directory once.
+#[cfg(feature = "sign")]
+pub fn load_samples() -> Result<Assets, Box<dyn Error>> {
+    use alpacker::sign::SigningKey;
+    use std::{path::PathBuf, sync::OnceLock};
+
+    static SIGNED_DIR: OnceLock<PathBuf> = OnceLock::new();
+    let dir = SIGNED_DIR.get_or_init(|| sign_samples().unwrap());
+
+    let key = SigningKey::from_bytes(&SIGNING_KEY).verifying_key();
+    Ok(Assets::load_from_dir_signed(dir, key)?)
+}
+
+/// Copies the samples into a temporary directory with a signed manifest.
+#[cfg(feature = "sign")]
+fn sign_samples() -> Result<std::path::PathBuf, Box<dyn Error>> {
+    use alpacker::{
+        MANIFEST_FILE, PackMeta,
+        sign::{Hashed, SIGNATURE_FILE, SigningKey},
+    };
+    use std::{collections::HashMap, fs, path::Path, process};
+
+    let dir = std::env::temp_dir().join(format!("alpacker-samples-{}", process::id()));
+    fs::create_dir_all(&dir)?;
+
+    let (mut packs, mut hashes) = (HashMap::new(), BTreeMap::new());
+    for (name, file) in [("test", "test.tar.zst"), ("invalid", "invalid.tar.zst")] {
+        let data = fs::read(Path::new(SAMPLES_DIR).join(file))?;
+        let mut hashed = Hashed::new(io::sink());
+        io::Write::write_all(&mut hashed, &data)?;
+        hashes.insert(name.to_string(), hashed.finish());
+
+        fs::write(dir.join(file), data)?;
+        packs.insert(name.to_string(), PackMeta::new(file));
+    }
+
+    let key = SigningKey::from_bytes(&SIGNING_KEY);
+    let signed = Assets::new("./", packs).sign(hashes, &key)?;
+    fs::write(dir.join(MANIFEST_FILE), signed.manifest)?;
+    fs::write(dir.join(SIGNATURE_FILE), signed.signature)?;
+
+    Ok(dir)
+}
+
 /// Fixture that loads assets from the test samples directory.
 /// This runs once before all tests due to the `#[once]` attribute.
 #[fixture]
 #[once]
 pub fn assets() -> Assets {
-    Assets::load_from_dir(SAMPLES_DIR).unwrap()
+    load_samples().unwrap()
 }
 
 /// Fixture that loads the "test" asset pack from the available assets.
@@ -25,3 +84,87 @@ pub fn assets() -> Assets {
 pub fn pack(assets: &Assets) -> TarZstPack {
     assets.load_pack("test").unwrap()
 }
+
+/// Builds an in-memory TAR archive from `(path, contents)` pairs.
+///
+/// Paths are written as they are, so unsafe paths like `../file` can be tested.
+pub fn tar_archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut tar = tar::Builder::new(Vec::new());
+
+    for (path, data) in files {
+        let mut header = tar::Header::new_gnu();
+        header.as_old_mut().name[..path.len()].copy_from_slice(path.as_bytes());
+        header.set_mode(0o644);
+        header.set_size(data.len() as u64);
+        header.set_cksum();
+        tar.append(&header, *data).unwrap();
+    }
+
+    tar.into_inner().unwrap()
+}
+
+/// Loads a [TarPack] from `(path, contents)` pairs. See [tar_archive].
+pub fn pack_of(files: &[(&str, &[u8])]) -> TarPack {
+    TarPack::load(tar_archive(files).as_slice()).unwrap()
+}
+
+fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0, |crc, &byte| {
+        (0..8).fold(crc ^ byte, |crc, _| match crc & 0x80 {
+            0 => crc << 1,
+            _ => (crc << 1) ^ 0x07,
+        })
+    })
+}
+
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0, |crc, &byte| {
+        (0..8).fold(crc ^ (u16::from(byte) << 8), |crc, _| match crc & 0x8000 {
+            0 => crc << 1,
+            _ => (crc << 1) ^ 0x8005,
+        })
+    })
+}
+
+/// Builds a 16-bit FLAC file of a single frame with verbatim subframes.
+pub fn flac(sample_rate: u32, channels: &[&[i16]]) -> Vec<u8> {
+    let block = channels[0].len();
+    let mut data = b"fLaC".to_vec();
+
+    // The last metadata block, STREAMINFO
+    data.extend([0x80, 0, 0, 34]);
+    // A frame shorter than the block size is the last one
+    data.extend(4096u16.to_be_bytes());
+    data.extend(4096u16.to_be_bytes());
+    data.extend([0; 6]);
+    let info = (u64::from(sample_rate) << 44)
+        | (((channels.len() - 1) as u64) << 41)
+        | (15 << 36)
+        | block as u64;
+    data.extend(info.to_be_bytes());
+    data.extend([0; 16]);
+
+    // Block size from the end of the header, sample rate from STREAMINFO, 16 bits
+    let mut frame = vec![
+        0xFF,
+        0xF8,
+        0x60,
+        ((channels.len() as u8 - 1) << 4) | 0x08,
+        0,
+    ];
+    frame.push((block - 1) as u8);
+    frame.push(crc8(&frame));
+    for samples in channels {
+        frame.push(0x02);
+        frame.extend(samples.iter().flat_map(|s| s.to_be_bytes()));
+    }
+    frame.extend(crc16(&frame).to_be_bytes());
+
+    data.extend(frame);
+    data
+}
+
+/// Extracts the typed error from an [io::Error] returned by pack loading.
+pub fn inner_error<E: Error + 'static>(err: &io::Error) -> Option<&E> {
+    err.get_ref()?.downcast_ref::<E>()
+}
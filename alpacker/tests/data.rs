@@ -0,0 +1,108 @@
+mod common;
+use common::*;
+
+use alpacker::{
+    Error, Pack,
+    data::serialized::{Data, DataError, Json, Location, Ron, Toml, Yaml},
+    error::ErrorKind,
+    pack::TarPack,
+};
+use rstest::{fixture, rstest};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Level {
+    name: String,
+    size: [u32; 2],
+}
+
+const LEVEL: &str = "Intro";
+
+/// Fixture with the same level in every format, and a few broken files.
+#[fixture]
+fn data_pack() -> TarPack {
+    pack_of(&[
+        ("level.json", br#"{ "name": "Intro", "size": [16, 9] }"#),
+        ("level.toml", b"name = \"Intro\"\nsize = [16, 9]\n"),
+        ("level.ron", b"(name: \"Intro\", size: (16, 9))"),
+        ("level.yaml", b"name: Intro\nsize: [16, 9]\n"),
+        (
+            "broken.json",
+            b"{\n  \"name\": \"Intro\",\n  \"size\": oops\n}",
+        ),
+        ("broken.toml", b"name = \"Intro\"\nsize = [16,\n"),
+        ("level.txt", b"Intro"),
+    ])
+}
+
+fn check(level: Level) {
+    assert_eq!(
+        level,
+        Level {
+            name: LEVEL.into(),
+            size: [16, 9]
+        }
+    );
+}
+
+/// Test that every format loads the same value.
+#[rstest]
+fn test_formats(mut data_pack: TarPack) -> TestResult {
+    check(data_pack.get::<Json<Level>>("level.json")?.into_inner());
+    check(data_pack.get::<Toml<Level>>("level.toml")?.into_inner());
+    check(data_pack.get::<Ron<Level>>("level.ron")?.into_inner());
+    check(data_pack.get::<Yaml<Level>>("level.yaml")?.into_inner());
+
+    Ok(())
+}
+
+/// Test that [Data] picks the format from the file extension.
+#[rstest]
+#[case("level.json")]
+#[case("level.toml")]
+#[case("level.ron")]
+#[case("level.yaml")]
+fn test_data(mut data_pack: TarPack, #[case] path: &str) -> TestResult {
+    let Data(level) = data_pack.get::<Data<Level>>(path)?;
+    check(level);
+
+    Ok(())
+}
+
+/// Test that unknown extensions are rejected.
+#[rstest]
+fn test_unknown_format(mut data_pack: TarPack) {
+    let err = data_pack.get::<Data<Level>>("level.txt").unwrap_err();
+    assert!(matches!(err, DataError::UnknownFormat(_)), "{err}");
+}
+
+/// Test that parse errors report the path, line and column.
+#[rstest]
+#[case("broken.json", 3)]
+#[case("broken.toml", 2)]
+fn test_parse_location(mut data_pack: TarPack, #[case] path: &str, #[case] line: usize) {
+    let err = data_pack.get::<Data<Level>>(path).unwrap_err();
+    let Some(Location { line: found, .. }) = err.location() else {
+        panic!("Expected a location, got {err}");
+    };
+    assert_eq!(found, line);
+
+    let message = err.to_string();
+    assert!(message.contains(path), "{message}");
+    assert!(message.contains(&format!("line {line}")), "{message}");
+}
+
+/// Test that data errors convert into [Error] with the entry path.
+#[rstest]
+fn test_into_error(mut data_pack: TarPack) {
+    let err: Error = data_pack
+        .load_asset::<Json<Level>>("broken.json")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Asset);
+    assert_eq!(err.path(), Some("broken.json".as_ref()));
+
+    let err: Error = data_pack
+        .load_asset::<Json<Level>>("missing.json")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
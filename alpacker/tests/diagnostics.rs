@@ -0,0 +1,120 @@
+mod common;
+use common::*;
+
+use std::{io, path::PathBuf};
+
+use alpacker::{
+    DuplicatePolicy, LoadOptions, Pack,
+    limits::LoadLimits,
+    pack::{Diagnostics, Skipped, TarPack, TarZstPack},
+};
+use rstest::rstest;
+
+/// Builds an archive with a directory, a symlink, a hard link and a FIFO next to a file.
+fn special_entries() -> Vec<u8> {
+    let mut tar = tar::Builder::new(Vec::new());
+
+    let mut append = |path: &str, entry_type: tar::EntryType, link: Option<&str>| {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(entry_type);
+        header.set_size(0);
+        header.set_mode(0o644);
+        if let Some(link) = link {
+            header.set_link_name(link).unwrap();
+        }
+        tar.append_data(&mut header, path, io::empty()).unwrap();
+    };
+
+    append("dir/", tar::EntryType::Directory, None);
+    append("file.txt", tar::EntryType::Regular, None);
+    append("link.txt", tar::EntryType::Symlink, Some("file.txt"));
+    append("hard.txt", tar::EntryType::Link, Some("file.txt"));
+    append("pipe", tar::EntryType::Fifo, None);
+
+    tar.into_inner().unwrap()
+}
+
+/// Test that every skipped entry type is reported.
+#[test]
+fn test_skipped_kinds() -> TestResult {
+    let options = LoadOptions {
+        resolve_links: false,
+        ..Default::default()
+    };
+    let pack = <TarPack>::load_with(special_entries().as_slice(), &options)?;
+
+    assert_eq!(
+        pack.skipped(),
+        [
+            Skipped::NotAFile(PathBuf::from("dir/")),
+            Skipped::Symlink(PathBuf::from("link.txt")),
+            Skipped::HardLink(PathBuf::from("hard.txt")),
+            Skipped::Unsupported(PathBuf::from("pipe")),
+        ]
+    );
+    assert!(!pack.is_clean(), "Links must make the pack dirty");
+
+    Ok(())
+}
+
+/// Test every [DuplicatePolicy].
+#[rstest]
+#[case(DuplicatePolicy::FirstWins, "first")]
+#[case(DuplicatePolicy::LastWins, "last")]
+fn test_duplicates(#[case] duplicates: DuplicatePolicy, #[case] expected: &str) -> TestResult {
+    let data = tar_archive(&[("a.txt", b"first"), ("./a.txt", b"last")]);
+    let options = LoadOptions {
+        duplicates,
+        ..Default::default()
+    };
+
+    let mut pack = <TarPack>::load_with(data.as_slice(), &options)?;
+    assert_eq!(pack.get::<String>("a.txt")?, expected);
+    assert!(matches!(pack.skipped()[..], [Skipped::Duplicate(_)]));
+
+    let options = LoadOptions {
+        duplicates: DuplicatePolicy::Error,
+        ..Default::default()
+    };
+    let err = <TarPack>::load_with(data.as_slice(), &options).unwrap_err();
+    assert_eq!(
+        inner_error::<Skipped>(&err),
+        Some(&Skipped::Duplicate(PathBuf::from("./a.txt")))
+    );
+
+    Ok(())
+}
+
+/// Test that oversized entries can be skipped instead of failing the load.
+#[test]
+fn test_skip_oversized() -> TestResult {
+    let data = tar_archive(&[("big.bin", &[0; 64]), ("small.txt", b"ok")]);
+    let options = LoadOptions {
+        limits: LoadLimits {
+            max_entry_size: 16,
+            ..Default::default()
+        },
+        skip_oversized: true,
+        ..Default::default()
+    };
+
+    let mut pack = <TarPack>::load_with(data.as_slice(), &options)?;
+    assert!(!pack.exists("big.bin"));
+    assert_eq!(pack.get::<String>("small.txt")?, "ok");
+    assert_eq!(
+        pack.skipped()[..],
+        [Skipped::Oversize {
+            path: PathBuf::from("big.bin"),
+            size: 64
+        }]
+    );
+
+    Ok(())
+}
+
+/// Test that diagnostics are reachable through an encoded pack.
+#[rstest]
+fn test_encoded_diagnostics(pack: TarZstPack) {
+    assert!(pack.skipped().is_empty());
+    assert!(pack.is_clean());
+}
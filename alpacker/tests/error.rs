@@ -0,0 +1,84 @@
+mod common;
+use common::*;
+
+use std::{io, path::Path};
+
+use alpacker::{
+    Assets, Error, LoadOptions, Pack, PackLoadError,
+    error::ErrorKind,
+    limits::{LimitError, LoadLimits},
+    pack::{TarPack, TarZstPack},
+};
+use rstest::rstest;
+
+/// Test that pack loading errors carry the pack name and type.
+#[rstest]
+fn test_pack_context(assets: &Assets) {
+    let err = assets
+        .load_pack::<TarZstPack>("non_existent_pack")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NoSuchPack);
+    assert_eq!(err.pack(), Some("non_existent_pack"));
+    assert!(matches!(
+        err.downcast_ref(),
+        Some(PackLoadError::NoSuchPack(name)) if name == "non_existent_pack"
+    ));
+
+    let err = assets.load_pack::<TarZstPack>("invalid").unwrap_err();
+    assert_eq!(err.pack(), Some("invalid"));
+    assert_eq!(err.context().backend, Some("tar"));
+    assert_eq!(err.context().codec, Some("zstd"));
+    assert!(err.to_string().contains("pack \"invalid\""));
+}
+
+/// Test that the message shows the kind and context, and leaves the details to the source.
+#[rstest]
+fn test_display(assets: &Assets) {
+    let err = assets
+        .load_pack::<TarZstPack>("non_existent_pack")
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Pack not found (pack \"non_existent_pack\", backend tar, codec zstd)"
+    );
+
+    let source = std::error::Error::source(&err).unwrap().to_string();
+    assert!(source.contains("non_existent_pack"), "{source}");
+}
+
+/// Test that asset errors carry the entry path, and that the error is `'static`.
+#[rstest]
+fn test_entry_context(mut pack: TarZstPack) {
+    let err = pack
+        .load_asset::<String>("non_existent_file.txt")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert_eq!(err.path(), Some(Path::new("non_existent_file.txt")));
+
+    let boxed: Box<dyn std::error::Error + Send + Sync + 'static> = Box::new(err);
+    assert!(boxed.source().is_some());
+}
+
+/// Test that wrapped errors keep their kind through `io::Error`.
+#[test]
+fn test_io_roundtrip() {
+    let data = tar_archive(&[("big.bin", &[0; 16])]);
+    let options = LoadOptions {
+        limits: LoadLimits {
+            max_entry_size: 8,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let err = Error::from(<TarPack>::load_with(data.as_slice(), &options).unwrap_err());
+    assert_eq!(err.kind(), ErrorKind::Limit);
+    assert!(matches!(
+        err.downcast_ref(),
+        Some(LimitError::EntrySize { size: 16, .. })
+    ));
+
+    let err = io::Error::from(err.with_pack("mod"));
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(Error::from(err).pack(), Some("mod"));
+}
@@ -0,0 +1,148 @@
+mod common;
+use common::*;
+
+use ab_glyph::{Font as _, FontArc, FontVec};
+use alpacker::{
+    Error, Pack,
+    data::font::{FontError, face_path, load_font_vec, load_fontdue},
+    error::ErrorKind,
+    pack::TarPack,
+};
+use fontdue::{Font, FontSettings};
+use rstest::{fixture, rstest};
+use std::path::Path;
+
+/// Builds the `head`, `hhea` and `maxp` tables of a font with a single empty glyph.
+fn tables(units_per_em: u16) -> [(&'static [u8; 4], Vec<u8>); 3] {
+    let mut head = vec![0; 54];
+    head[..4].copy_from_slice(&0x0001_0000u32.to_be_bytes());
+    head[12..16].copy_from_slice(&0x5F0F_3CF5u32.to_be_bytes());
+    head[18..20].copy_from_slice(&units_per_em.to_be_bytes());
+
+    let mut hhea = vec![0; 36];
+    hhea[..4].copy_from_slice(&0x0001_0000u32.to_be_bytes());
+    hhea[34..36].copy_from_slice(&1u16.to_be_bytes());
+
+    let mut maxp = 0x0000_5000u32.to_be_bytes().to_vec();
+    maxp.extend(1u16.to_be_bytes());
+
+    [(b"head", head), (b"hhea", hhea), (b"maxp", maxp)]
+}
+
+/// Builds a minimal TrueType font, or a collection if there are several faces.
+fn font(units_per_em: &[u16]) -> Vec<u8> {
+    const TABLES: usize = 3;
+    let face_size = 12 + 16 * TABLES;
+    let header_size = match units_per_em.len() {
+        1 => 0,
+        faces => 12 + 4 * faces,
+    };
+
+    let mut data = Vec::new();
+    if header_size > 0 {
+        data.extend(b"ttcf");
+        data.extend(0x0001_0000u32.to_be_bytes());
+        data.extend((units_per_em.len() as u32).to_be_bytes());
+        for face in 0..units_per_em.len() {
+            data.extend(((header_size + face * face_size) as u32).to_be_bytes());
+        }
+    }
+
+    // Table data follows every table directory, at offsets from the start of the file
+    let mut offset = header_size + units_per_em.len() * face_size;
+    let mut contents = Vec::new();
+    for &units in units_per_em {
+        data.extend(0x0001_0000u32.to_be_bytes());
+        data.extend((TABLES as u16).to_be_bytes());
+        data.extend([0; 6]);
+        for (tag, table) in tables(units) {
+            data.extend(tag);
+            data.extend(0u32.to_be_bytes());
+            data.extend((offset as u32).to_be_bytes());
+            data.extend((table.len() as u32).to_be_bytes());
+            offset += table.len();
+            contents.extend(table);
+        }
+    }
+    data.extend(contents);
+    data
+}
+
+#[fixture]
+fn font_pack() -> TarPack {
+    let (single, collection) = (font(&[1000]), font(&[1000, 2048]));
+    pack_of(&[
+        ("fonts/ui.ttf", &single),
+        ("fonts/family.ttc", &collection),
+        ("fonts/broken.ttf", b"not a font"),
+    ])
+}
+
+/// Test that face indices are split off paths.
+#[test]
+fn test_face_path() {
+    let (path, index) = face_path(Path::new("fonts/family.ttc#2"));
+    assert_eq!((path, index), (Path::new("fonts/family.ttc"), Some(2)));
+
+    let (path, index) = face_path(Path::new("fonts/ui.ttf"));
+    assert_eq!((path, index), (Path::new("fonts/ui.ttf"), None));
+
+    // A `#` without a number is a part of the name
+    let (path, index) = face_path(Path::new("fonts/#1 font.ttf"));
+    assert_eq!((path, index), (Path::new("fonts/#1 font.ttf"), None));
+}
+
+/// Test loading `ab_glyph` fonts and selecting faces of collections.
+#[rstest]
+fn test_ab_glyph(mut font_pack: TarPack) -> TestResult {
+    let font = font_pack.get::<FontVec>("fonts/ui.ttf")?;
+    assert_eq!(font.units_per_em(), Some(1000.0));
+
+    let first = font_pack.get::<FontArc>("fonts/family.ttc")?;
+    let second = font_pack.get::<FontArc>("fonts/family.ttc#1")?;
+    assert_eq!(first.units_per_em(), Some(1000.0));
+    assert_eq!(second.units_per_em(), Some(2048.0));
+
+    let font = load_font_vec(&mut font_pack, "fonts/family.ttc", 1)?;
+    assert_eq!(font.units_per_em(), Some(2048.0));
+
+    Ok(())
+}
+
+/// Test loading `fontdue` fonts and selecting faces of collections.
+#[rstest]
+fn test_fontdue(mut font_pack: TarPack) -> TestResult {
+    let font = font_pack.get::<Font>("fonts/family.ttc#1")?;
+    assert_eq!(font.units_per_em(), 2048.0);
+
+    let settings = FontSettings {
+        collection_index: 1,
+        scale: 16.0,
+        ..FontSettings::default()
+    };
+    let font = load_fontdue(&mut font_pack, "fonts/family.ttc", settings)?;
+    assert_eq!(font.units_per_em(), 2048.0);
+
+    Ok(())
+}
+
+/// Test that invalid fonts and faces report the path and the index.
+#[rstest]
+fn test_errors(mut font_pack: TarPack) {
+    let err = font_pack.get::<FontVec>("fonts/family.ttc#5").unwrap_err();
+    assert!(matches!(err, FontError::Invalid { index: 5, .. }), "{err}");
+
+    let err = font_pack.get::<Font>("fonts/broken.ttf").unwrap_err();
+    assert!(matches!(err, FontError::Invalid { index: 0, .. }), "{err}");
+
+    let err: Error = font_pack
+        .load_asset::<Font>("fonts/broken.ttf")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Asset);
+    assert_eq!(err.path(), Some(Path::new("fonts/broken.ttf")));
+
+    let err: Error = font_pack
+        .load_asset::<FontVec>("fonts/missing.ttf#1")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
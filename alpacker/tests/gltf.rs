@@ -0,0 +1,168 @@
+mod common;
+use common::*;
+
+use alpacker::{
+    Error, Pack,
+    data::{
+        gltf::{EncodedImage, GltfError, Model},
+        image::ImageModel,
+    },
+    error::ErrorKind,
+    pack::TarPack,
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use image::{GenericImageView, ImageFormat, RgbaImage};
+use rstest::{fixture, rstest};
+use std::{io::Cursor, path::Path};
+
+/// Encodes a PNG image of the given size.
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut data = Cursor::new(Vec::new());
+    (RgbaImage::new(width, height).write_to(&mut data, ImageFormat::Png)).unwrap();
+    data.into_inner()
+}
+
+/// Positions of a triangle, as little-endian `f32`s.
+fn triangle() -> Vec<u8> {
+    let positions: [f32; 9] = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+    positions.iter().flat_map(|p| p.to_le_bytes()).collect()
+}
+
+/// Builds a glTF document with a triangle mesh in the first buffer.
+fn gltf(buffers: &str, images: &str, views: &str) -> String {
+    format!(
+        r#"{{
+  "asset": {{ "version": "2.0" }},
+  "buffers": [{buffers}],
+  "bufferViews": [{{ "buffer": 0, "byteLength": 36 }}{views}],
+  "accessors": [{{
+    "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+    "min": [0, 0, 0], "max": [1, 1, 0]
+  }}],
+  "meshes": [{{ "primitives": [{{ "attributes": {{ "POSITION": 0 }} }}] }}],
+  "images": [{images}]
+}}"#
+    )
+}
+
+/// Packs a document and a binary chunk into a GLB file.
+fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+    let chunk = |mut data: Vec<u8>, kind: &[u8; 4], pad: u8| {
+        data.resize(data.len().next_multiple_of(4), pad);
+        let mut chunk = (data.len() as u32).to_le_bytes().to_vec();
+        chunk.extend(kind);
+        chunk.extend(data);
+        chunk
+    };
+    let chunks = [
+        chunk(json.as_bytes().to_vec(), b"JSON", b' '),
+        chunk(bin.to_vec(), b"BIN\0", 0),
+    ]
+    .concat();
+
+    let mut data = b"glTF".to_vec();
+    data.extend(2u32.to_le_bytes());
+    data.extend((12 + chunks.len() as u32).to_le_bytes());
+    data.extend(chunks);
+    data
+}
+
+#[fixture]
+fn model_pack() -> TarPack {
+    let uri = format!("data:image/png;base64,{}", STANDARD.encode(png(1, 2)));
+    let ship = gltf(
+        r#"{ "uri": "ship%20data.bin", "byteLength": 36 }"#,
+        &format!(r#"{{ "uri": "../textures/hull.png" }}, {{ "uri": "{uri}" }}"#),
+        "",
+    );
+
+    let (icon, mut bin) = (png(3, 3), triangle());
+    let views = format!(
+        r#", {{ "buffer": 0, "byteOffset": 36, "byteLength": {} }}"#,
+        icon.len()
+    );
+    bin.extend(&icon);
+    let cube = glb(
+        &gltf(
+            &format!(r#"{{ "byteLength": {} }}"#, bin.len()),
+            r#"{ "bufferView": 1, "mimeType": "image/png" }"#,
+            &views,
+        ),
+        &bin,
+    );
+
+    let short = gltf(r#"{ "uri": "short.bin", "byteLength": 36 }"#, "", "");
+    let escaping = gltf(r#"{ "uri": "../../../x.bin", "byteLength": 36 }"#, "", "");
+    let missing = gltf(r#"{ "uri": "missing.bin", "byteLength": 36 }"#, "", "");
+
+    pack_of(&[
+        ("models/ship/ship.gltf", ship.as_bytes()),
+        ("models/ship/ship data.bin", &triangle()),
+        ("models/textures/hull.png", &png(4, 2)),
+        ("models/cube.glb", &cube),
+        ("models/short.gltf", short.as_bytes()),
+        ("models/short.bin", &[0; 8]),
+        ("models/escaping.gltf", escaping.as_bytes()),
+        ("models/missing.gltf", missing.as_bytes()),
+        ("models/broken.gltf", b"{ not json"),
+    ])
+}
+
+/// Test resolving external buffers and images relative to the model.
+#[rstest]
+fn test_gltf(mut model_pack: TarPack) -> TestResult {
+    let model = model_pack.get::<ImageModel>("models/ship/ship.gltf")?;
+    assert_eq!(model.buffers, vec![triangle()]);
+
+    let mesh = model.meshes().next().unwrap();
+    let primitive = mesh.primitives().next().unwrap();
+    let reader = primitive.reader(|buffer| model.buffer(buffer));
+    let positions = reader.read_positions().unwrap().collect::<Vec<_>>();
+    assert_eq!(positions[1], [1.0, 0.0, 0.0]);
+
+    // Images from the pack and from data URIs
+    let sizes = model.images.iter().map(|image| image.dimensions());
+    assert_eq!(sizes.collect::<Vec<_>>(), vec![(4, 2), (1, 2)]);
+
+    Ok(())
+}
+
+/// Test loading binary models with images in buffer views.
+#[rstest]
+fn test_glb(mut model_pack: TarPack) -> TestResult {
+    let model = model_pack.get::<Model>("models/cube.glb")?;
+    let image = model.images().next().unwrap();
+    let EncodedImage { data, mime_type } = model.image(&image).unwrap();
+    assert_eq!(data, &png(3, 3));
+    assert_eq!(mime_type.as_deref(), Some("image/png"));
+
+    let model = model_pack.get::<ImageModel>("models/cube.glb")?;
+    assert_eq!(model.images[0].dimensions(), (3, 3));
+    assert_eq!(&model.buffers[0][..36], triangle());
+
+    Ok(())
+}
+
+/// Test that invalid models and missing files report errors.
+#[rstest]
+fn test_errors(mut model_pack: TarPack) {
+    let err = model_pack.get::<Model>("models/short.gltf").unwrap_err();
+    assert!(matches!(err, GltfError::Buffer { index: 0, .. }), "{err}");
+
+    let err = model_pack.get::<Model>("models/escaping.gltf").unwrap_err();
+    assert!(matches!(err, GltfError::Path(_)), "{err}");
+
+    let err = model_pack.get::<Model>("models/broken.gltf").unwrap_err();
+    assert!(matches!(err, GltfError::Gltf { .. }), "{err}");
+
+    let err: Error = model_pack
+        .load_asset::<Model>("models/broken.gltf")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Asset);
+    assert_eq!(err.path(), Some(Path::new("models/broken.gltf")));
+
+    let err: Error = model_pack
+        .load_asset::<Model>("models/missing.gltf")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
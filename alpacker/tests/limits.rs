@@ -0,0 +1,119 @@
+mod common;
+use common::*;
+
+use std::io;
+
+use alpacker::{
+    LoadOptions, Pack,
+    limits::{LimitError, LoadLimits},
+    pack::{TarPack, TarZstPack},
+};
+
+fn options(limits: LoadLimits) -> LoadOptions {
+    LoadOptions {
+        limits,
+        ..Default::default()
+    }
+}
+
+/// Test that entry size, total size and entry count limits are enforced.
+#[test]
+fn test_size_limits() {
+    let data = tar_archive(&[("a.txt", b"0123456789"), ("b.txt", b"0123456789")]);
+
+    let limits = LoadLimits {
+        max_entry_size: 5,
+        ..Default::default()
+    };
+    let err = <TarPack>::load_with(data.as_slice(), &options(limits)).unwrap_err();
+    assert!(
+        matches!(
+            inner_error(&err),
+            Some(LimitError::EntrySize { size: 10, .. })
+        ),
+        "Expected the entry size limit to be exceeded, got {err}"
+    );
+
+    let limits = LoadLimits {
+        max_total_size: 15,
+        ..Default::default()
+    };
+    let err = <TarPack>::load_with(data.as_slice(), &options(limits)).unwrap_err();
+    assert_eq!(
+        inner_error(&err),
+        Some(&LimitError::TotalSize { limit: 15 })
+    );
+
+    let limits = LoadLimits {
+        max_entries: 1,
+        ..Default::default()
+    };
+    let err = <TarPack>::load_with(data.as_slice(), &options(limits)).unwrap_err();
+    assert_eq!(inner_error(&err), Some(&LimitError::Entries { limit: 1 }));
+
+    assert!(<TarPack>::load_with(data.as_slice(), &options(LoadLimits::UNLIMITED)).is_ok());
+}
+
+/// Test that a manifest with inconsistent or huge counts doesn't cause a panic or a huge allocation.
+#[test]
+fn test_hostile_manifest() -> TestResult {
+    let manifest = br#"{"entry_count":0,"file_count":18446744073709551615}"#;
+    let data = tar_archive(&[("manifest.json", manifest), ("a.txt", b"a")]);
+
+    let mut pack = <TarPack>::load(data.as_slice())?;
+    assert_eq!(pack.get::<String>("a.txt")?, "a");
+
+    // The manifest is read with the same size limit as other entries
+    let limits = LoadLimits {
+        max_entry_size: 16,
+        ..Default::default()
+    };
+    let err = <TarPack>::load_with(data.as_slice(), &options(limits)).unwrap_err();
+    assert!(
+        matches!(inner_error(&err), Some(LimitError::EntrySize { .. })),
+        "Expected the manifest to exceed the entry size limit, got {err}"
+    );
+
+    Ok(())
+}
+
+/// Test that sizes from untrusted headers and manifests aren't allocated up front without limits.
+#[test]
+fn test_hostile_sizes() -> TestResult {
+    let manifest = br#"{"entry_count":18446744073709551615,"file_count":18446744073709551615}"#;
+    let data = tar_archive(&[("manifest.json", manifest), ("a.txt", b"a")]);
+    let mut pack = <TarPack>::load_with(data.as_slice(), &options(LoadLimits::UNLIMITED))?;
+    assert_eq!(pack.get::<String>("a.txt")?, "a");
+
+    // A header claiming a terabyte with only a few bytes of data
+    let mut data = tar_archive(&[("big.bin", b"data")]);
+    let mut header = tar::Header::from_byte_slice(&data[..512]).clone();
+    header.set_size(1 << 40);
+    header.set_cksum();
+    data[..512].copy_from_slice(header.as_bytes());
+
+    let err = <TarPack>::load_with(data.as_slice(), &options(LoadLimits::UNLIMITED)).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+    Ok(())
+}
+
+/// Test that a codec stops decoding data that expands too much.
+#[test]
+fn test_decompression_ratio() -> TestResult {
+    let zeros = vec![0; 8 * 1024 * 1024];
+    let data = tar_archive(&[("zeros.bin", &zeros)]);
+    let data = zstd::encode_all(data.as_slice(), 19)?;
+
+    let limits = LoadLimits {
+        max_ratio: 100,
+        ..Default::default()
+    };
+    let err = TarZstPack::load_with(data.as_slice(), &options(limits)).unwrap_err();
+    assert_eq!(inner_error(&err), Some(&LimitError::Ratio { limit: 100 }));
+
+    let mut pack = TarZstPack::load_with(data.as_slice(), &options(LoadLimits::UNLIMITED))?;
+    assert_eq!(pack.get::<Vec<u8>>("zeros.bin")?, zeros);
+
+    Ok(())
+}
@@ -0,0 +1,125 @@
+mod common;
+use common::*;
+
+use std::path::PathBuf;
+
+use alpacker::{
+    LoadOptions, Pack,
+    pack::{Diagnostics, Skipped, TarPack},
+};
+
+/// Loads an archive with link resolution enabled.
+fn load_links(data: &[u8]) -> std::io::Result<TarPack> {
+    let options = LoadOptions {
+        resolve_links: true,
+        ..Default::default()
+    };
+    TarPack::load_with(data, &options)
+}
+
+/// Builds an archive from files and `(path, target, is_symlink)` links.
+///
+/// Link targets are written as they are, so escaping targets can be tested.
+fn archive_with_links(files: &[(&str, &[u8])], links: &[(&str, &str, bool)]) -> Vec<u8> {
+    let mut tar = tar::Builder::new(tar_archive(files));
+    // Drop the end-of-archive marker written by `tar_archive`.
+    let data = tar.get_mut();
+    data.truncate(data.len() - 1024);
+
+    for (path, target, is_symlink) in links {
+        let mut header = tar::Header::new_gnu();
+        header.as_old_mut().name[..path.len()].copy_from_slice(path.as_bytes());
+        header.as_old_mut().linkname[..target.len()].copy_from_slice(target.as_bytes());
+        header.set_entry_type(match is_symlink {
+            true => tar::EntryType::Symlink,
+            false => tar::EntryType::Link,
+        });
+        header.set_mode(0o644);
+        header.set_size(0);
+        header.set_cksum();
+        tar.append(&header, &[][..]).unwrap();
+    }
+
+    tar.into_inner().unwrap()
+}
+
+/// Test that links inside the archive resolve to their targets.
+#[test]
+fn test_resolve_links() -> TestResult {
+    let data = archive_with_links(
+        &[("textures/shared.png", b"texture")],
+        &[
+            ("textures/hero.png", "shared.png", true),
+            ("levels/1/wall.png", "../../textures/shared.png", true),
+            ("enemy.png", "textures/shared.png", false),
+            // A link to a link, declared before the target link
+            ("boss.png", "textures/alias.png", false),
+            ("textures/alias.png", "hero.png", true),
+        ],
+    );
+    let mut pack = load_links(&data)?;
+
+    for path in [
+        "textures/hero.png",
+        "levels/1/wall.png",
+        "enemy.png",
+        "boss.png",
+    ] {
+        assert_eq!(pack.get::<String>(path)?, "texture", "Link {path:?}");
+    }
+    assert!(pack.skipped().is_empty(), "{:?}", pack.skipped());
+
+    Ok(())
+}
+
+/// Test that links escaping the archive, missing targets and cycles are rejected.
+#[test]
+fn test_broken_links() -> TestResult {
+    let data = archive_with_links(
+        &[("file.txt", b"data")],
+        &[
+            ("escape.txt", "../../etc/passwd", true),
+            ("absolute.txt", "/etc/passwd", true),
+            ("missing.txt", "nothing.txt", true),
+            ("loop_a.txt", "loop_b.txt", true),
+            ("loop_b.txt", "loop_a.txt", true),
+        ],
+    );
+    let mut pack = load_links(&data)?;
+
+    assert_eq!(pack.get::<String>("file.txt")?, "data");
+    let broken = [
+        "escape.txt",
+        "absolute.txt",
+        "missing.txt",
+        "loop_a.txt",
+        "loop_b.txt",
+    ];
+    for path in broken {
+        assert!(!pack.exists(path), "Broken link {path:?} must be skipped");
+    }
+
+    // Broken links are reported in archive order
+    let reported = pack.skipped().iter().map(|skipped| match skipped {
+        Skipped::BrokenLink { path, .. } => path.clone(),
+        other => panic!("Unexpected skipped entry {other:?}"),
+    });
+    assert_eq!(
+        reported.collect::<Vec<_>>(),
+        broken.map(PathBuf::from).to_vec()
+    );
+
+    Ok(())
+}
+
+/// Test that links are skipped unless resolving them is enabled.
+#[test]
+fn test_links_disabled() -> TestResult {
+    let data = archive_with_links(&[("file.txt", b"data")], &[("link.txt", "file.txt", true)]);
+    let pack = <TarPack>::load(data.as_slice())?;
+
+    assert!(!pack.exists("link.txt"));
+    assert!(matches!(pack.skipped(), [Skipped::Symlink(_)]));
+
+    Ok(())
+}
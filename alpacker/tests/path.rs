@@ -0,0 +1,93 @@
+mod common;
+use common::*;
+
+use std::path::PathBuf;
+
+use alpacker::{
+    LoadOptions, Pack,
+    pack::{Diagnostics, TarPack, tar::Skipped},
+    path::{PathError, VirtualPath},
+};
+
+/// Test that paths are normalized and unsafe paths are rejected.
+#[test]
+fn test_normalize() {
+    let normalize = |path: &str| VirtualPath::new(path).map(|path| path.as_str().to_string());
+
+    assert_eq!(normalize("./myfile.txt").unwrap(), "myfile.txt");
+    assert_eq!(normalize("dir\\file.png").unwrap(), "dir/file.png");
+    assert_eq!(normalize("dir/../file.png").unwrap(), "file.png");
+    assert_eq!(normalize("a//b/./c/").unwrap(), "a/b/c");
+
+    assert!(matches!(normalize("../file"), Err(PathError::Traversal(_))));
+    assert!(matches!(
+        normalize("a/../../b"),
+        Err(PathError::Traversal(_))
+    ));
+    assert!(matches!(
+        normalize("/etc/passwd"),
+        Err(PathError::Absolute(_))
+    ));
+    assert!(matches!(normalize("C:\\file"), Err(PathError::Absolute(_))));
+    assert!(matches!(normalize("a/.."), Err(PathError::Empty(_))));
+}
+
+/// Test that lookups are normalized and unsafe archive entries are skipped.
+#[test]
+fn test_tar_lookup() -> TestResult {
+    let data = tar_archive(&[
+        ("dir/File.png", b"image"),
+        ("../evil.txt", b"evil"),
+        ("/abs.txt", b"abs"),
+    ]);
+    let mut pack = <TarPack>::load(data.as_slice())?;
+
+    for path in [
+        "dir/File.png",
+        "./dir/File.png",
+        "dir\\File.png",
+        "x/../dir/File.png",
+    ] {
+        assert!(pack.exists(path), "Expected {path:?} to exist");
+        assert_eq!(pack.get::<String>(path)?, "image");
+    }
+
+    assert!(
+        !pack.exists("dir/file.png"),
+        "Lookups are case-sensitive by default"
+    );
+    assert!(!pack.exists("../evil.txt"));
+    assert!(!pack.exists("evil.txt"));
+
+    let skipped = pack.skipped();
+    assert!(
+        skipped.contains(&Skipped::InvalidPath(
+            PathBuf::from("../evil.txt"),
+            PathError::Traversal(PathBuf::from("../evil.txt"))
+        )),
+        "Expected a traversing entry to be skipped"
+    );
+    assert_eq!(
+        skipped.len(),
+        2,
+        "Expected both unsafe entries to be skipped"
+    );
+
+    Ok(())
+}
+
+/// Test that case-insensitive lookups match paths in any case.
+#[test]
+fn test_case_insensitive() -> TestResult {
+    let data = tar_archive(&[("Textures/Hero.PNG", b"hero")]);
+    let options = LoadOptions {
+        case_insensitive: true,
+        ..Default::default()
+    };
+    let mut pack = <TarPack>::load_with(data.as_slice(), &options)?;
+
+    assert!(pack.exists("textures/hero.png"));
+    assert_eq!(pack.get::<String>("TEXTURES\\HERO.png")?, "hero");
+
+    Ok(())
+}
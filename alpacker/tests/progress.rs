@@ -0,0 +1,73 @@
+mod common;
+use common::*;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use alpacker::{
+    Assets, LoadOptions, Pack,
+    pack::{TarPack, TarZstPack},
+    progress::{LoadProgress, Progress, ProgressRead},
+};
+use rstest::rstest;
+
+/// Test that loading through [Assets] reports bytes and entries.
+#[rstest]
+fn test_load_progress(assets: &Assets) -> TestResult {
+    let progress = LoadProgress::new();
+    let options = LoadOptions {
+        progress: Some(progress.clone()),
+        ..Default::default()
+    };
+
+    assert_eq!(progress.fraction(), None);
+    assets.load_pack_with::<TarZstPack>("test", &options)?;
+
+    let size = fs::metadata(Path::new(SAMPLES_DIR).join("test.tar.zst"))?.len();
+    assert_eq!(progress.total_bytes(), size);
+    assert_eq!(progress.bytes_read(), size);
+    assert!(
+        progress.bytes_decoded() > size,
+        "Decoded bytes must be reported"
+    );
+    assert!(progress.entries() > 0);
+    assert_eq!(progress.fraction(), Some(1.0));
+
+    Ok(())
+}
+
+/// Records every entry reported to it.
+#[derive(Default)]
+struct Entries(Mutex<Vec<PathBuf>>);
+
+impl Progress for Entries {
+    fn entry(&self, path: &Path) {
+        self.0.lock().unwrap().push(path.to_path_buf());
+    }
+}
+
+/// Test a custom observer on a pack loaded from memory.
+#[test]
+fn test_custom_observer() -> TestResult {
+    let data = tar_archive(&[("a.txt", b"a"), ("b/c.txt", b"c")]);
+    let entries = Arc::new(Entries::default());
+    let counter = LoadProgress::new();
+    let options = LoadOptions {
+        progress: Some(entries.clone()),
+        ..Default::default()
+    };
+
+    let read = ProgressRead::new(data.as_slice(), counter.clone());
+    <TarPack>::load_with(read, &options)?;
+
+    assert_eq!(
+        *entries.0.lock().unwrap(),
+        [PathBuf::from("a.txt"), PathBuf::from("b/c.txt")]
+    );
+    assert!(counter.bytes_read() > 0);
+
+    Ok(())
+}
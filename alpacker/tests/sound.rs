@@ -0,0 +1,126 @@
+mod common;
+use common::*;
+
+use alpacker::{
+    Error, Pack,
+    data::{
+        kira::KiraError,
+        rodio::{BufferedSound, PackDecoder, RodioError},
+    },
+    error::ErrorKind,
+    pack::TarPack,
+};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use kira::sound::static_sound::StaticSoundData;
+use rodio::Source;
+use rstest::{fixture, rstest};
+use std::{io::Cursor, path::Path};
+
+/// Writes a 16-bit WAV file with hound.
+fn wav(channels: u16, sample_rate: u32, samples: &[i16]) -> Vec<u8> {
+    let spec = WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+
+    let mut data = Cursor::new(Vec::new());
+    let mut writer = WavWriter::new(&mut data, spec).unwrap();
+    for &sample in samples {
+        writer.write_sample(sample).unwrap();
+    }
+    writer.finalize().unwrap();
+    data.into_inner()
+}
+
+#[fixture]
+fn sound_pack() -> TarPack {
+    let (jump, theme) = (wav(1, 8000, &[0; 80]), wav(2, 22050, &[0; 200]));
+    let coin = flac(44100, &[&[1, 2, 3, 4]]);
+    pack_of(&[
+        ("sounds/jump.wav", &jump),
+        ("music/theme.wav", &theme),
+        ("sounds/coin.flac", &coin),
+        ("sounds/broken.wav", b"not a sound"),
+    ])
+}
+
+/// Test that in-memory entries are shared instead of copied.
+#[rstest]
+fn test_shared(mut sound_pack: TarPack) -> TestResult {
+    let first = sound_pack.get_shared(Path::new("sounds/jump.wav"))?;
+    let second = sound_pack.get_shared(Path::new("sounds/jump.wav"))?;
+    assert_eq!(first.as_ptr(), second.as_ptr());
+    assert_eq!(&*first, sound_pack.get::<Vec<u8>>("sounds/jump.wav")?);
+
+    Ok(())
+}
+
+/// Test loading `rodio` decoders, which outlive the pack.
+#[rstest]
+fn test_rodio(mut sound_pack: TarPack) -> TestResult {
+    let decoder = sound_pack.get::<PackDecoder>("sounds/jump.wav")?;
+    let sound = sound_pack.get::<BufferedSound>("music/theme.wav")?;
+    let coin = sound_pack.get::<PackDecoder>("sounds/coin.flac")?;
+    drop(sound_pack);
+
+    assert_eq!(
+        (decoder.sample_rate().get(), decoder.channels().get()),
+        (8000, 1)
+    );
+    assert_eq!(decoder.count(), 80);
+
+    assert_eq!(
+        (sound.sample_rate().get(), sound.channels().get()),
+        (22050, 2)
+    );
+    // Buffered sounds are decoded once and can be played several times
+    assert_eq!(sound.clone().count(), 200);
+    assert_eq!(sound.count(), 200);
+
+    // The `rodio` feature enables the codecs of rodio, which has none without its default features
+    assert_eq!((coin.sample_rate().get(), coin.count()), (44100, 4));
+
+    Ok(())
+}
+
+/// Test loading `kira` sounds.
+#[rstest]
+fn test_kira(mut sound_pack: TarPack) -> TestResult {
+    let sound = sound_pack.get::<StaticSoundData>("music/theme.wav")?;
+    assert_eq!(sound.sample_rate, 22050);
+    assert_eq!(sound.num_frames(), 100);
+
+    let sound = sound_pack.get::<StaticSoundData>("sounds/coin.flac")?;
+    assert_eq!((sound.sample_rate, sound.num_frames()), (44100, 4));
+
+    Ok(())
+}
+
+/// Test that invalid sounds report the path.
+#[rstest]
+fn test_errors(mut sound_pack: TarPack) {
+    // Decoders don't implement `Debug`, so `unwrap_err` can't be used
+    let Err(err) = sound_pack.get::<PackDecoder>("sounds/broken.wav") else {
+        panic!("decoded an invalid sound");
+    };
+    assert!(matches!(err, RodioError::Decoder { .. }), "{err}");
+
+    let err = sound_pack
+        .get::<StaticSoundData>("sounds/broken.wav")
+        .unwrap_err();
+    assert!(matches!(err, KiraError::Decode { .. }), "{err}");
+    assert_eq!(err.path(), Some(Path::new("sounds/broken.wav")));
+
+    let Err(err) = sound_pack.load_asset::<BufferedSound>("sounds/broken.wav") else {
+        panic!("decoded an invalid sound");
+    };
+    assert_eq!(err.kind(), ErrorKind::Asset);
+    assert_eq!(err.path(), Some(Path::new("sounds/broken.wav")));
+
+    let err: Error = sound_pack
+        .load_asset::<StaticSoundData>("sounds/missing.wav")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
@@ -0,0 +1,129 @@
+mod common;
+use common::*;
+
+use std::time::Duration;
+
+use alpacker::{
+    Pack,
+    data::{
+        aseprite::SpritesheetData,
+        sprite::{Animation, Direction, FrameTag, SpriteMeta},
+    },
+    pack::TarPack,
+};
+
+/// A horizontal strip of four 8x8 frames, with a tag for each direction.
+const SHEET: &str = r#"{
+    "frames": [
+        { "filename": "0", "frame": { "x": 0, "y": 0, "w": 8, "h": 8 }, "rotated": false, "trimmed": false,
+          "spriteSourceSize": { "x": 0, "y": 0, "w": 8, "h": 8 }, "sourceSize": { "w": 8, "h": 8 }, "duration": 100 },
+        { "filename": "1", "frame": { "x": 8, "y": 0, "w": 8, "h": 8 }, "rotated": false, "trimmed": false,
+          "spriteSourceSize": { "x": 0, "y": 0, "w": 8, "h": 8 }, "sourceSize": { "w": 8, "h": 8 }, "duration": 200 },
+        { "filename": "2", "frame": { "x": 16, "y": 0, "w": 8, "h": 8 }, "rotated": false, "trimmed": false,
+          "spriteSourceSize": { "x": 0, "y": 0, "w": 8, "h": 8 }, "sourceSize": { "w": 8, "h": 8 }, "duration": 100 },
+        { "filename": "3", "frame": { "x": 24, "y": 0, "w": 8, "h": 8 }, "rotated": false, "trimmed": false,
+          "spriteSourceSize": { "x": 0, "y": 0, "w": 8, "h": 8 }, "sourceSize": { "w": 8, "h": 8 }, "duration": 100 }
+    ],
+    "meta": {
+        "app": "https://www.aseprite.org/", "version": "1.3", "format": "RGBA8888",
+        "size": { "w": 32, "h": 8 }, "scale": "1", "image": "sheet.png",
+        "frameTags": [
+            { "name": "walk", "from": 0, "to": 2, "direction": "forward" },
+            { "name": "back", "from": 0, "to": 2, "direction": "reverse" },
+            { "name": "bounce", "from": 0, "to": 3, "direction": "pingpong" }
+        ]
+    }
+}"#;
+
+fn sheet() -> SpritesheetData {
+    serde_json::from_str(SHEET).unwrap()
+}
+
+fn indices(animation: &Animation) -> Vec<usize> {
+    animation.frames().iter().map(|frame| frame.index).collect()
+}
+
+/// Test that tags produce frame sequences in their direction.
+#[test]
+fn test_directions() {
+    let sheet = sheet();
+
+    let walk = Animation::from_tag(&sheet, "walk").unwrap();
+    assert_eq!(indices(&walk), [0, 1, 2]);
+    assert_eq!(walk.duration(), Duration::from_millis(400));
+
+    let back = Animation::from_tag(&sheet, "back").unwrap();
+    assert_eq!(indices(&back), [2, 1, 0]);
+
+    let bounce = Animation::from_tag(&sheet, "bounce").unwrap();
+    assert_eq!(indices(&bounce), [0, 1, 2, 3, 2, 1]);
+
+    let tag = FrameTag {
+        name: "custom".into(),
+        from: 1,
+        to: 3,
+        direction: Direction::PingPongReverse,
+    };
+    assert_eq!(indices(&Animation::new(&sheet, &tag)), [3, 2, 1, 2]);
+
+    assert!(Animation::from_tag(&sheet, "missing").is_none());
+    assert_eq!(indices(&Animation::all(&sheet)), [0, 1, 2, 3]);
+}
+
+/// Test that elapsed time selects frames by their durations.
+#[test]
+fn test_frame_at() {
+    let sheet = sheet();
+    let walk = Animation::from_tag(&sheet, "walk").unwrap();
+    let at = |ms| walk.frame_at(Duration::from_millis(ms)).unwrap().index;
+
+    assert_eq!(at(0), 0);
+    assert_eq!(at(99), 0);
+    assert_eq!(at(100), 1);
+    assert_eq!(at(299), 1);
+    assert_eq!(at(300), 2);
+    // The animation loops
+    assert_eq!(at(400), 0);
+    assert_eq!(at(1050), 1);
+
+    let once = walk.frame_at_once(Duration::from_millis(1150)).unwrap();
+    assert_eq!(once.index, 2);
+    assert!(walk.is_finished(Duration::from_millis(400)));
+
+    let rect = walk.frame_at(Duration::from_millis(350)).unwrap().rect;
+    assert_eq!((rect.x, rect.width), (16, 8));
+
+    assert!(Animation::default().frame_at(Duration::ZERO).is_none());
+}
+
+/// Test that frames are cut out of the sprite image.
+#[cfg(feature = "image")]
+#[test]
+fn test_frame_image() -> TestResult {
+    use alpacker::data::image::ImageSprite;
+    use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+    use std::io::Cursor;
+
+    // Each frame is filled with its index
+    let image = RgbaImage::from_fn(32, 8, |x, _| Rgba([(x / 8) as u8, 0, 0, 255]));
+    let mut png = Vec::new();
+    DynamicImage::ImageRgba8(image).write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+
+    let archive = tar_archive(&[
+        ("sprites/sheet.json", SHEET.as_bytes()),
+        ("sprites/sheet.png", &png),
+    ]);
+    let mut pack: TarPack = TarPack::load(archive.as_slice())?;
+    let sprite = pack.get::<ImageSprite>("sprites/sheet.json")?;
+
+    assert_eq!(sprite.meta.frame_count(), 4);
+    let frame = sprite.frame_image(2).unwrap();
+    assert_eq!((frame.width(), frame.height()), (8, 8));
+    assert_eq!(frame.to_rgba8().get_pixel(0, 0).0, [2, 0, 0, 255]);
+    assert!(sprite.frame_image(4).is_none());
+
+    let walk = sprite.animation("walk").unwrap();
+    assert_eq!(walk.frames().len(), 3);
+
+    Ok(())
+}
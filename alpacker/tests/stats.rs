@@ -0,0 +1,86 @@
+mod common;
+use common::*;
+
+use std::{fs, path::Path};
+
+use alpacker::{
+    Assets, Pack,
+    pack::{Diagnostics, TarPack, TarZstPack},
+    stats::Measured,
+};
+use rstest::rstest;
+
+/// Test that TAR packs report their entries and the memory they hold.
+#[test]
+fn test_tar_stats() -> TestResult {
+    let archive = tar_archive(&[("a.txt", b"Hello"), ("b/c.txt", b", World!")]);
+    let pack = TarPack::<std::hash::RandomState>::load(archive.as_slice())?;
+
+    let stats = pack.stats().unwrap();
+    assert_eq!(stats.entries, 2);
+    assert_eq!(stats.bytes_held, 13);
+    // The end-of-archive blocks may be left unread
+    let size = stats.uncompressed_size.unwrap();
+    assert!(size > 13 && size <= archive.len() as u64);
+    assert_eq!(stats.compressed_size, None);
+    assert!(stats.load_duration.is_some());
+
+    Ok(())
+}
+
+/// Test that encoded packs report the size of their input.
+#[rstest]
+fn test_encoded_stats(pack: TarZstPack) -> TestResult {
+    let stats = pack.stats().unwrap();
+    let size = fs::metadata(Path::new(SAMPLES_DIR).join("test.tar.zst"))?.len();
+
+    assert!(stats.entries > 0);
+    let compressed = stats.compressed_size.unwrap();
+    assert!(compressed > 0 && compressed <= size);
+    assert!(stats.compression_ratio().unwrap() > 1.0);
+
+    Ok(())
+}
+
+/// Test that [Measured] counts loads and failures per asset type.
+#[rstest]
+fn test_measured(assets: &Assets) -> TestResult {
+    let mut pack = assets.load_pack::<Measured<TarZstPack>>("test")?;
+    assert!(pack.stats().unwrap().load_duration.is_some());
+
+    pack.get::<String>("myfile.txt")?;
+    pack.get::<String>("myfile.txt")?;
+    assert!(pack.get::<String>("missing.txt").is_err());
+    pack.get::<Vec<u8>>("myfile.txt")?;
+
+    let strings = pack.asset_stats_of::<String>().unwrap();
+    assert_eq!(strings.loads, 3);
+    assert_eq!(strings.failures, 1);
+    assert!(strings.average_time().is_some());
+    assert_eq!(pack.asset_stats_of::<Vec<u8>>().unwrap().loads, 1);
+    assert_eq!(pack.asset_stats().len(), 2);
+
+    // Inherent methods of the inner pack are still available
+    assert!(pack.skipped().len() <= pack.stats().unwrap().entries + 1);
+
+    pack.reset_asset_stats();
+    assert!(pack.asset_stats().is_empty());
+
+    Ok(())
+}
+
+/// Test that cached packs are listed with their statistics.
+#[test]
+fn test_loaded_pack_stats() -> TestResult {
+    // A separate instance, so other tests don't share the cache
+    let assets = load_samples()?;
+    let pack = assets.shared_pack::<TarZstPack>("test")?;
+
+    let loaded = assets.loaded_packs();
+    assert_eq!(loaded.len(), 1);
+    let stats = loaded[0].stats.as_ref().unwrap();
+    assert_eq!(Some(stats), pack.lock().unwrap().stats().as_ref());
+    assert!(stats.bytes_held > 0);
+
+    Ok(())
+}
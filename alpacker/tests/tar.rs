@@ -1,7 +1,10 @@
 mod common;
 use common::*;
 
-use alpacker::{Assets, Pack, pack::TarZstPack};
+use alpacker::{
+    Assets, Pack,
+    pack::{Diagnostics, TarZstPack},
+};
 use rstest::rstest;
 
 /// Test that verifies the correct behavior of `load_pack()`.
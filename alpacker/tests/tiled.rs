@@ -0,0 +1,374 @@
+mod common;
+use common::*;
+
+use alpacker::{
+    Error, Pack,
+    data::{
+        sprite::Rect,
+        tiled::{
+            Chunk, FLIPPED_HORIZONTALLY, ImageRef, LayerKind, Map, Orientation, PropertyValue,
+            Shape, TileLayer, TiledError, TiledMap, Tileset,
+        },
+    },
+    error::ErrorKind,
+    pack::TarPack,
+};
+use base64::Engine;
+use flate2::{Compression, write::ZlibEncoder};
+use rstest::{fixture, rstest};
+use std::{io::Write, path::Path, time::Duration};
+
+const TERRAIN_TSX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" name="terrain" tilewidth="8" tileheight="8" spacing="1" margin="1" tilecount="4" columns="2">
+  <image source="../images/terrain.png" width="19" height="19"/>
+  <tile id="1" type="water">
+    <properties>
+      <property name="solid" type="bool" value="false"/>
+    </properties>
+    <animation>
+      <frame tileid="1" duration="100"/>
+      <frame tileid="2" duration="200"/>
+    </animation>
+  </tile>
+</tileset>
+"#;
+
+const TERRAIN_TSJ: &str = r#"{
+  "name": "terrain", "tilewidth": 8, "tileheight": 8, "spacing": 1, "margin": 1,
+  "tilecount": 4, "columns": 2,
+  "image": "../images/terrain.png", "imagewidth": 19, "imageheight": 19,
+  "tiles": [{
+    "id": 1, "type": "water",
+    "properties": [{ "name": "solid", "type": "bool", "value": false }],
+    "animation": [{ "tileid": 1, "duration": 100 }, { "tileid": 2, "duration": 200 }]
+  }]
+}"#;
+
+/// Tiles 1, 2, 0, 3 as Base64 of little-endian `u32`.
+const TILES_BASE64: &str = "AQAAAAIAAAAAAAAAAwAAAA==";
+
+fn level_tmx(zlib_tiles: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="2" tilewidth="8" tileheight="8" infinite="0">
+  <properties>
+    <property name="title" value="Intro"/>
+    <property name="spawn" type="class">
+      <properties>
+        <property name="x" type="int" value="3"/>
+      </properties>
+    </property>
+  </properties>
+  <tileset firstgid="1" source="../tilesets/terrain.tsx"/>
+  <tileset firstgid="5" name="props" tilewidth="16" tileheight="16" tilecount="1" columns="0">
+    <tile id="0">
+      <image source="props/chest.png" width="16" height="16"/>
+    </tile>
+  </tileset>
+  <layer id="1" name="ground" width="2" height="2">
+    <data encoding="csv">
+1,2,
+0,2147483651
+</data>
+  </layer>
+  <group id="2" name="details" opacity="0.5">
+    <layer id="3" name="base64" width="2" height="2">
+      <data encoding="base64">{TILES_BASE64}</data>
+    </layer>
+    <layer id="4" name="zlib" width="2" height="2" visible="0">
+      <data encoding="base64" compression="zlib">{zlib_tiles}</data>
+    </layer>
+  </group>
+  <objectgroup id="5" name="objects" offsetx="4" offsety="-2">
+    <object id="1" name="spawn" type="marker" x="8" y="4">
+      <point/>
+    </object>
+    <object id="2" name="pond" x="0" y="0">
+      <properties>
+        <property name="depth" type="float" value="1.5"/>
+      </properties>
+      <polygon points="0,0 8,0 4,6"/>
+    </object>
+    <object id="3" gid="5" x="0" y="16" width="16" height="16"/>
+  </objectgroup>
+  <imagelayer id="6" name="sky">
+    <image source="../images/sky.png"/>
+  </imagelayer>
+</map>
+"#
+    )
+}
+
+fn level_tmj(zlib_tiles: &str) -> String {
+    format!(
+        r#"{{
+  "type": "map", "orientation": "orthogonal", "width": 2, "height": 2,
+  "tilewidth": 8, "tileheight": 8, "infinite": false,
+  "properties": [
+    {{ "name": "title", "type": "string", "value": "Intro" }},
+    {{ "name": "spawn", "type": "class", "value": {{ "x": 3 }} }}
+  ],
+  "tilesets": [
+    {{ "firstgid": 1, "source": "../tilesets/terrain.tsj" }},
+    {{ "firstgid": 5, "name": "props", "tilewidth": 16, "tileheight": 16, "tilecount": 1, "columns": 0,
+      "tiles": [{{ "id": 0, "image": "props/chest.png", "imagewidth": 16, "imageheight": 16 }}] }}
+  ],
+  "layers": [
+    {{ "id": 1, "name": "ground", "type": "tilelayer", "width": 2, "height": 2,
+      "data": [1, 2, 0, 2147483651] }},
+    {{ "id": 2, "name": "details", "type": "group", "opacity": 0.5, "layers": [
+      {{ "id": 3, "name": "base64", "type": "tilelayer", "width": 2, "height": 2,
+        "encoding": "base64", "data": "{TILES_BASE64}" }},
+      {{ "id": 4, "name": "zlib", "type": "tilelayer", "width": 2, "height": 2, "visible": false,
+        "encoding": "base64", "compression": "zlib", "data": "{zlib_tiles}" }}
+    ] }},
+    {{ "id": 5, "name": "objects", "type": "objectgroup", "offsetx": 4, "offsety": -2, "objects": [
+      {{ "id": 1, "name": "spawn", "type": "marker", "x": 8, "y": 4, "point": true }},
+      {{ "id": 2, "name": "pond", "x": 0, "y": 0,
+        "properties": [{{ "name": "depth", "type": "float", "value": 1.5 }}],
+        "polygon": [{{ "x": 0, "y": 0 }}, {{ "x": 8, "y": 0 }}, {{ "x": 4, "y": 6 }}] }},
+      {{ "id": 3, "gid": 5, "x": 0, "y": 16, "width": 16, "height": 16 }}
+    ] }},
+    {{ "id": 6, "name": "sky", "type": "imagelayer", "image": "../images/sky.png" }}
+  ]
+}}"#
+    )
+}
+
+const INFINITE_TMX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map orientation="orthogonal" width="2" height="2" tilewidth="8" tileheight="8" infinite="1">
+  <layer id="1" name="ground" width="2" height="2">
+    <data encoding="csv">
+      <chunk x="-2" y="0" width="2" height="1">4,3</chunk>
+      <chunk x="0" y="0" width="2" height="1">1,2</chunk>
+    </data>
+  </layer>
+</map>
+"#;
+
+fn zlib_tiles() -> String {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    for gid in [4u32, 3, 2, 1] {
+        encoder.write_all(&gid.to_le_bytes()).unwrap();
+    }
+    base64::engine::general_purpose::STANDARD.encode(encoder.finish().unwrap())
+}
+
+#[fixture]
+fn tiled_pack() -> TarPack {
+    let (tmx, tmj) = (level_tmx(&zlib_tiles()), level_tmj(&zlib_tiles()));
+    pack_of(&[
+        ("maps/level.tmx", tmx.as_bytes()),
+        ("maps/level.tmj", tmj.as_bytes()),
+        ("maps/infinite.tmx", INFINITE_TMX.as_bytes()),
+        ("maps/broken.tmx", b"<map><layer></map>"),
+        (
+            "maps/missing-tileset.tmx",
+            b"<map><tileset firstgid=\"1\" source=\"none.tsx\"/></map>",
+        ),
+        ("maps/level.txt", b"level"),
+        (
+            "maps/huge.tmx",
+            b"<map><layer width=\"4294967295\" height=\"4294967295\">\
+              <data encoding=\"base64\">AAAAAA==</data></layer></map>",
+        ),
+        (
+            "maps/short.tmx",
+            b"<map><layer width=\"2\" height=\"2\">\
+              <data><tile gid=\"1\"/><tile gid=\"2\"/></data></layer></map>",
+        ),
+        ("tilesets/terrain.tsx", TERRAIN_TSX.as_bytes()),
+        ("tilesets/terrain.tsj", TERRAIN_TSJ.as_bytes()),
+        ("images/terrain.png", b"terrain"),
+        ("images/sky.png", b"sky"),
+        ("maps/props/chest.png", b"chest"),
+    ])
+}
+
+fn tiles(map: &Map, name: &str) -> Vec<u32> {
+    match &map.layer(name).unwrap().kind {
+        LayerKind::Tiles(layer) => layer.tiles.clone(),
+        kind => panic!("{name} isn't a tile layer: {kind:?}"),
+    }
+}
+
+/// Test that both formats load into the same map, with external tilesets and paths resolved.
+#[rstest]
+#[case("maps/level.tmx", "tilesets/terrain.tsx")]
+#[case("maps/level.tmj", "tilesets/terrain.tsj")]
+fn test_map(mut tiled_pack: TarPack, #[case] path: &str, #[case] tileset: &str) -> TestResult {
+    let map = tiled_pack.get::<Map>(path)?;
+
+    assert_eq!(map.orientation, Orientation::Orthogonal);
+    assert_eq!((map.width, map.height, map.tile_width), (2, 2, 8));
+    assert_eq!(
+        map.properties["title"],
+        PropertyValue::String("Intro".into())
+    );
+    let PropertyValue::Class(spawn) = &map.properties["spawn"] else {
+        panic!("spawn isn't a class");
+    };
+    assert_eq!(spawn["x"], PropertyValue::Int(3));
+
+    // External tileset
+    let terrain = &map.tilesets[0];
+    assert_eq!(terrain.source.as_deref(), Some(Path::new(tileset)));
+    assert_eq!((terrain.first_gid, terrain.name.as_str()), (1, "terrain"));
+    let image = terrain.image.as_ref().unwrap();
+    assert_eq!(image.source, Path::new("images/terrain.png"));
+    assert_eq!(
+        terrain.tile_rect(3),
+        Some(Rect {
+            x: 10,
+            y: 10,
+            width: 8,
+            height: 8
+        })
+    );
+
+    let water = terrain.tile(1).unwrap();
+    assert_eq!(water.class, "water");
+    assert_eq!(water.properties["solid"], PropertyValue::Bool(false));
+    assert_eq!(water.animation[1].duration, Duration::from_millis(200));
+
+    // Embedded image collection tileset
+    let props = &map.tilesets[1];
+    assert_eq!(props.source, None);
+    let chest = props.tile(0).unwrap().image.as_ref().unwrap();
+    assert_eq!(chest.source, Path::new("maps/props/chest.png"));
+
+    // Tile layers in every encoding
+    assert_eq!(tiles(&map, "ground"), [1, 2, 0, 3 | FLIPPED_HORIZONTALLY]);
+    assert_eq!(tiles(&map, "base64"), [1, 2, 0, 3]);
+    assert_eq!(tiles(&map, "zlib"), [4, 3, 2, 1]);
+    assert!(!map.layer("zlib").unwrap().visible);
+    assert_eq!(map.layer("details").unwrap().opacity, 0.5);
+
+    let (tileset, id) = map.tileset_of(3 | FLIPPED_HORIZONTALLY).unwrap();
+    assert_eq!((tileset.name.as_str(), id), ("terrain", 2));
+    assert_eq!(map.tileset_of(5).unwrap().0.name, "props");
+    assert!(map.tileset_of(0).is_none());
+
+    // Objects
+    let objects = map.layer("objects").unwrap();
+    assert_eq!(objects.offset, (4.0, -2.0));
+    let LayerKind::Objects(objects) = &objects.kind else {
+        panic!("objects isn't an object layer");
+    };
+    assert_eq!(objects.len(), 3);
+    assert_eq!(
+        (objects[0].class.as_str(), &objects[0].shape),
+        ("marker", &Shape::Point)
+    );
+    assert_eq!(
+        objects[1].shape,
+        Shape::Polygon(vec![(0.0, 0.0), (8.0, 0.0), (4.0, 6.0)])
+    );
+    assert_eq!(objects[1].properties["depth"], PropertyValue::Float(1.5));
+    assert_eq!(objects[2].gid, Some(5));
+
+    let LayerKind::Image(Some(ImageRef { source, .. })) = &map.layer("sky").unwrap().kind else {
+        panic!("sky isn't an image layer");
+    };
+    assert_eq!(source, Path::new("images/sky.png"));
+
+    assert_eq!(
+        map.image_paths(),
+        [
+            "images/terrain.png",
+            "maps/props/chest.png",
+            "images/sky.png"
+        ]
+        .map(Path::new)
+    );
+
+    Ok(())
+}
+
+/// Test that the images of a map are loaded from the same pack.
+#[rstest]
+fn test_tiled_map_images(mut tiled_pack: TarPack) -> TestResult {
+    let map = tiled_pack.get::<TiledMap<Vec<u8>>>("maps/level.tmx")?;
+
+    assert_eq!(map.images.len(), 3);
+    let terrain = map.tilesets[0].image.as_ref().unwrap();
+    assert_eq!(map.image(terrain).unwrap(), b"terrain");
+
+    let (image, rect) = map.tile_image(2).unwrap();
+    assert_eq!((image.as_slice(), rect.unwrap().x), (&b"terrain"[..], 10));
+    let (image, rect) = map.tile_image(5).unwrap();
+    assert_eq!((image.as_slice(), rect), (&b"chest"[..], None));
+
+    Ok(())
+}
+
+/// Test chunks of infinite maps.
+#[rstest]
+fn test_infinite_map(mut tiled_pack: TarPack) -> TestResult {
+    let map = tiled_pack.get::<Map>("maps/infinite.tmx")?;
+    assert!(map.infinite);
+
+    let LayerKind::Tiles(layer) = &map.layers[0].kind else {
+        panic!("ground isn't a tile layer");
+    };
+    assert_eq!(layer.chunks.len(), 2);
+    assert_eq!(layer.gid(-2, 0), Some(4));
+    assert_eq!(layer.gid(1, 0), Some(2));
+    assert_eq!(layer.gid(0, 1), None);
+
+    Ok(())
+}
+
+/// Test that errors name the file they occurred in.
+#[rstest]
+fn test_errors(mut tiled_pack: TarPack) {
+    let err = tiled_pack.get::<Map>("maps/broken.tmx").unwrap_err();
+    assert!(matches!(err, TiledError::Xml { .. }), "{err}");
+    assert_eq!(err.path(), Some(Path::new("maps/broken.tmx")));
+
+    let err: Error = tiled_pack.load_asset::<Map>("maps/broken.tmx").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Asset);
+    assert_eq!(err.path(), Some(Path::new("maps/broken.tmx")));
+
+    let err = tiled_pack.get::<Map>("maps/short.tmx").unwrap_err();
+    assert!(matches!(err, TiledError::Invalid { .. }), "{err}");
+    assert!(err.to_string().contains("2 tiles, expected 4"), "{err}");
+
+    let err = tiled_pack.get::<Map>("maps/level.txt").unwrap_err();
+    assert!(matches!(err, TiledError::UnknownFormat(_)), "{err}");
+
+    let err: Error = tiled_pack
+        .load_asset::<Map>("maps/missing-tileset.tmx")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+/// Test that huge sizes and positions from the file don't overflow.
+#[rstest]
+fn test_huge_values(mut tiled_pack: TarPack) {
+    let err = tiled_pack.get::<Map>("maps/huge.tmx").unwrap_err();
+    assert!(matches!(err, TiledError::Invalid { .. }), "{err}");
+
+    let tileset = Tileset {
+        tile_width: u32::MAX,
+        tile_height: 8,
+        spacing: 1,
+        tile_count: 4,
+        columns: 2,
+        ..Default::default()
+    };
+    assert_eq!(tileset.tile_rect(0), None);
+
+    let layer = TileLayer {
+        chunks: vec![Chunk {
+            x: i32::MIN,
+            y: 0,
+            width: u32::MAX,
+            height: u32::MAX,
+            tiles: vec![1],
+        }],
+        ..Default::default()
+    };
+    assert_eq!(layer.gid(i32::MIN, 0), Some(1));
+    assert_eq!(layer.gid(i32::MAX, i32::MAX), None);
+}
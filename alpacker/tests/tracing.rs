@@ -0,0 +1,66 @@
+mod common;
+use common::*;
+
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicU64, Ordering},
+};
+
+use alpacker::{Pack, pack::TarZstPack};
+use tracing::{
+    Event, Metadata, Subscriber,
+    span::{Attributes, Id, Record},
+};
+
+/// A subscriber that records the names of created spans.
+#[derive(Default)]
+struct Spans {
+    names: Arc<Mutex<Vec<&'static str>>>,
+    next_id: AtomicU64,
+}
+
+impl Subscriber for Spans {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        self.names.lock().unwrap().push(span.metadata().name());
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+/// Test that loading packs and assets emits spans.
+#[test]
+fn test_spans() -> TestResult {
+    let subscriber = Spans::default();
+    let names = subscriber.names.clone();
+
+    tracing::subscriber::with_default(subscriber, || -> TestResult {
+        let assets = load_samples()?;
+        let mut pack = assets.load_pack::<TarZstPack>("test")?;
+        pack.get::<String>("myfile.txt")?;
+        Ok(())
+    })?;
+
+    #[cfg(not(feature = "sign"))]
+    let load_from_dir = "load_from_dir";
+    #[cfg(feature = "sign")]
+    let load_from_dir = "load_from_dir_signed";
+    assert_eq!(
+        *names.lock().unwrap(),
+        [load_from_dir, "load_pack", "decode", "load_tar", "get"]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use alpacker::{Assets, JsonIoError, PackManifest, version::Versioned};
+use serde_json::json;
+
+/// Test that documents without a version are migrated to the latest format.
+#[test]
+fn test_migrate_legacy() {
+    let manifest = PackManifest::from_json(json!({ "entry_count": 2, "file_count": 1 }))
+        .expect("Expected a legacy pack manifest to be migrated");
+    assert_eq!(manifest.format_version, PackManifest::FORMAT_VERSION);
+    assert_eq!(manifest.file_count, 1);
+
+    let assets = json!({ "packs_dir": "./", "packs": { "test": "test.tar.zst" } });
+    assert!(
+        Assets::from_json(assets).is_ok(),
+        "Expected a legacy assets manifest to be migrated"
+    );
+}
+
+/// Test that unknown fields are ignored and newer versions are rejected.
+#[test]
+fn test_newer_version() {
+    let manifest = json!({
+        "format_version": PackManifest::FORMAT_VERSION,
+        "entry_count": 0,
+        "file_count": 0,
+        "unknown_field": true,
+    });
+    assert!(
+        PackManifest::from_json(manifest).is_ok(),
+        "Expected unknown fields to be ignored"
+    );
+
+    let manifest = json!({
+        "format_version": PackManifest::FORMAT_VERSION + 1,
+        "entry_count": 0,
+        "file_count": 0,
+    });
+    assert!(
+        matches!(
+            PackManifest::from_json(manifest),
+            Err(JsonIoError::Version(err)) if err.found == PackManifest::FORMAT_VERSION as u64 + 1
+        ),
+        "Expected a newer manifest to be rejected"
+    );
+}
+
+/// Test that a version which isn't an unsigned integer is rejected instead of read as 0.
+#[test]
+fn test_invalid_version() {
+    for version in [json!("1"), json!(-1), json!(1.5), json!(null)] {
+        let manifest = json!({ "format_version": version, "entry_count": 0, "file_count": 0 });
+        assert!(
+            matches!(PackManifest::from_json(manifest), Err(JsonIoError::Json(_))),
+            "Expected format version {version} to be rejected"
+        );
+    }
+}